@@ -0,0 +1,30 @@
+-- Opaque refresh tokens with rotation.
+-- Only a SHA-256 hash of the token is stored. Every refresh marks the presented token
+-- as used and issues a new one in the same family; presenting a used token again
+-- revokes the whole family (reuse detection).
+
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+  id uuid PRIMARY KEY DEFAULT uuid_generate_v4 (),
+
+  user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+
+  -- All tokens descending from one login share a family id
+  family_id uuid NOT NULL,
+
+  -- hex(sha256(token))
+  token_hash text NOT NULL,
+
+  -- Free-form label sent by the client (e.g. "iPhone 15")
+  device_label text,
+
+  expires_at timestamptz NOT NULL,
+  used_at timestamptz,
+  revoked_at timestamptz,
+
+  created_at timestamptz NOT NULL DEFAULT now (),
+
+  CONSTRAINT refresh_tokens_token_hash_unique UNIQUE (token_hash)
+);
+
+CREATE INDEX IF NOT EXISTS refresh_tokens_user_id_idx ON refresh_tokens (user_id);
+CREATE INDEX IF NOT EXISTS refresh_tokens_family_id_idx ON refresh_tokens (family_id);
@@ -0,0 +1,17 @@
+-- Server-side access token revocation.
+
+-- Access tokens issued at or before this instant are rejected (logout everywhere).
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS tokens_valid_after timestamptz;
+
+-- Individually revoked access tokens, by JWT id (jti).
+-- Rows are only needed until the token would have expired anyway.
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+  jti uuid PRIMARY KEY,
+  user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  expires_at timestamptz NOT NULL,
+  revoked_at timestamptz NOT NULL DEFAULT now ()
+);
+
+CREATE INDEX IF NOT EXISTS revoked_tokens_user_id_idx ON revoked_tokens (user_id);
+CREATE INDEX IF NOT EXISTS revoked_tokens_expires_at_idx ON revoked_tokens (expires_at);
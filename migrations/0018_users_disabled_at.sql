@@ -0,0 +1,4 @@
+-- Account disable switch. A disabled user cannot log in, refresh or use existing tokens.
+
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS disabled_at timestamptz;
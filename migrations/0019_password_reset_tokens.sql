@@ -0,0 +1,19 @@
+-- Single-use, expiring password reset tokens.
+-- Only hex(sha256(token)) is stored; the raw token only travels in the reset email.
+
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+  id uuid PRIMARY KEY DEFAULT uuid_generate_v4 (),
+
+  user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+
+  token_hash text NOT NULL,
+
+  expires_at timestamptz NOT NULL,
+  used_at timestamptz,
+
+  created_at timestamptz NOT NULL DEFAULT now (),
+
+  CONSTRAINT password_reset_tokens_token_hash_unique UNIQUE (token_hash)
+);
+
+CREATE INDEX IF NOT EXISTS password_reset_tokens_user_id_idx ON password_reset_tokens (user_id);
@@ -0,0 +1,25 @@
+-- Email verification for new signups.
+
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS email_verified_at timestamptz;
+
+-- Single-use verification tokens. The address being verified is stored with the token,
+-- so a token cannot verify an email the user has since changed.
+CREATE TABLE IF NOT EXISTS email_verification_tokens (
+  id uuid PRIMARY KEY DEFAULT uuid_generate_v4 (),
+
+  user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  email text NOT NULL,
+
+  -- hex(sha256(token))
+  token_hash text NOT NULL,
+
+  expires_at timestamptz NOT NULL,
+  used_at timestamptz,
+
+  created_at timestamptz NOT NULL DEFAULT now (),
+
+  CONSTRAINT email_verification_tokens_token_hash_unique UNIQUE (token_hash)
+);
+
+CREATE INDEX IF NOT EXISTS email_verification_tokens_user_id_idx ON email_verification_tokens (user_id);
@@ -0,0 +1,34 @@
+-- TOTP two-factor authentication.
+
+-- Set once a TOTP secret has been confirmed; login then requires a second factor.
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS mfa_enabled_at timestamptz;
+
+-- One TOTP secret per user. A row with confirmed_at IS NULL is a pending enrollment.
+CREATE TABLE IF NOT EXISTS user_totp (
+  user_id uuid PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+
+  -- base32 secret (kept in clear: it is needed to compute codes)
+  secret text NOT NULL,
+  confirmed_at timestamptz,
+
+  -- Last accepted time step; codes for this step or earlier are rejected (no replay)
+  last_used_step bigint,
+
+  created_at timestamptz NOT NULL DEFAULT now ()
+);
+
+-- Single-use recovery codes.
+CREATE TABLE IF NOT EXISTS mfa_recovery_codes (
+  id uuid PRIMARY KEY DEFAULT uuid_generate_v4 (),
+
+  user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+
+  -- hex(sha256(normalized code))
+  code_hash text NOT NULL,
+  used_at timestamptz,
+
+  created_at timestamptz NOT NULL DEFAULT now ()
+);
+
+CREATE INDEX IF NOT EXISTS mfa_recovery_codes_user_id_idx ON mfa_recovery_codes (user_id);
@@ -0,0 +1,52 @@
+-- Brute-force protection for login and the second factor.
+
+-- Failed attempts per key: 'account:<lowercased email>' or 'ip:<address>'.
+-- Attempts are refused until blocked_until (exponential backoff, then lockout).
+CREATE TABLE IF NOT EXISTS login_throttle (
+  key text PRIMARY KEY,
+
+  failures integer NOT NULL DEFAULT 0,
+  last_failure_at timestamptz NOT NULL DEFAULT now (),
+  blocked_until timestamptz
+);
+
+-- Audit record of every lockout.
+CREATE TABLE IF NOT EXISTS auth_lockouts (
+  id uuid PRIMARY KEY DEFAULT uuid_generate_v4 (),
+
+  scope text NOT NULL,
+  -- Lowercased email (account scope) or IP address (ip scope)
+  subject text NOT NULL,
+  -- Set for account lockouts of existing users
+  user_id uuid REFERENCES users (id) ON DELETE SET NULL,
+  ip text,
+
+  failures integer NOT NULL,
+  locked_until timestamptz NOT NULL,
+  unlocked_at timestamptz,
+
+  created_at timestamptz NOT NULL DEFAULT now (),
+
+  CONSTRAINT auth_lockouts_scope_chk CHECK (scope IN ('account', 'ip'))
+);
+
+CREATE INDEX IF NOT EXISTS auth_lockouts_user_id_idx ON auth_lockouts (user_id);
+
+-- Single-use tokens from the "unlock your account" email.
+CREATE TABLE IF NOT EXISTS account_unlock_tokens (
+  id uuid PRIMARY KEY DEFAULT uuid_generate_v4 (),
+
+  user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+
+  -- hex(sha256(token))
+  token_hash text NOT NULL,
+
+  expires_at timestamptz NOT NULL,
+  used_at timestamptz,
+
+  created_at timestamptz NOT NULL DEFAULT now (),
+
+  CONSTRAINT account_unlock_tokens_token_hash_unique UNIQUE (token_hash)
+);
+
+CREATE INDEX IF NOT EXISTS account_unlock_tokens_user_id_idx ON account_unlock_tokens (user_id);
@@ -0,0 +1,9 @@
+-- Token buckets for the Postgres rate limit backend (RATE_LIMIT_BACKEND=postgres).
+-- UNLOGGED: losing buckets on a crash only resets the limits.
+CREATE UNLOGGED TABLE IF NOT EXISTS rate_limit_buckets (
+  -- '<route group>:ip:<address>' or '<route group>:user:<uuid>'
+  key text PRIMARY KEY,
+
+  tokens double precision NOT NULL,
+  updated_at timestamptz NOT NULL DEFAULT now ()
+);
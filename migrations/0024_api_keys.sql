@@ -0,0 +1,26 @@
+-- Personal API keys for organizer integrations (`Authorization: ApiKey <key>`).
+CREATE TABLE IF NOT EXISTS api_keys (
+  id uuid PRIMARY KEY DEFAULT uuid_generate_v4 (),
+
+  user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  name text NOT NULL,
+
+  -- Public, non-secret start of the key (e.g. 'nxl_k3J9aQ2b'), to recognise it in listings
+  prefix text NOT NULL,
+  -- hex(sha256(full key))
+  key_hash text NOT NULL,
+
+  -- 'events:read', 'sales:read', 'tickets:scan'
+  scopes text[] NOT NULL,
+
+  last_used_at timestamptz,
+  expires_at timestamptz,
+  revoked_at timestamptz,
+
+  created_at timestamptz NOT NULL DEFAULT now (),
+
+  CONSTRAINT api_keys_key_hash_unique UNIQUE (key_hash),
+  CONSTRAINT api_keys_scopes_not_empty_chk CHECK (cardinality(scopes) > 0)
+);
+
+CREATE INDEX IF NOT EXISTS api_keys_user_id_idx ON api_keys (user_id);
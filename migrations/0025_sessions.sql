@@ -0,0 +1,39 @@
+-- Login sessions, one per refresh token family (sessions.id = refresh_tokens.family_id).
+
+CREATE TABLE IF NOT EXISTS sessions (
+  id uuid PRIMARY KEY DEFAULT uuid_generate_v4 (),
+
+  user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+
+  -- Label sent by the client at login (e.g. "iPhone 15") and the User-Agent header
+  device_label text,
+  user_agent text,
+  -- Client IP at login, then at the latest refresh
+  ip text,
+
+  created_at timestamptz NOT NULL DEFAULT now (),
+  -- Updated on every token refresh
+  last_seen_at timestamptz NOT NULL DEFAULT now (),
+  revoked_at timestamptz
+);
+
+CREATE INDEX IF NOT EXISTS sessions_user_id_idx ON sessions (user_id);
+
+-- Families issued before this migration become sessions too.
+INSERT INTO sessions (id, user_id, device_label, created_at, last_seen_at, revoked_at)
+SELECT
+  family_id,
+  (array_agg(user_id))[1],
+  max(device_label),
+  min(created_at),
+  max(created_at),
+  CASE WHEN bool_and(revoked_at IS NOT NULL) THEN max(revoked_at) END
+FROM refresh_tokens
+GROUP BY family_id
+ON CONFLICT (id) DO NOTHING;
+
+ALTER TABLE refresh_tokens
+  DROP CONSTRAINT IF EXISTS refresh_tokens_family_id_fkey;
+
+ALTER TABLE refresh_tokens
+  ADD CONSTRAINT refresh_tokens_family_id_fkey FOREIGN KEY (family_id) REFERENCES sessions (id) ON DELETE CASCADE;
@@ -0,0 +1,8 @@
+-- Last change to the user's profile (users, role data or address), set by `PATCH /users/me`.
+
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS updated_at timestamptz NOT NULL DEFAULT now ();
+
+UPDATE users
+SET updated_at = created_at
+WHERE updated_at > created_at;
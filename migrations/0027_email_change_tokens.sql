@@ -0,0 +1,22 @@
+-- Pending email address changes, confirmed from a link sent to the new address.
+-- The current address is stored too: the change is dropped if it no longer matches.
+
+CREATE TABLE IF NOT EXISTS email_change_tokens (
+  id uuid PRIMARY KEY DEFAULT uuid_generate_v4 (),
+
+  user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  old_email text NOT NULL,
+  new_email text NOT NULL,
+
+  -- hex(sha256(token))
+  token_hash text NOT NULL,
+
+  expires_at timestamptz NOT NULL,
+  used_at timestamptz,
+
+  created_at timestamptz NOT NULL DEFAULT now (),
+
+  CONSTRAINT email_change_tokens_token_hash_unique UNIQUE (token_hash)
+);
+
+CREATE INDEX IF NOT EXISTS email_change_tokens_user_id_idx ON email_change_tokens (user_id);
@@ -0,0 +1,17 @@
+-- Store gov_identification as text again: as bigint, CPFs and CNPJs starting with 0 lost
+-- their leading zeros (and were then rejected by the range check). Values are digits
+-- only; formatting and check digits are handled by the app (`GovId`).
+
+ALTER TABLE users
+  DROP CONSTRAINT IF EXISTS users_gov_identification_digits_chk;
+
+ALTER TABLE users
+  ALTER COLUMN gov_identification TYPE text
+  USING gov_identification::text;
+
+ALTER TABLE users
+  ADD CONSTRAINT users_gov_identification_digits_chk
+  CHECK (
+    gov_identification IS NULL
+    OR gov_identification ~ '^([0-9]{11}|[0-9]{14})$'
+  );
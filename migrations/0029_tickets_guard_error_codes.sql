@@ -0,0 +1,50 @@
+-- Give every error raised by tickets_before_insert_guard a SQLSTATE and a constraint
+-- name, so the API can tell them apart without parsing messages. Messages are unchanged;
+-- the sold-out check now runs before the sellable one.
+
+CREATE OR REPLACE FUNCTION tickets_before_insert_guard()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+DECLARE
+  lot_event_id uuid;
+  sold int;
+  max_tickets int;
+BEGIN
+  IF NEW.lot_id IS NULL THEN
+    RAISE EXCEPTION 'lot_id is required'
+      USING ERRCODE = 'not_null_violation', CONSTRAINT = 'tickets_guard_lot_required';
+  END IF;
+
+  SELECT event_id INTO lot_event_id
+  FROM ticket_lots
+  WHERE id = NEW.lot_id;
+
+  IF lot_event_id IS NULL THEN
+    RAISE EXCEPTION 'invalid lot_id %', NEW.lot_id
+      USING ERRCODE = 'foreign_key_violation', CONSTRAINT = 'tickets_guard_lot_exists';
+  END IF;
+
+  IF lot_event_id <> NEW.event_id THEN
+    RAISE EXCEPTION 'ticket event_id % does not match lot event_id %', NEW.event_id, lot_event_id
+      USING ERRCODE = 'check_violation', CONSTRAINT = 'tickets_guard_lot_event_match';
+  END IF;
+
+  -- Sold out first: a sold-out lot is also not sellable, and "sold out" says more.
+  SELECT s.sold, s.max_tickets INTO sold, max_tickets
+  FROM ticket_lot_sales s
+  WHERE s.lot_id = NEW.lot_id;
+
+  IF sold >= max_tickets THEN
+    RAISE EXCEPTION 'ticket lot % sold out (% / %)', NEW.lot_id, sold, max_tickets
+      USING ERRCODE = 'check_violation', CONSTRAINT = 'tickets_guard_lot_not_sold_out';
+  END IF;
+
+  IF NOT ticket_lot_is_sellable(NEW.lot_id) THEN
+    RAISE EXCEPTION 'ticket lot % is not sellable', NEW.lot_id
+      USING ERRCODE = 'check_violation', CONSTRAINT = 'tickets_guard_lot_sellable';
+  END IF;
+
+  RETURN NEW;
+END;
+$$;
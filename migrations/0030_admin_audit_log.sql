@@ -0,0 +1,21 @@
+-- Actions taken by admins on user accounts (role changes, disables, forced password
+-- resets, impersonation). Rows outlive the accounts involved.
+
+CREATE TABLE IF NOT EXISTS admin_audit_log (
+  id uuid PRIMARY KEY DEFAULT uuid_generate_v4 (),
+
+  admin_user_id uuid REFERENCES users (id) ON DELETE SET NULL,
+  target_user_id uuid REFERENCES users (id) ON DELETE SET NULL,
+
+  -- e.g. 'change_role', 'disable', 'impersonate'
+  action text NOT NULL,
+  -- Action specific (e.g. previous and new role)
+  details jsonb NOT NULL DEFAULT '{}'::jsonb,
+  -- Admin's client IP
+  ip text,
+
+  created_at timestamptz NOT NULL DEFAULT now ()
+);
+
+CREATE INDEX IF NOT EXISTS admin_audit_log_target_user_id_idx ON admin_audit_log (target_user_id, created_at);
+CREATE INDEX IF NOT EXISTS admin_audit_log_admin_user_id_idx ON admin_audit_log (admin_user_id, created_at);
@@ -0,0 +1,23 @@
+-- Personal data exports (LGPD access requests) built in the background for large
+-- accounts. The finished file is kept here until it expires.
+
+CREATE TABLE IF NOT EXISTS data_exports (
+  id uuid PRIMARY KEY DEFAULT uuid_generate_v4 (),
+
+  user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+
+  format text NOT NULL,
+  status text NOT NULL DEFAULT 'pending',
+  -- JSON document or zip archive, once ready
+  content bytea,
+
+  created_at timestamptz NOT NULL DEFAULT now (),
+  completed_at timestamptz,
+  expires_at timestamptz NOT NULL,
+
+  CONSTRAINT data_exports_format_chk CHECK (format IN ('json', 'zip')),
+  CONSTRAINT data_exports_status_chk CHECK (status IN ('pending', 'ready', 'failed'))
+);
+
+CREATE INDEX IF NOT EXISTS data_exports_user_id_idx ON data_exports (user_id);
+CREATE INDEX IF NOT EXISTS data_exports_expires_at_idx ON data_exports (expires_at);
@@ -0,0 +1,18 @@
+-- Account deletion requests (LGPD right to erasure). Users cannot be deleted (their
+-- tickets and events reference them with ON DELETE RESTRICT), so once the grace period
+-- ends their personal data is anonymized in place instead.
+
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS deletion_requested_at timestamptz;
+
+-- End of the grace period; the request can be cancelled until then
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS deletion_scheduled_for timestamptz;
+
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS anonymized_at timestamptz;
+
+CREATE INDEX IF NOT EXISTS users_deletion_scheduled_for_idx ON users (deletion_scheduled_for)
+WHERE
+  deletion_scheduled_for IS NOT NULL
+  AND anonymized_at IS NULL;
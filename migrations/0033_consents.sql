@@ -0,0 +1,52 @@
+-- Versioned legal documents (terms of service, privacy policy) and the consent ledger.
+-- A document version is current from its published_at until a newer one is published.
+
+CREATE TABLE IF NOT EXISTS legal_documents (
+  id uuid PRIMARY KEY DEFAULT uuid_generate_v4 (),
+
+  kind text NOT NULL,
+  -- Free-form label shown to users and sent back on acceptance (e.g. '2026-03')
+  version text NOT NULL,
+  title text NOT NULL,
+  -- Where the full text is published
+  url text,
+
+  published_at timestamptz NOT NULL DEFAULT now (),
+  created_at timestamptz NOT NULL DEFAULT now (),
+
+  CONSTRAINT legal_documents_kind_chk CHECK (kind IN ('terms', 'privacy')),
+  CONSTRAINT legal_documents_kind_version_unique UNIQUE (kind, version)
+);
+
+CREATE INDEX IF NOT EXISTS legal_documents_kind_published_at_idx ON legal_documents (kind, published_at);
+
+-- Initial versions, so signups can accept something until real documents are published.
+INSERT INTO legal_documents (kind, version, title)
+VALUES
+  ('terms', '1', 'Termos de Uso'),
+  ('privacy', '1', 'Política de Privacidade')
+ON CONFLICT (kind, version) DO NOTHING;
+
+-- Append-only: every acceptance, opt-in and opt-out is a new row. The latest marketing
+-- row is the user's current choice.
+CREATE TABLE IF NOT EXISTS consent_records (
+  id uuid PRIMARY KEY DEFAULT uuid_generate_v4 (),
+
+  user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+
+  kind text NOT NULL,
+  -- Accepted document version (terms and privacy only)
+  document_id uuid REFERENCES legal_documents (id) ON DELETE RESTRICT,
+  granted boolean NOT NULL,
+
+  -- Client at the time of the decision
+  ip text,
+  user_agent text,
+
+  accepted_at timestamptz NOT NULL DEFAULT now (),
+
+  CONSTRAINT consent_records_kind_chk CHECK (kind IN ('terms', 'privacy', 'marketing')),
+  CONSTRAINT consent_records_document_chk CHECK ((kind = 'marketing') = (document_id IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS consent_records_user_id_idx ON consent_records (user_id, kind, accepted_at);
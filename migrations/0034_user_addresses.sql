@@ -0,0 +1,54 @@
+-- Several labelled addresses per user, one of them the default, with CEPs stored as
+-- 8 digits only (formatting is handled by the app, `Cep`). Plus a local CEP directory,
+-- seeded from the Correios dataset, to prefill and check addresses.
+
+ALTER TABLE user_address
+  DROP CONSTRAINT IF EXISTS user_address_user_id_key;
+
+ALTER TABLE user_address
+  DROP CONSTRAINT IF EXISTS user_address_cep_check;
+
+UPDATE user_address
+SET cep = replace(cep, '-', '')
+WHERE cep LIKE '%-%';
+
+ALTER TABLE user_address
+  ADD CONSTRAINT user_address_cep_digits_chk
+  CHECK (cep ~ '^[0-9]{8}$');
+
+ALTER TABLE user_address
+  -- Shown to the user to tell addresses apart (e.g. 'Casa', 'Trabalho')
+  ADD COLUMN IF NOT EXISTS label text NOT NULL DEFAULT 'Principal',
+  ADD COLUMN IF NOT EXISTS is_default boolean NOT NULL DEFAULT false,
+  ADD COLUMN IF NOT EXISTS updated_at timestamptz NOT NULL DEFAULT now ();
+
+ALTER TABLE user_address
+  DROP CONSTRAINT IF EXISTS user_address_label_chk;
+
+ALTER TABLE user_address
+  ADD CONSTRAINT user_address_label_chk
+  CHECK (char_length(btrim(label)) BETWEEN 1 AND 64);
+
+-- Until now every user had at most one address.
+UPDATE user_address
+SET is_default = true
+WHERE NOT is_default;
+
+CREATE UNIQUE INDEX IF NOT EXISTS user_address_one_default_idx ON user_address (user_id)
+WHERE
+  is_default;
+
+-- CEP -> address, loaded with `import-ceps`. Localities with a single CEP have no
+-- logradouro nor bairro.
+CREATE TABLE IF NOT EXISTS cep_directory (
+  cep text PRIMARY KEY,
+  logradouro text,
+  bairro text,
+  cidade text NOT NULL,
+  estado char(2) NOT NULL,
+
+  updated_at timestamptz NOT NULL DEFAULT now (),
+
+  CONSTRAINT cep_directory_cep_digits_chk CHECK (cep ~ '^[0-9]{8}$'),
+  CONSTRAINT cep_directory_estado_chk CHECK (estado ~ '^[A-Z]{2}$')
+);
@@ -0,0 +1,17 @@
+-- At most one background export in progress per user: repeated requests get the pending
+-- one back instead of starting another job.
+
+UPDATE data_exports d
+SET status = 'failed', completed_at = now()
+WHERE status = 'pending'
+  AND EXISTS (
+    SELECT 1
+    FROM data_exports newer
+    WHERE newer.user_id = d.user_id
+      AND newer.status = 'pending'
+      AND (newer.created_at, newer.id) > (d.created_at, d.id)
+  );
+
+CREATE UNIQUE INDEX IF NOT EXISTS data_exports_one_pending_idx ON data_exports (user_id)
+WHERE
+  status = 'pending';
@@ -0,0 +1,113 @@
+//! Brazilian postal codes (CEP, 8 digits).
+
+use std::fmt;
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum CepError {
+    #[error("must be 8 digits")]
+    Length,
+    #[error("contains characters other than digits and . -")]
+    Characters,
+}
+
+/// A CEP, kept as its 8 digits (leading zeros included).
+///
+/// Parsed from formatted (`01001-000`, `01.001-000`) or raw input. Serialized and stored in
+/// canonical form: digits only.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, sqlx::Type)]
+#[serde(into = "String")]
+#[sqlx(transparent)]
+pub struct Cep(String);
+
+impl Cep {
+    pub fn parse(input: &str) -> Result<Self, CepError> {
+        let mut digits = String::with_capacity(8);
+        for c in input.trim().chars() {
+            match c {
+                '0'..='9' => digits.push(c),
+                '.' | '-' | ' ' => {}
+                _ => return Err(CepError::Characters),
+            }
+        }
+        if digits.len() != 8 {
+            return Err(CepError::Length);
+        }
+        Ok(Cep(digits))
+    }
+
+    /// Digits only, as stored.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Human-readable form: `01001-000`.
+    pub fn formatted(&self) -> String {
+        format!("{}-{}", &self.0[..5], &self.0[5..])
+    }
+}
+
+impl fmt::Display for Cep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Cep> for String {
+    fn from(cep: Cep) -> Self {
+        cep.0
+    }
+}
+
+impl std::str::FromStr for Cep {
+    type Err = CepError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Cep::parse(s)
+    }
+}
+
+/// Only strings: as a JSON number, CEPs starting with 0 would lose their leading zeros.
+impl<'de> Deserialize<'de> for Cep {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        Cep::parse(&raw).map_err(|e| de::Error::custom(format!("invalid CEP: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_raw_and_formatted() {
+        for input in ["01001000", "01001-000", "01.001-000", " 01001 000 "] {
+            assert_eq!(Cep::parse(input).unwrap().as_str(), "01001000", "{input}");
+        }
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(Cep::parse("0100100"), Err(CepError::Length));
+        assert_eq!(Cep::parse("010010000"), Err(CepError::Length));
+        assert_eq!(Cep::parse(""), Err(CepError::Length));
+        assert_eq!(Cep::parse("01001a00"), Err(CepError::Characters));
+        assert_eq!(Cep::parse("01001/000"), Err(CepError::Characters));
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(Cep::parse("01001000").unwrap().formatted(), "01001-000");
+    }
+
+    #[test]
+    fn serde_uses_digit_strings() {
+        let cep: Cep = serde_json::from_str(r#""01001-000""#).unwrap();
+        assert_eq!(serde_json::to_string(&cep).unwrap(), r#""01001000""#);
+        // A number would have lost the leading zero.
+        assert!(serde_json::from_str::<Cep>("1001000").is_err());
+        assert!(serde_json::from_str::<Cep>(r#""0100-100""#).is_err());
+    }
+}
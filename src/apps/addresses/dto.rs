@@ -0,0 +1,10 @@
+use utoipa::ToSchema;
+
+use super::models::UserAddress;
+
+#[derive(Debug, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListAddressesResponse {
+    /// Default address first, then oldest first
+    pub addresses: Vec<UserAddress>,
+}
@@ -0,0 +1,308 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth::AuthContext,
+    results::{ApiError, ApiResult},
+    validation::{FieldError, ValidJson, Validator},
+    AppState,
+};
+
+use super::{
+    cep::Cep,
+    dto::ListAddressesResponse,
+    lookup::{same_city, CepInfo},
+    models::{AddressFields, UserAddress},
+    requests::{CreateAddressRequest, UpdateAddressRequest, DEFAULT_LABEL},
+    sql,
+};
+
+/// Most addresses a user can keep.
+const MAX_ADDRESSES: i64 = 10;
+
+#[utoipa::path(
+    tag = "addresses",
+    operation_id = "listAddresses",
+    get,
+    path = "/users/me/addresses",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "The user's addresses, default first", body = ListAddressesResponse),
+        (status = 401, description = "Missing, expired, invalid or revoked token", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn list_addresses(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+) -> ApiResult<StatusCode, ListAddressesResponse> {
+    let addresses = sql::list_addresses(&state.db, auth_context.user.id).await?;
+    Ok((StatusCode::OK, Json(ListAddressesResponse { addresses })))
+}
+
+#[utoipa::path(
+    tag = "addresses",
+    operation_id = "createAddress",
+    post,
+    path = "/users/me/addresses",
+    security(("bearer_auth" = [])),
+    request_body = CreateAddressRequest,
+    responses(
+        (status = 201, description = "Address added, completed from the CEP when it is known", body = UserAddress),
+        (status = 409, description = "The user already has the maximum number of addresses (10)", body = crate::results::ApiErrorBody),
+        (status = 422, description = "Validation failed, or `cidade` / `estado` do not match the CEP (see `fields`)", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn create_address(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    ValidJson(req): ValidJson<CreateAddressRequest>,
+) -> ApiResult<StatusCode, UserAddress> {
+    let user_id = auth_context.user.id;
+    let fields = new_address(&state, "", &req).await?;
+    let address = sql::create_address(&state.db, user_id, &fields, req.is_default, MAX_ADDRESSES)
+        .await?
+        .ok_or(ApiError::TooManyAddresses)?;
+    info!(target: "api.addresses", %user_id, address_id = %address.id, is_default = address.is_default, "address created");
+    Ok((StatusCode::CREATED, Json(address)))
+}
+
+#[utoipa::path(
+    tag = "addresses",
+    operation_id = "updateAddress",
+    patch,
+    path = "/users/me/addresses/{id}",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "Address id")),
+    request_body = UpdateAddressRequest,
+    responses(
+        (status = 200, description = "Updated address", body = UserAddress),
+        (status = 404, description = "No such address", body = crate::results::ApiErrorBody),
+        (status = 422, description = "Validation failed, or `cidade` / `estado` do not match the CEP (see `fields`)", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn update_address(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    ValidJson(req): ValidJson<UpdateAddressRequest>,
+) -> ApiResult<StatusCode, UserAddress> {
+    let user_id = auth_context.user.id;
+    let current = sql::get_address(&state.db, user_id, id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let fields = updated_address(&state, "", Some(&current), req).await?;
+    let address = sql::update_address(&state.db, user_id, id, &fields)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    info!(target: "api.addresses", %user_id, address_id = %id, "address updated");
+    Ok((StatusCode::OK, Json(address)))
+}
+
+#[utoipa::path(
+    tag = "addresses",
+    operation_id = "setDefaultAddress",
+    post,
+    path = "/users/me/addresses/{id}/default",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "Address id")),
+    responses(
+        (status = 204, description = "It is now the default address"),
+        (status = 404, description = "No such address", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn set_default_address(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = auth_context.user.id;
+    if !sql::set_default(&state.db, user_id, id).await? {
+        return Err(ApiError::NotFound);
+    }
+    info!(target: "api.addresses", %user_id, address_id = %id, "default address changed");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    tag = "addresses",
+    operation_id = "deleteAddress",
+    delete,
+    path = "/users/me/addresses/{id}",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "Address id")),
+    responses(
+        (status = 204, description = "Address deleted; if it was the default, the oldest remaining address becomes the default"),
+        (status = 404, description = "No such address", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn delete_address(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = auth_context.user.id;
+    if !sql::delete_address(&state.db, user_id, id).await? {
+        return Err(ApiError::NotFound);
+    }
+    info!(target: "api.addresses", %user_id, address_id = %id, "address deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    tag = "addresses",
+    operation_id = "lookupCep",
+    get,
+    path = "/ceps/{cep}",
+    params(("cep" = String, Path, description = "CEP, with or without formatting", example = "01001-000")),
+    responses(
+        (status = 200, description = "Address of the CEP, to prefill address forms", body = CepInfo),
+        (status = 404, description = "CEP not in the directory", body = crate::results::ApiErrorBody),
+        (status = 422, description = "Not a CEP", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn lookup_cep(
+    State(state): State<AppState>,
+    Path(cep): Path<String>,
+) -> ApiResult<StatusCode, CepInfo> {
+    let cep = Cep::parse(&cep).map_err(|e| {
+        ApiError::Validation(vec![FieldError {
+            path: "cep".to_string(),
+            code: "invalid_cep",
+            message: e.to_string(),
+        }])
+    })?;
+    let info = resolve(&state, &cep).await.ok_or(ApiError::NotFound)?;
+    Ok((StatusCode::OK, Json(info)))
+}
+
+/// Address to create from a request: trimmed, completed from the CEP directory and
+/// checked. `path` prefixes error paths (`address` at signup).
+pub(crate) async fn new_address(
+    state: &AppState,
+    path: &str,
+    req: &CreateAddressRequest,
+) -> Result<AddressFields, ApiError> {
+    let fields = AddressFields {
+        label: trimmed(req.label.clone()).unwrap_or_else(|| DEFAULT_LABEL.to_string()),
+        cep: req.cep.clone(),
+        logradouro: trimmed(req.logradouro.clone()).unwrap_or_default(),
+        numero: req.numero.trim().to_string(),
+        complemento: trimmed(req.complemento.clone()),
+        bairro: trimmed(req.bairro.clone()),
+        cidade: trimmed(req.cidade.clone()).unwrap_or_default(),
+        estado: trimmed(req.estado.clone()).unwrap_or_default(),
+    };
+    complete(state, path, fields).await
+}
+
+/// Apply an address patch on top of `current` (if any) and complete and check the result:
+/// without a current address every required field must be sent (or prefilled from the
+/// CEP). A new CEP drops the current `logradouro`, `bairro`, `cidade` and `estado`, so that
+/// they are prefilled from it unless sent.
+pub(crate) async fn updated_address(
+    state: &AppState,
+    path: &str,
+    current: Option<&UserAddress>,
+    patch: UpdateAddressRequest,
+) -> Result<AddressFields, ApiError> {
+    let Some(cep) = patch.cep.clone().or_else(|| current.map(|a| a.cep.clone())) else {
+        return Err(ApiError::Validation(vec![FieldError {
+            path: field_path(path, "cep"),
+            code: "required",
+            message: "must not be empty".to_string(),
+        }]));
+    };
+    let old = current.filter(|a| a.cep == cep);
+    let fields = AddressFields {
+        label: trimmed(patch.label)
+            .or_else(|| current.map(|a| a.label.clone()))
+            .unwrap_or_else(|| DEFAULT_LABEL.to_string()),
+        cep,
+        logradouro: trimmed(patch.logradouro)
+            .or_else(|| old.map(|a| a.logradouro.clone()))
+            .unwrap_or_default(),
+        numero: trimmed(patch.numero)
+            .or_else(|| current.map(|a| a.numero.clone()))
+            .unwrap_or_default(),
+        complemento: match patch.complemento {
+            Some(complemento) => trimmed(complemento),
+            None => current.and_then(|a| a.complemento.clone()),
+        },
+        bairro: match patch.bairro {
+            Some(bairro) => trimmed(bairro),
+            None => old.and_then(|a| a.bairro.clone()),
+        },
+        cidade: trimmed(patch.cidade)
+            .or_else(|| old.map(|a| a.cidade.clone()))
+            .unwrap_or_default(),
+        estado: trimmed(patch.estado)
+            .or_else(|| old.map(|a| a.estado.clone()))
+            .unwrap_or_default(),
+    };
+    complete(state, path, fields).await
+}
+
+/// Prefill the missing parts of `fields` from the CEP directory and check `cidade` and
+/// `estado` against it (`422 cep_mismatch`), then run the address rules. `cidade` is
+/// stored as the directory spells it. Unknown CEPs are taken as sent.
+async fn complete(
+    state: &AppState,
+    path: &str,
+    mut fields: AddressFields,
+) -> Result<AddressFields, ApiError> {
+    if let Some(info) = resolve(state, &fields.cep).await {
+        let mismatch = |field: &str, expected: &str| FieldError {
+            path: field_path(path, field),
+            code: "cep_mismatch",
+            message: format!("does not match CEP {} ({expected})", info.cep.formatted()),
+        };
+        let mut errors = Vec::new();
+        if !fields.estado.is_empty() && fields.estado != info.estado {
+            errors.push(mismatch("estado", &info.estado));
+        }
+        if !fields.cidade.is_empty() && !same_city(&fields.cidade, &info.cidade) {
+            errors.push(mismatch("cidade", &info.cidade));
+        }
+        if !errors.is_empty() {
+            return Err(ApiError::Validation(errors));
+        }
+        fields.estado = info.estado;
+        fields.cidade = info.cidade;
+        if fields.logradouro.is_empty() {
+            fields.logradouro = info.logradouro.unwrap_or_default();
+        }
+        if fields.bairro.is_none() {
+            fields.bairro = info.bairro;
+        }
+    }
+    Validator::check_at(path, &fields)?;
+    Ok(fields)
+}
+
+/// The directory's entry for `cep`. Lookup failures are logged and the CEP treated as
+/// unknown: the directory only helps, it must not block address changes.
+async fn resolve(state: &AppState, cep: &Cep) -> Option<CepInfo> {
+    state.cep_resolver.resolve(cep).await.unwrap_or_else(|e| {
+        warn!(target: "api.addresses", %cep, cause = %e, "CEP lookup failed");
+        None
+    })
+}
+
+fn trimmed(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn field_path(prefix: &str, field: &str) -> String {
+    if prefix.is_empty() {
+        field.to_string()
+    } else {
+        format!("{prefix}.{field}")
+    }
+}
@@ -0,0 +1,271 @@
+//! CEP -> address resolution, used to prefill and check `cidade` / `estado`.
+//!
+//! The local data comes from the Correios dataset, converted to a CSV of
+//! `cep,logradouro,bairro,cidade,estado` rows (header optional, RFC 4180 quoting), which is
+//! either imported into `cep_directory` (`import-ceps <file>`) or loaded in memory.
+
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Context;
+use axum::async_trait;
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+
+use super::cep::Cep;
+
+/// What the directory knows about a CEP.
+#[derive(Debug, Clone, Serialize, ToSchema, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CepInfo {
+    #[schema(value_type = String, pattern = "^[0-9]{8}$", example = "01001000")]
+    pub cep: Cep,
+    /// Absent for localities with a single CEP
+    #[schema(nullable = true, example = "Praça da Sé")]
+    pub logradouro: Option<String>,
+    #[schema(nullable = true, example = "Sé")]
+    pub bairro: Option<String>,
+    #[schema(example = "São Paulo")]
+    pub cidade: String,
+    #[schema(example = "SP")]
+    pub estado: String,
+}
+
+/// Pluggable CEP directory.
+#[async_trait]
+pub trait CepResolver: Send + Sync {
+    /// `None` for CEPs the directory does not know.
+    async fn resolve(&self, cep: &Cep) -> anyhow::Result<Option<CepInfo>>;
+}
+
+/// No directory: every CEP is unknown, addresses are taken as sent.
+pub struct NoResolver;
+
+#[async_trait]
+impl CepResolver for NoResolver {
+    async fn resolve(&self, _cep: &Cep) -> anyhow::Result<Option<CepInfo>> {
+        Ok(None)
+    }
+}
+
+/// The `cep_directory` table.
+pub struct PostgresResolver {
+    db: PgPool,
+}
+
+#[async_trait]
+impl CepResolver for PostgresResolver {
+    async fn resolve(&self, cep: &Cep) -> anyhow::Result<Option<CepInfo>> {
+        Ok(sqlx::query_as(
+            r#"SELECT cep, logradouro, bairro, cidade, estado
+               FROM cep_directory
+               WHERE cep = $1"#,
+        )
+        .bind(cep)
+        .fetch_optional(&self.db)
+        .await?)
+    }
+}
+
+/// A dataset file loaded in memory at startup. Fine for a region; import the full
+/// dataset into Postgres instead.
+pub struct FileResolver {
+    entries: HashMap<Cep, CepInfo>,
+}
+
+impl FileResolver {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let data = std::fs::read_to_string(path).with_context(|| format!("reading {path}"))?;
+        let entries = parse_dataset(&data)
+            .with_context(|| format!("parsing {path}"))?
+            .into_iter()
+            .map(|info| (info.cep.clone(), info))
+            .collect();
+        Ok(Self { entries })
+    }
+}
+
+#[async_trait]
+impl CepResolver for FileResolver {
+    async fn resolve(&self, cep: &Cep) -> anyhow::Result<Option<CepInfo>> {
+        Ok(self.entries.get(cep).cloned())
+    }
+}
+
+/// Build the resolver selected by `CEP_RESOLVER`: `postgres` (default, the `cep_directory`
+/// table), `file` (the dataset at `CEP_DATA_FILE`, in memory) or `off`.
+pub fn resolver_from_env(db: &PgPool) -> anyhow::Result<Arc<dyn CepResolver>> {
+    match std::env::var("CEP_RESOLVER").as_deref() {
+        Err(_) | Ok("") | Ok("postgres") => Ok(Arc::new(PostgresResolver { db: db.clone() })),
+        Ok("file") => {
+            let path =
+                std::env::var("CEP_DATA_FILE").context("CEP_RESOLVER=file needs CEP_DATA_FILE")?;
+            let resolver = FileResolver::load(&path)?;
+            tracing::info!(%path, ceps = resolver.entries.len(), "CEP dataset loaded");
+            Ok(Arc::new(resolver))
+        }
+        Ok("off") => Ok(Arc::new(NoResolver)),
+        Ok(other) => anyhow::bail!("unknown CEP_RESOLVER `{other}`"),
+    }
+}
+
+/// Parse a dataset CSV. A first line whose CEP does not parse is taken as the header.
+pub fn parse_dataset(data: &str) -> anyhow::Result<Vec<CepInfo>> {
+    let mut entries = Vec::new();
+    for (i, line) in data.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_record(line);
+        let [cep, logradouro, bairro, cidade, estado] = fields.as_slice() else {
+            anyhow::bail!("line {}: expected 5 fields, found {}", i + 1, fields.len());
+        };
+        let cep = match Cep::parse(cep) {
+            Ok(cep) => cep,
+            Err(_) if i == 0 => continue,
+            Err(e) => anyhow::bail!("line {}: invalid CEP `{cep}`: {e}", i + 1),
+        };
+        let estado = estado.trim().to_uppercase();
+        anyhow::ensure!(
+            estado.len() == 2 && estado.bytes().all(|b| b.is_ascii_uppercase()),
+            "line {}: invalid estado `{estado}`",
+            i + 1
+        );
+        let cidade = cidade.trim();
+        anyhow::ensure!(!cidade.is_empty(), "line {}: empty cidade", i + 1);
+        let optional = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+        entries.push(CepInfo {
+            cep,
+            logradouro: optional(logradouro),
+            bairro: optional(bairro),
+            cidade: cidade.to_string(),
+            estado,
+        });
+    }
+    Ok(entries)
+}
+
+/// Fields of one CSV record (RFC 4180 quoting, no line breaks inside fields).
+fn split_record(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("starts with one field");
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields
+}
+
+/// Whether a user-entered `cidade` names the same city as the directory's, ignoring case,
+/// accents and extra spaces (`sao  paulo` matches `São Paulo`).
+pub fn same_city(a: &str, b: &str) -> bool {
+    fn fold(s: &str) -> String {
+        s.split_whitespace()
+            .flat_map(|word| {
+                word.chars()
+                    .map(|c| match c.to_lowercase().next().unwrap_or(c) {
+                        'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+                        'é' | 'è' | 'ê' | 'ë' => 'e',
+                        'í' | 'ì' | 'î' | 'ï' => 'i',
+                        'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+                        'ú' | 'ù' | 'û' | 'ü' => 'u',
+                        'ç' => 'c',
+                        other => other,
+                    })
+                    .chain([' '])
+            })
+            .collect()
+    }
+    fold(a) == fold(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_plain_and_quoted_fields() {
+        assert_eq!(split_record("a,b,,d"), ["a", "b", "", "d"]);
+        assert_eq!(
+            split_record(r#"01001000,"Praça da Sé, lado ímpar",Sé"#),
+            ["01001000", "Praça da Sé, lado ímpar", "Sé"]
+        );
+        assert_eq!(split_record(r#""Rua ""Nova""",x"#), [r#"Rua "Nova""#, "x"]);
+        assert_eq!(split_record("a,b\r"), ["a", "b"]);
+    }
+
+    #[test]
+    fn parses_dataset_with_header() {
+        let data = "cep,logradouro,bairro,cidade,estado\r\n\
+                    01001-000,\"Praça da Sé, lado ímpar\",Sé,São Paulo,sp\r\n\
+                    \r\n\
+                    69900000,,,Rio Branco,AC\n";
+        let entries = parse_dataset(data).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let se = &entries[0];
+        assert_eq!(se.cep.as_str(), "01001000");
+        assert_eq!(se.logradouro.as_deref(), Some("Praça da Sé, lado ímpar"));
+        assert_eq!(se.bairro.as_deref(), Some("Sé"));
+        assert_eq!(se.cidade, "São Paulo");
+        assert_eq!(se.estado, "SP");
+
+        let rio_branco = &entries[1];
+        assert_eq!(rio_branco.logradouro, None);
+        assert_eq!(rio_branco.bairro, None);
+    }
+
+    #[test]
+    fn parses_dataset_without_header() {
+        let entries = parse_dataset("01001000,Praça da Sé,Sé,São Paulo,SP").unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn rejects_bad_rows() {
+        let header = "cep,logradouro,bairro,cidade,estado\n";
+        for (row, error) in [
+            (
+                "01001000,Praça da Sé,Sé,São Paulo",
+                "line 2: expected 5 fields, found 4",
+            ),
+            ("0100100,Praça da Sé,Sé,São Paulo,SP", "line 2: invalid CEP"),
+            (
+                "01001000,Praça da Sé,Sé,São Paulo,S",
+                "line 2: invalid estado",
+            ),
+            (
+                "01001000,Praça da Sé,Sé,São Paulo,S1",
+                "line 2: invalid estado",
+            ),
+            ("01001000,Praça da Sé,Sé, ,SP", "line 2: empty cidade"),
+        ] {
+            let e = parse_dataset(&format!("{header}{row}")).unwrap_err();
+            assert!(e.to_string().starts_with(error), "{row}: {e}");
+        }
+        // Only the first line can be a header.
+        let e = parse_dataset(&format!("01001000,,,São Paulo,SP\n{header}")).unwrap_err();
+        assert!(e.to_string().starts_with("line 2: invalid CEP"), "{e}");
+    }
+
+    #[test]
+    fn same_city_folds_case_accents_and_spaces() {
+        assert!(same_city("São Paulo", "São Paulo"));
+        assert!(same_city("sao  paulo", "São Paulo"));
+        assert!(same_city(" SÃO PAULO ", "são paulo"));
+        assert!(same_city("Goiania", "Goiânia"));
+        assert!(same_city("ITAJUBÁ", "Itajuba"));
+        assert!(same_city("Mogi das Cruzes", "mogi das  cruzes"));
+        assert!(!same_city("São Pedro", "São Paulo"));
+        assert!(!same_city("Santana", "Santa Ana"));
+    }
+}
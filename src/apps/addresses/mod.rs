@@ -0,0 +1,12 @@
+//! User addresses and CEP lookup.
+
+pub mod cep;
+pub mod dto;
+pub mod handlers;
+pub mod lookup;
+pub mod models;
+pub mod requests;
+pub mod routes;
+pub mod sql;
+
+pub use routes::{public_router, router};
@@ -0,0 +1,76 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::validation::{Validate, Validator};
+
+use super::{cep::Cep, requests::MAX_LABEL_CHARS};
+
+/// One of the user's addresses.
+#[derive(Debug, Clone, Serialize, ToSchema, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct UserAddress {
+    pub id: Uuid,
+
+    #[schema(example = "Casa")]
+    pub label: String,
+
+    /// Used wherever a single address is needed (e.g. `address` in `GET /users/me`)
+    pub is_default: bool,
+
+    /// CEP (Brazilian postal code), digits only
+    #[schema(value_type = String, pattern = "^[0-9]{8}$", example = "01001000")]
+    pub cep: Cep,
+
+    #[schema(example = "Praça da Sé")]
+    pub logradouro: String,
+
+    #[schema(example = "123")]
+    pub numero: String,
+
+    #[schema(nullable = true, example = "Apto 12")]
+    pub complemento: Option<String>,
+
+    #[schema(nullable = true, example = "Sé")]
+    pub bairro: Option<String>,
+
+    #[schema(example = "São Paulo")]
+    pub cidade: String,
+
+    /// State abbreviation (e.g. SP)
+    #[schema(example = "SP")]
+    pub estado: String,
+
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Address columns to write, built by the handlers from a request (and the current
+/// address for updates), then completed from the CEP directory.
+///
+/// Empty required strings are missing values: prefilled from the CEP when it is known,
+/// `422 required` otherwise.
+#[derive(Debug, Clone)]
+pub struct AddressFields {
+    pub label: String,
+    pub cep: Cep,
+    pub logradouro: String,
+    pub numero: String,
+    pub complemento: Option<String>,
+    pub bairro: Option<String>,
+    pub cidade: String,
+    pub estado: String,
+}
+
+impl Validate for AddressFields {
+    fn validate(&self, v: &mut Validator) {
+        v.field("label", &self.label)
+            .required()
+            .max_chars(MAX_LABEL_CHARS);
+        v.field("logradouro", &self.logradouro).required();
+        v.field("numero", &self.numero).required();
+        v.field("cidade", &self.cidade).required();
+        v.field("estado", &self.estado).required().uf();
+    }
+}
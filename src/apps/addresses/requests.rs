@@ -0,0 +1,114 @@
+use utoipa::ToSchema;
+
+use crate::{
+    apps::users::requests::nullable,
+    validation::{Validate, Validator},
+};
+
+use super::cep::Cep;
+
+/// Longest `user_address.label` accepted.
+pub const MAX_LABEL_CHARS: usize = 64;
+/// Label of addresses created without one.
+pub const DEFAULT_LABEL: &str = "Principal";
+
+/// A new address (signup, `POST /users/me/addresses`).
+///
+/// `logradouro`, `bairro`, `cidade` and `estado` are prefilled from the CEP when the CEP
+/// directory knows it, and must match it when sent; for unknown CEPs `logradouro`,
+/// `cidade` and `estado` are required.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAddressRequest {
+    /// Defaults to "Principal"
+    #[schema(max_length = 64, example = "Casa")]
+    pub label: Option<String>,
+
+    /// CEP, with or without formatting
+    #[schema(value_type = String, nullable = false, example = "01001-000")]
+    pub cep: Cep,
+
+    #[schema(example = "Praça da Sé")]
+    pub logradouro: Option<String>,
+
+    #[schema(nullable = false, min_length = 1, example = "123")]
+    pub numero: String,
+
+    #[schema(example = "Apto 12")]
+    pub complemento: Option<String>,
+
+    #[schema(example = "Sé")]
+    pub bairro: Option<String>,
+
+    #[schema(example = "São Paulo")]
+    pub cidade: Option<String>,
+
+    /// State abbreviation (e.g. SP)
+    #[schema(pattern = "^[A-Z]{2}$", example = "SP")]
+    pub estado: Option<String>,
+
+    /// Make it the default address (the first address always is; ignored at signup)
+    #[serde(default)]
+    #[schema(nullable = false, example = false)]
+    pub is_default: bool,
+}
+
+impl Validate for CreateAddressRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("label", self.label.as_ref())
+            .not_blank()
+            .max_chars(MAX_LABEL_CHARS);
+        v.field("logradouro", self.logradouro.as_ref()).not_blank();
+        v.field("numero", &self.numero).required();
+        v.field("cidade", self.cidade.as_ref()).not_blank();
+        v.field("estado", self.estado.as_ref()).uf();
+    }
+}
+
+/// Partial address update: only the fields present are changed. A new CEP prefills
+/// `logradouro`, `bairro`, `cidade` and `estado` again unless they are sent too.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdateAddressRequest {
+    #[schema(max_length = 64, example = "Trabalho")]
+    pub label: Option<String>,
+
+    /// CEP, with or without formatting
+    #[schema(value_type = Option<String>, example = "01001-000")]
+    pub cep: Option<Cep>,
+
+    #[schema(example = "Praça da Sé")]
+    pub logradouro: Option<String>,
+
+    #[schema(example = "123")]
+    pub numero: Option<String>,
+
+    /// `null` clears it
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>, nullable = true, example = "Apto 12")]
+    pub complemento: Option<Option<String>>,
+
+    /// `null` clears it
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>, nullable = true, example = "Sé")]
+    pub bairro: Option<Option<String>>,
+
+    #[schema(example = "São Paulo")]
+    pub cidade: Option<String>,
+
+    /// State abbreviation (e.g. SP)
+    #[schema(pattern = "^[A-Z]{2}$", example = "SP")]
+    pub estado: Option<String>,
+}
+
+impl Validate for UpdateAddressRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("label", self.label.as_ref())
+            .not_blank()
+            .max_chars(MAX_LABEL_CHARS);
+        v.field("logradouro", self.logradouro.as_ref()).not_blank();
+        v.field("numero", self.numero.as_ref()).not_blank();
+        v.field("cidade", self.cidade.as_ref()).not_blank();
+        v.field("estado", self.estado.as_ref()).uf();
+    }
+}
@@ -0,0 +1,40 @@
+use axum::{
+    middleware::from_fn_with_state,
+    routing::{get, patch, post},
+    Router,
+};
+
+use crate::{
+    middleware::rate_limit::{rate_limit, KeyBy, Quota, RateLimit},
+    AppState,
+};
+
+use super::handlers;
+
+/// Addresses of the current user, merged into the users account router (which applies
+/// `require_auth` and `require_session`).
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/me/addresses",
+            get(handlers::list_addresses).post(handlers::create_address),
+        )
+        .route(
+            "/me/addresses/:id",
+            patch(handlers::update_address).delete(handlers::delete_address),
+        )
+        .route(
+            "/me/addresses/:id/default",
+            post(handlers::set_default_address),
+        )
+}
+
+/// CEP lookup, public (signup forms prefill the address with it), mounted at the top level.
+///
+/// Rate limited per IP (`RATE_LIMIT_CEP_LOOKUP`, default 60/min).
+pub fn public_router(state: &AppState) -> Router<AppState> {
+    let limit = RateLimit::for_group(state, "cep_lookup", Quota::per_minute(60), KeyBy::Ip);
+    Router::new()
+        .route("/ceps/:cep", get(handlers::lookup_cep))
+        .route_layer(from_fn_with_state(limit, rate_limit))
+}
@@ -0,0 +1,315 @@
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use super::{
+    lookup::CepInfo,
+    models::{AddressFields, UserAddress},
+};
+
+/// Rows per statement when importing the CEP directory.
+const IMPORT_CHUNK: usize = 1000;
+
+/// The user's addresses, default first.
+pub async fn list_addresses(db: &PgPool, user_id: Uuid) -> Result<Vec<UserAddress>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT id, label, is_default, cep, logradouro, numero, complemento, bairro, cidade,
+                  estado, created_at, updated_at
+           FROM user_address
+           WHERE user_id = $1
+           ORDER BY is_default DESC, created_at, id"#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
+
+pub async fn get_address(
+    db: &PgPool,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<Option<UserAddress>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT id, label, is_default, cep, logradouro, numero, complemento, bairro, cidade,
+                  estado, created_at, updated_at
+           FROM user_address
+           WHERE user_id = $1 AND id = $2"#,
+    )
+    .bind(user_id)
+    .bind(id)
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn default_address(
+    db: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<UserAddress>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT id, label, is_default, cep, logradouro, numero, complemento, bairro, cidade,
+                  estado, created_at, updated_at
+           FROM user_address
+           WHERE user_id = $1 AND is_default"#,
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Insert an address as is; callers keep a single default (see `create_address`).
+pub async fn insert_address(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    fields: &AddressFields,
+    is_default: bool,
+) -> Result<UserAddress, sqlx::Error> {
+    sqlx::query_as(
+        r#"INSERT INTO user_address (
+              user_id,
+              label,
+              is_default,
+              cep,
+              logradouro,
+              numero,
+              complemento,
+              bairro,
+              cidade,
+              estado
+           ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+           RETURNING id, label, is_default, cep, logradouro, numero, complemento, bairro,
+                     cidade, estado, created_at, updated_at"#,
+    )
+    .bind(user_id)
+    .bind(&fields.label)
+    .bind(is_default)
+    .bind(&fields.cep)
+    .bind(&fields.logradouro)
+    .bind(&fields.numero)
+    .bind(&fields.complemento)
+    .bind(&fields.bairro)
+    .bind(&fields.cidade)
+    .bind(&fields.estado)
+    .fetch_one(&mut **tx)
+    .await
+}
+
+/// Add an address; it becomes the default if `make_default` or if the user had none.
+/// `None` if the user already has `max` addresses.
+pub async fn create_address(
+    db: &PgPool,
+    user_id: Uuid,
+    fields: &AddressFields,
+    make_default: bool,
+    max: i64,
+) -> Result<Option<UserAddress>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    lock_addresses(&mut tx, user_id).await?;
+    let (count, has_default): (i64, bool) = sqlx::query_as(
+        r#"SELECT count(*), COALESCE(bool_or(is_default), false)
+           FROM user_address
+           WHERE user_id = $1"#,
+    )
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if count >= max {
+        return Ok(None);
+    }
+    let is_default = make_default || !has_default;
+    if is_default {
+        clear_default(&mut tx, user_id).await?;
+    }
+    let address = insert_address(&mut tx, user_id, fields, is_default).await?;
+    tx.commit().await?;
+    Ok(Some(address))
+}
+
+/// Overwrite an address. `None` if the user has no such address.
+pub async fn update_address(
+    db: &PgPool,
+    user_id: Uuid,
+    id: Uuid,
+    fields: &AddressFields,
+) -> Result<Option<UserAddress>, sqlx::Error> {
+    sqlx::query_as(
+        r#"UPDATE user_address
+           SET label = $3,
+               cep = $4,
+               logradouro = $5,
+               numero = $6,
+               complemento = $7,
+               bairro = $8,
+               cidade = $9,
+               estado = $10,
+               updated_at = now()
+           WHERE user_id = $1 AND id = $2
+           RETURNING id, label, is_default, cep, logradouro, numero, complemento, bairro,
+                     cidade, estado, created_at, updated_at"#,
+    )
+    .bind(user_id)
+    .bind(id)
+    .bind(&fields.label)
+    .bind(&fields.cep)
+    .bind(&fields.logradouro)
+    .bind(&fields.numero)
+    .bind(&fields.complemento)
+    .bind(&fields.bairro)
+    .bind(&fields.cidade)
+    .bind(&fields.estado)
+    .fetch_optional(db)
+    .await
+}
+
+/// Overwrite the default address, or create it if the user has none (`PATCH /users/me`).
+pub async fn save_default(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    fields: &AddressFields,
+) -> Result<(), sqlx::Error> {
+    lock_addresses(tx, user_id).await?;
+    let updated = sqlx::query(
+        r#"UPDATE user_address
+           SET label = $2,
+               cep = $3,
+               logradouro = $4,
+               numero = $5,
+               complemento = $6,
+               bairro = $7,
+               cidade = $8,
+               estado = $9,
+               updated_at = now()
+           WHERE user_id = $1 AND is_default"#,
+    )
+    .bind(user_id)
+    .bind(&fields.label)
+    .bind(&fields.cep)
+    .bind(&fields.logradouro)
+    .bind(&fields.numero)
+    .bind(&fields.complemento)
+    .bind(&fields.bairro)
+    .bind(&fields.cidade)
+    .bind(&fields.estado)
+    .execute(&mut **tx)
+    .await?;
+    if updated.rows_affected() == 0 {
+        insert_address(tx, user_id, fields, true).await?;
+    }
+    Ok(())
+}
+
+/// Make `id` the user's default address. Returns `false` if the user has no such address.
+pub async fn set_default(db: &PgPool, user_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    lock_addresses(&mut tx, user_id).await?;
+    let exists: bool = sqlx::query_scalar(
+        r#"SELECT EXISTS (SELECT 1 FROM user_address WHERE user_id = $1 AND id = $2)"#,
+    )
+    .bind(user_id)
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if !exists {
+        return Ok(false);
+    }
+    // Two statements: `user_address_one_default_idx` is checked row by row.
+    clear_default(&mut tx, user_id).await?;
+    sqlx::query(
+        r#"UPDATE user_address SET is_default = true, updated_at = now()
+           WHERE user_id = $1 AND id = $2"#,
+    )
+    .bind(user_id)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Delete an address; if it was the default, the oldest remaining one takes over.
+/// Returns `false` if the user has no such address.
+pub async fn delete_address(db: &PgPool, user_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    lock_addresses(&mut tx, user_id).await?;
+    let was_default: Option<bool> = sqlx::query_scalar(
+        r#"DELETE FROM user_address WHERE user_id = $1 AND id = $2 RETURNING is_default"#,
+    )
+    .bind(user_id)
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(was_default) = was_default else {
+        return Ok(false);
+    };
+    if was_default {
+        sqlx::query(
+            r#"UPDATE user_address SET is_default = true, updated_at = now()
+               WHERE id = (
+                 SELECT id FROM user_address WHERE user_id = $1 ORDER BY created_at, id LIMIT 1
+               )"#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Serialize changes to the user's default address (locks the user row).
+async fn lock_addresses(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"SELECT 1 FROM users WHERE id = $1 FOR NO KEY UPDATE"#)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+async fn clear_default(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE user_address SET is_default = false, updated_at = now()
+           WHERE user_id = $1 AND is_default"#,
+    )
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Upsert dataset rows into `cep_directory`. Returns the number of rows written.
+pub async fn import_directory(db: &PgPool, entries: &[CepInfo]) -> Result<u64, sqlx::Error> {
+    let mut written = 0;
+    for chunk in entries.chunks(IMPORT_CHUNK) {
+        let ceps: Vec<&str> = chunk.iter().map(|e| e.cep.as_str()).collect();
+        let logradouros: Vec<Option<&str>> =
+            chunk.iter().map(|e| e.logradouro.as_deref()).collect();
+        let bairros: Vec<Option<&str>> = chunk.iter().map(|e| e.bairro.as_deref()).collect();
+        let cidades: Vec<&str> = chunk.iter().map(|e| e.cidade.as_str()).collect();
+        let estados: Vec<&str> = chunk.iter().map(|e| e.estado.as_str()).collect();
+        let result = sqlx::query(
+            r#"INSERT INTO cep_directory (cep, logradouro, bairro, cidade, estado)
+               SELECT DISTINCT ON (cep) *
+               FROM unnest($1::text[], $2::text[], $3::text[], $4::text[], $5::text[])
+                 AS t (cep, logradouro, bairro, cidade, estado)
+               ON CONFLICT (cep) DO UPDATE
+               SET logradouro = EXCLUDED.logradouro,
+                   bairro = EXCLUDED.bairro,
+                   cidade = EXCLUDED.cidade,
+                   estado = EXCLUDED.estado,
+                   updated_at = now()"#,
+        )
+        .bind(&ceps)
+        .bind(&logradouros)
+        .bind(&bairros)
+        .bind(&cidades)
+        .bind(&estados)
+        .execute(db)
+        .await?;
+        written += result.rows_affected();
+    }
+    Ok(written)
+}
@@ -0,0 +1,17 @@
+use utoipa::ToSchema;
+
+use crate::apps::users::models::User;
+
+/// Response of `POST /admin/users/{id}/impersonate`.
+#[derive(Debug, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImpersonationResponse {
+    /// Access token acting as the user, marked with an `act` claim naming the admin.
+    /// There is no refresh token; it stops working when the admin's session ends.
+    pub token: String,
+    /// Token lifetime in seconds
+    #[schema(example = 900)]
+    pub expires_in: u64,
+    /// The impersonated user
+    pub user: User,
+}
@@ -0,0 +1,541 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde_json::json;
+use sqlx::PgExecutor;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    apps::{
+        auth::{emails, revocation::RevocationStore, tokens},
+        consents::{
+            self,
+            dto::ListDocumentsResponse,
+            models::{LegalDocument, LegalDocumentRow},
+            requests::PublishDocumentRequest,
+        },
+        deletion::{
+            self,
+            dto::{ListPendingDeletionsResponse, ProcessDeletionsResponse},
+        },
+        users::{
+            self,
+            dto::{ListUsersResponse, UserWithRelatedData},
+            models::{User, UserRole},
+        },
+    },
+    middleware::{
+        auth::{AuthContext, Credential},
+        client_ip::ClientIp,
+        jwt,
+    },
+    results::{ApiError, ApiResult},
+    validation::ValidJson,
+    AppState,
+};
+
+use super::{
+    dto::ImpersonationResponse,
+    models::AdminAction,
+    requests::{ChangeRoleRequest, ListUsersQuery, MAX_PER_PAGE},
+    sql,
+};
+
+/// Default page size of `GET /admin/users`.
+const DEFAULT_PER_PAGE: u32 = 20;
+
+#[utoipa::path(
+    tag = "admin",
+    operation_id = "adminListUsers",
+    get,
+    path = "/admin/users",
+    security(("bearer_auth" = [])),
+    params(ListUsersQuery),
+    responses(
+        (status = 200, description = "One page of users, newest first", body = ListUsersResponse),
+        (status = 400, description = "Invalid page, page size or date range", body = crate::results::ApiErrorBody),
+        (status = 403, description = "Not an admin", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn list_users(
+    State(state): State<AppState>,
+    Query(query): Query<ListUsersQuery>,
+) -> ApiResult<StatusCode, ListUsersResponse> {
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(DEFAULT_PER_PAGE);
+    if page == 0 || per_page == 0 || per_page > MAX_PER_PAGE {
+        return Err(ApiError::BadRequest(format!(
+            "page must be at least 1 and perPage between 1 and {MAX_PER_PAGE}"
+        )));
+    }
+    if let (Some(from), Some(to)) = (query.created_from, query.created_to) {
+        if from > to {
+            return Err(ApiError::BadRequest(
+                "createdFrom must not be after createdTo".to_string(),
+            ));
+        }
+    }
+
+    let offset = (page as i64 - 1) * per_page as i64;
+    let (users, total) = sql::list_users(&state.db, &query, per_page as i64, offset).await?;
+    Ok((
+        StatusCode::OK,
+        Json(ListUsersResponse {
+            users,
+            total,
+            page,
+            per_page,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    operation_id = "adminGetUser",
+    get,
+    path = "/admin/users/{id}",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 200, description = "The user with role data and address", body = UserWithRelatedData),
+        (status = 403, description = "Not an admin", body = crate::results::ApiErrorBody),
+        (status = 404, description = "No such user", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn get_user(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<StatusCode, UserWithRelatedData> {
+    let user = target_user(&state, id).await?;
+    let profile = users::handlers::load_profile(&state, user).await?;
+    Ok((StatusCode::OK, Json(profile)))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    operation_id = "adminChangeRole",
+    patch,
+    path = "/admin/users/{id}/role",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "User id")),
+    request_body = ChangeRoleRequest,
+    responses(
+        (status = 200, description = "Role changed; applies from the user's next request on this instance, \
+            and within `USER_CACHE_TTL_SECS` (default 15s) on the others", body = User),
+        (status = 400, description = "Own account, or the user lacks the organizer/attendee data the role needs", body = crate::results::ApiErrorBody),
+        (status = 403, description = "Not an admin", body = crate::results::ApiErrorBody),
+        (status = 404, description = "No such user", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn change_role(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ChangeRoleRequest>,
+) -> ApiResult<StatusCode, User> {
+    ensure_not_self(&auth_context, id)?;
+    let user = target_user(&state, id).await?;
+    if user.role == req.role {
+        return Ok((StatusCode::OK, Json(user)));
+    }
+    // Organizer and attendee profiles are created at signup; switching to one of those
+    // roles without its data row would leave the account without a profile.
+    if !sql::has_role_data(&state.db, id, &req.role).await? {
+        return Err(ApiError::BadRequest(format!(
+            "user has no {} data; that role can only be obtained by signing up",
+            req.role.as_str()
+        )));
+    }
+
+    let mut tx = state.db.begin().await?;
+    let updated = sql::set_role(&mut *tx, id, &req.role).await?;
+    audit(
+        &mut *tx,
+        &auth_context,
+        Some(id),
+        AdminAction::ChangeRole,
+        json!({ "from": user.role.as_str(), "to": req.role.as_str() }),
+        ip,
+    )
+    .await?;
+    tx.commit().await?;
+    state.users.invalidate(id);
+    Ok((StatusCode::OK, Json(updated)))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    operation_id = "adminDisableUser",
+    post,
+    path = "/admin/users/{id}/disable",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 204, description = "Account disabled and every session signed out"),
+        (status = 400, description = "Own account", body = crate::results::ApiErrorBody),
+        (status = 403, description = "Not an admin", body = crate::results::ApiErrorBody),
+        (status = 404, description = "No such user", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn disable_user(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    ensure_not_self(&auth_context, id)?;
+    target_user(&state, id).await?;
+
+    let mut tx = state.db.begin().await?;
+    sql::set_disabled(&mut *tx, id, true).await?;
+    let cutoff = RevocationStore::revoke_all_in(&mut tx, id).await?;
+    audit(
+        &mut *tx,
+        &auth_context,
+        Some(id),
+        AdminAction::Disable,
+        json!({}),
+        ip,
+    )
+    .await?;
+    tx.commit().await?;
+    state.revocations.all_revoked(id, cutoff);
+    state.users.invalidate(id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    tag = "admin",
+    operation_id = "adminEnableUser",
+    post,
+    path = "/admin/users/{id}/enable",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 204, description = "Account enabled; the user can log in again"),
+        (status = 400, description = "The account has been anonymized", body = crate::results::ApiErrorBody),
+        (status = 403, description = "Not an admin", body = crate::results::ApiErrorBody),
+        (status = 404, description = "No such user", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn enable_user(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    target_user(&state, id).await?;
+    if deletion::sql::is_anonymized(&state.db, id).await? {
+        return Err(ApiError::BadRequest(
+            "anonymized accounts cannot be enabled".to_string(),
+        ));
+    }
+
+    let mut tx = state.db.begin().await?;
+    sql::set_disabled(&mut *tx, id, false).await?;
+    audit(
+        &mut *tx,
+        &auth_context,
+        Some(id),
+        AdminAction::Enable,
+        json!({}),
+        ip,
+    )
+    .await?;
+    tx.commit().await?;
+    state.users.invalidate(id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    tag = "admin",
+    operation_id = "adminForcePasswordReset",
+    post,
+    path = "/admin/users/{id}/password-reset",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 202, description = "Password invalidated, every session signed out and a reset link sent (not to disabled accounts)"),
+        (status = 400, description = "Own account", body = crate::results::ApiErrorBody),
+        (status = 403, description = "Not an admin", body = crate::results::ApiErrorBody),
+        (status = 404, description = "No such user", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn force_password_reset(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    ensure_not_self(&auth_context, id)?;
+    let user = target_user(&state, id).await?;
+
+    // Replace the password with one nobody knows: only the reset link gets the user back in.
+    let password_hash =
+        users::sql::hash_password(&tokens::generate_opaque_token()).map_err(|e| {
+            error!(target: "api.admin", cause = %e, "password hashing failed");
+            ApiError::Internal
+        })?;
+    let mut tx = state.db.begin().await?;
+    users::sql::update_password_hash(&mut *tx, id, &password_hash).await?;
+    let cutoff = RevocationStore::revoke_all_in(&mut tx, id).await?;
+    audit(
+        &mut *tx,
+        &auth_context,
+        Some(id),
+        AdminAction::ForcePasswordReset,
+        json!({}),
+        ip,
+    )
+    .await?;
+    tx.commit().await?;
+    state.revocations.all_revoked(id, cutoff);
+
+    {
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = emails::send_password_reset(&state, &user.email).await {
+                error!(target: "api.admin", user_id = %user.id, cause = %e, "failed to send forced password reset");
+            }
+        });
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    tag = "admin",
+    operation_id = "adminImpersonateUser",
+    post,
+    path = "/admin/users/{id}/impersonate",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 200, description = "Access token acting as the user (no refresh token)", body = ImpersonationResponse),
+        (status = 400, description = "Own account", body = crate::results::ApiErrorBody),
+        (status = 403, description = "Not an admin, target is an admin, or the account is disabled", body = crate::results::ApiErrorBody),
+        (status = 404, description = "No such user", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn impersonate_user(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    Path(id): Path<Uuid>,
+) -> ApiResult<StatusCode, ImpersonationResponse> {
+    ensure_not_self(&auth_context, id)?;
+    // `require_session` keeps API keys and impersonation tokens out of this router.
+    let Credential::AccessToken { sid, .. } = auth_context.credential else {
+        return Err(ApiError::SessionRequired);
+    };
+    let user = target_user(&state, id).await?;
+    if user.role == UserRole::Admin {
+        return Err(ApiError::Forbidden);
+    }
+    if user.is_disabled() {
+        return Err(ApiError::AccountDisabled);
+    }
+
+    let token = tokens::issue_impersonation_token(&state.jwt, &user, auth_context.user.id, sid)?;
+    let expires_in = jwt::access_token_ttl_secs();
+    audit(
+        &state.db,
+        &auth_context,
+        Some(id),
+        AdminAction::Impersonate,
+        json!({ "sessionId": sid, "expiresIn": expires_in }),
+        ip,
+    )
+    .await?;
+    Ok((
+        StatusCode::OK,
+        Json(ImpersonationResponse {
+            token,
+            expires_in,
+            user,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    operation_id = "adminListPendingDeletions",
+    get,
+    path = "/admin/deletions",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Pending account deletions, oldest scheduled first", body = ListPendingDeletionsResponse),
+        (status = 403, description = "Not an admin", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn list_pending_deletions(
+    State(state): State<AppState>,
+) -> ApiResult<StatusCode, ListPendingDeletionsResponse> {
+    let deletions = deletion::sql::list_pending(&state.db).await?;
+    Ok((
+        StatusCode::OK,
+        Json(ListPendingDeletionsResponse { deletions }),
+    ))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    operation_id = "adminProcessDeletions",
+    post,
+    path = "/admin/deletions/process",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Every account whose grace period has ended was anonymized", body = ProcessDeletionsResponse),
+        (status = 403, description = "Not an admin", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn process_deletions(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+) -> ApiResult<StatusCode, ProcessDeletionsResponse> {
+    let admin = (auth_context.user.id, ip);
+    let anonymized = deletion::jobs::process_due(&state.db, Some(admin))
+        .await
+        .map_err(|e| {
+            error!(target: "api.admin", cause = %e, "processing account deletions failed");
+            ApiError::Internal
+        })?;
+    // The anonymizations (and their audit entries) are committed: from here on, failures
+    // only delay this instance noticing them and must not hide the list.
+    for &id in &anonymized {
+        if let Err(e) = state.revocations.revoke_all(&state.db, id).await {
+            warn!(target: "api.admin", user_id = %id, cause = %e, "revoking anonymized account's tokens failed");
+        }
+        state.users.invalidate(id);
+    }
+    Ok((
+        StatusCode::OK,
+        Json(ProcessDeletionsResponse { anonymized }),
+    ))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    operation_id = "adminListLegalDocuments",
+    get,
+    path = "/admin/legal-documents",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Every version of every legal document, newest first (scheduled ones included)", body = ListDocumentsResponse),
+        (status = 403, description = "Not an admin", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn list_legal_documents(
+    State(state): State<AppState>,
+) -> ApiResult<StatusCode, ListDocumentsResponse> {
+    let documents = consents::sql::list_documents(&state.db)
+        .await?
+        .into_iter()
+        .map(LegalDocumentRow::into_document)
+        .collect();
+    Ok((StatusCode::OK, Json(ListDocumentsResponse { documents })))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    operation_id = "adminPublishLegalDocument",
+    post,
+    path = "/admin/legal-documents",
+    security(("bearer_auth" = [])),
+    request_body = PublishDocumentRequest,
+    responses(
+        (status = 201, description = "Version published; from `publishedAt` on, signups must accept it and \
+            existing users see it in `pending_consents`", body = LegalDocument),
+        (status = 403, description = "Not an admin", body = crate::results::ApiErrorBody),
+        (status = 409, description = "This version of the document already exists", body = crate::results::ApiErrorBody),
+        (status = 422, description = "Validation failed", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn publish_legal_document(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    ValidJson(req): ValidJson<PublishDocumentRequest>,
+) -> ApiResult<StatusCode, LegalDocument> {
+    let mut tx = state.db.begin().await?;
+    let document = consents::sql::insert_document(
+        &mut *tx,
+        req.kind,
+        req.version.trim(),
+        req.title.trim(),
+        req.url.as_deref().map(str::trim),
+        req.published_at.unwrap_or_else(chrono::Utc::now),
+    )
+    .await?
+    .into_document();
+    audit(
+        &mut *tx,
+        &auth_context,
+        None,
+        AdminAction::PublishLegalDocument,
+        json!({
+            "documentId": document.id,
+            "kind": document.kind.as_str(),
+            "version": document.version,
+            "publishedAt": document.published_at,
+        }),
+        ip,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok((StatusCode::CREATED, Json(document)))
+}
+
+async fn target_user(state: &AppState, id: Uuid) -> Result<User, ApiError> {
+    users::sql::get_user_by_id(&state.db, id)
+        .await?
+        .ok_or(ApiError::NotFound)
+}
+
+/// Admins cannot lock themselves out (role change, disable, forced reset) or impersonate
+/// themselves.
+fn ensure_not_self(auth_context: &AuthContext, id: Uuid) -> Result<(), ApiError> {
+    if auth_context.user.id == id {
+        return Err(ApiError::BadRequest(
+            "admins cannot do this to their own account".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Record an admin action in `admin_audit_log` (and the logs). Pass the transaction that
+/// makes the change, so that there is no change without its audit entry. `target_user_id`
+/// is `None` for actions on something else than a user.
+async fn audit(
+    db: impl PgExecutor<'_>,
+    auth_context: &AuthContext,
+    target_user_id: Option<Uuid>,
+    action: AdminAction,
+    details: serde_json::Value,
+    ip: std::net::IpAddr,
+) -> Result<(), ApiError> {
+    let admin_id = auth_context.user.id;
+    sql::insert_audit_entry(
+        db,
+        Some(admin_id),
+        target_user_id,
+        action,
+        details.clone(),
+        Some(ip),
+    )
+    .await?;
+    info!(
+        target: "api.admin.audit",
+        %admin_id,
+        target_user_id = ?target_user_id,
+        action = action.as_str(),
+        %details,
+        %ip,
+        "admin action"
+    );
+    Ok(())
+}
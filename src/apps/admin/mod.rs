@@ -0,0 +1,8 @@
+pub mod dto;
+pub mod handlers;
+pub mod models;
+pub mod requests;
+pub mod routes;
+pub mod sql;
+
+pub use routes::router;
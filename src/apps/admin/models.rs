@@ -0,0 +1,25 @@
+/// Actions recorded in `admin_audit_log`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminAction {
+    ChangeRole,
+    Disable,
+    Enable,
+    ForcePasswordReset,
+    Impersonate,
+    Anonymize,
+    PublishLegalDocument,
+}
+
+impl AdminAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminAction::ChangeRole => "change_role",
+            AdminAction::Disable => "disable",
+            AdminAction::Enable => "enable",
+            AdminAction::ForcePasswordReset => "force_password_reset",
+            AdminAction::Impersonate => "impersonate",
+            AdminAction::Anonymize => "anonymize",
+            AdminAction::PublishLegalDocument => "publish_legal_document",
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use utoipa::{IntoParams, ToSchema};
+
+use crate::apps::users::models::UserRole;
+
+/// Largest page of `GET /admin/users`.
+pub const MAX_PER_PAGE: u32 = 100;
+
+/// Query of `GET /admin/users`. Every filter is optional.
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListUsersQuery {
+    /// Only users with this role
+    pub role: Option<UserRole>,
+    /// Case-insensitive substring of the email or full name
+    #[param(example = "johnson")]
+    pub q: Option<String>,
+    /// Created at or after this instant
+    pub created_from: Option<chrono::DateTime<chrono::Utc>>,
+    /// Created before this instant
+    pub created_to: Option<chrono::DateTime<chrono::Utc>>,
+    /// 1-based page number (default 1)
+    #[param(minimum = 1, example = 1)]
+    pub page: Option<u32>,
+    /// Page size (default 20, at most 100)
+    #[param(minimum = 1, maximum = 100, example = 20)]
+    pub per_page: Option<u32>,
+}
+
+/// Request body for `PATCH /admin/users/{id}/role`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeRoleRequest {
+    #[schema(nullable = false, example = "promoter")]
+    pub role: UserRole,
+}
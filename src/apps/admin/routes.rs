@@ -0,0 +1,46 @@
+use axum::{
+    middleware::{from_fn, from_fn_with_state},
+    routing::{get, patch, post},
+    Router,
+};
+
+use crate::{
+    apps::users::models::UserRole,
+    middleware::{
+        auth::require_auth,
+        authz::{require_roles, require_session},
+        rate_limit::{rate_limit, KeyBy, Quota, RateLimit},
+    },
+    AppState,
+};
+
+use super::handlers;
+
+/// Admin endpoints, mounted under `/admin`: admins only, with a full session (no API
+/// keys or impersonation tokens).
+///
+/// Rate limited per user (`RATE_LIMIT_ADMIN`, default 120/min).
+pub fn router(state: AppState) -> Router<AppState> {
+    let limit = RateLimit::for_group(&state, "admin", Quota::per_minute(120), KeyBy::UserOrIp);
+    Router::new()
+        .route("/users", get(handlers::list_users))
+        .route("/users/:id", get(handlers::get_user))
+        .route("/users/:id/role", patch(handlers::change_role))
+        .route("/users/:id/disable", post(handlers::disable_user))
+        .route("/users/:id/enable", post(handlers::enable_user))
+        .route(
+            "/users/:id/password-reset",
+            post(handlers::force_password_reset),
+        )
+        .route("/users/:id/impersonate", post(handlers::impersonate_user))
+        .route("/deletions", get(handlers::list_pending_deletions))
+        .route("/deletions/process", post(handlers::process_deletions))
+        .route(
+            "/legal-documents",
+            get(handlers::list_legal_documents).post(handlers::publish_legal_document),
+        )
+        .route_layer(from_fn_with_state(&[UserRole::Admin][..], require_roles))
+        .route_layer(from_fn(require_session))
+        .route_layer(from_fn_with_state(limit, rate_limit))
+        .route_layer(from_fn_with_state(state, require_auth))
+}
@@ -0,0 +1,140 @@
+use std::net::IpAddr;
+
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::apps::users::models::{User, UserRole, UserRow};
+
+use super::{models::AdminAction, requests::ListUsersQuery};
+
+/// `ILIKE` pattern matching `q` anywhere, with its wildcards escaped.
+fn contains_pattern(q: &str) -> String {
+    let escaped = q
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// One page of users matching `query` (newest first), and the total number of matches.
+pub async fn list_users(
+    db: &PgPool,
+    query: &ListUsersQuery,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<User>, i64), sqlx::Error> {
+    let role = query.role.as_ref().map(UserRole::as_str);
+    let search = query
+        .q
+        .as_deref()
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+        .map(contains_pattern);
+    const FILTER: &str = r#"($1::text IS NULL OR role = $1)
+             AND ($2::text IS NULL OR email ILIKE $2 OR full_name ILIKE $2)
+             AND ($3::timestamptz IS NULL OR created_at >= $3)
+             AND ($4::timestamptz IS NULL OR created_at < $4)"#;
+
+    let total: i64 = sqlx::query_scalar(&format!("SELECT count(*) FROM users WHERE {FILTER}"))
+        .bind(role)
+        .bind(&search)
+        .bind(query.created_from)
+        .bind(query.created_to)
+        .fetch_one(db)
+        .await?;
+
+    let rows: Vec<UserRow> = sqlx::query_as(&format!(
+        r#"SELECT id, full_name, role, email, gov_identification, created_at, updated_at,
+                  disabled_at, email_verified_at, mfa_enabled_at
+           FROM users
+           WHERE {FILTER}
+           ORDER BY created_at DESC, id
+           LIMIT $5 OFFSET $6"#
+    ))
+    .bind(role)
+    .bind(&search)
+    .bind(query.created_from)
+    .bind(query.created_to)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await?;
+
+    Ok((rows.into_iter().map(UserRow::into_user).collect(), total))
+}
+
+/// Whether the user has the role data row `role` needs (organizers and attendees).
+pub async fn has_role_data(
+    db: &PgPool,
+    user_id: Uuid,
+    role: &UserRole,
+) -> Result<bool, sqlx::Error> {
+    let sql = match role {
+        UserRole::Organizer => r#"SELECT EXISTS (SELECT 1 FROM organizer_data WHERE user_id = $1)"#,
+        UserRole::Attendee => r#"SELECT EXISTS (SELECT 1 FROM consumer_data WHERE user_id = $1)"#,
+        _ => return Ok(true),
+    };
+    sqlx::query_scalar(sql).bind(user_id).fetch_one(db).await
+}
+
+pub async fn set_role(
+    db: impl PgExecutor<'_>,
+    user_id: Uuid,
+    role: &UserRole,
+) -> Result<User, sqlx::Error> {
+    let row: UserRow = sqlx::query_as(
+        r#"UPDATE users
+           SET role = $2, updated_at = now()
+           WHERE id = $1
+           RETURNING id, full_name, role, email, gov_identification, created_at, updated_at,
+                  disabled_at, email_verified_at, mfa_enabled_at"#,
+    )
+    .bind(user_id)
+    .bind(role.as_str())
+    .fetch_one(db)
+    .await?;
+    Ok(row.into_user())
+}
+
+/// Disable (`true`) or re-enable an account. Disabling keeps the original `disabled_at`.
+pub async fn set_disabled(
+    db: impl PgExecutor<'_>,
+    user_id: Uuid,
+    disabled: bool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE users
+           SET disabled_at = CASE WHEN $2 THEN COALESCE(disabled_at, now()) END,
+               updated_at = now()
+           WHERE id = $1"#,
+    )
+    .bind(user_id)
+    .bind(disabled)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// `admin_user_id` and `ip` are `None` for actions run from the command line,
+/// `target_user_id` for actions on something else than a user (details say what).
+pub async fn insert_audit_entry(
+    db: impl PgExecutor<'_>,
+    admin_user_id: Option<Uuid>,
+    target_user_id: Option<Uuid>,
+    action: AdminAction,
+    details: serde_json::Value,
+    ip: Option<IpAddr>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO admin_audit_log (admin_user_id, target_user_id, action, details, ip)
+           VALUES ($1, $2, $3, $4, $5)"#,
+    )
+    .bind(admin_user_id)
+    .bind(target_user_id)
+    .bind(action.as_str())
+    .bind(details)
+    .bind(ip.map(|ip| ip.to_string()))
+    .execute(db)
+    .await?;
+    Ok(())
+}
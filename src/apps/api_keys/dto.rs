@@ -0,0 +1,18 @@
+use utoipa::ToSchema;
+
+use super::models::ApiKey;
+
+#[derive(Debug, serde::Serialize, ToSchema)]
+pub struct ListApiKeysResponse {
+    pub keys: Vec<ApiKey>,
+}
+
+/// Returned once, on creation: the full key cannot be retrieved again.
+#[derive(Debug, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyResponse {
+    /// The secret key, sent as `Authorization: ApiKey <key>`
+    #[schema(example = "nxl_k3J9aQ2b_q3Jv0m8c0q0yYy5G3b1n1xJc3cX9mVq3a7m2hQyqkS4")]
+    pub key: String,
+    pub api_key: ApiKey,
+}
@@ -0,0 +1,140 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    apps::auth::tokens,
+    middleware::{
+        auth::AuthContext,
+        authz::{Organizer, RequireRole},
+    },
+    results::{ApiError, ApiResult},
+    AppState,
+};
+
+use super::{
+    dto::{CreateApiKeyResponse, ListApiKeysResponse},
+    requests::CreateApiKeyRequest,
+    sql,
+};
+
+/// Every key starts with this, so leaked keys are easy to grep for.
+const KEY_PREFIX: &str = "nxl_";
+/// Characters of the key kept in clear as its `prefix` (`nxl_` plus 8).
+const DISPLAY_PREFIX_LEN: usize = KEY_PREFIX.len() + 8;
+
+#[utoipa::path(
+    tag = "api-keys",
+    operation_id = "createApiKey",
+    post,
+    path = "/users/me/api-keys",
+    security(("bearer_auth" = [])),
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "Key created; the full key is only returned here", body = CreateApiKeyResponse),
+        (status = 400, description = "Empty name or scopes, or expiry in the past", body = crate::results::ApiErrorBody),
+        (status = 403, description = "Not an organizer, or called with an API key", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn create_api_key(
+    RequireRole(auth_context, ..): RequireRole<Organizer>,
+    State(state): State<AppState>,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> ApiResult<StatusCode, CreateApiKeyResponse> {
+    let name = req.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::BadRequest("name must not be empty".to_string()));
+    }
+    let mut scopes: Vec<&str> = req.scopes.iter().map(|s| s.as_str()).collect();
+    scopes.sort_unstable();
+    scopes.dedup();
+    if scopes.is_empty() {
+        return Err(ApiError::BadRequest(
+            "at least one scope is required".to_string(),
+        ));
+    }
+    if req.expires_at.is_some_and(|at| at <= chrono::Utc::now()) {
+        return Err(ApiError::BadRequest(
+            "expiresAt must be in the future".to_string(),
+        ));
+    }
+
+    let key = format!("{KEY_PREFIX}{}", tokens::generate_opaque_token());
+    let row = sql::insert_api_key(
+        &state.db,
+        auth_context.user.id,
+        name,
+        &key[..DISPLAY_PREFIX_LEN],
+        &tokens::hash_token(&key),
+        &scopes,
+        req.expires_at,
+    )
+    .await?;
+
+    info!(
+        target: "api.api_keys",
+        user_id = %auth_context.user.id,
+        api_key_id = %row.id,
+        scopes = ?scopes,
+        status = 201,
+        "api key created"
+    );
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateApiKeyResponse {
+            key,
+            api_key: row.into_api_key(),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    tag = "api-keys",
+    operation_id = "listApiKeys",
+    get,
+    path = "/users/me/api-keys",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Active keys of the current user", body = ListApiKeysResponse),
+        (status = 401, description = "Missing, expired, invalid or revoked token", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn list_api_keys(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+) -> ApiResult<StatusCode, ListApiKeysResponse> {
+    let keys = sql::list_api_keys(&state.db, auth_context.user.id)
+        .await?
+        .into_iter()
+        .map(|row| row.into_api_key())
+        .collect();
+    Ok((StatusCode::OK, Json(ListApiKeysResponse { keys })))
+}
+
+#[utoipa::path(
+    tag = "api-keys",
+    operation_id = "revokeApiKey",
+    delete,
+    path = "/users/me/api-keys/{id}",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "API key id")),
+    responses(
+        (status = 204, description = "Key revoked"),
+        (status = 404, description = "No such active key", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn revoke_api_key(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if !sql::revoke_api_key(&state.db, auth_context.user.id, id).await? {
+        return Err(ApiError::NotFound);
+    }
+    info!(target: "api.api_keys", user_id = %auth_context.user.id, api_key_id = %id, status = 204, "api key revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,11 @@
+pub mod dto;
+pub mod handlers;
+pub mod models;
+pub mod requests;
+pub mod routes;
+pub mod sql;
+
+pub use routes::router;
+
+/// Resolution of `api_keys.last_used_at`: a key's use is written at most this often.
+pub const API_KEY_USE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
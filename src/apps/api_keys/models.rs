@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// What an API key may do.
+///
+/// Scopes:
+/// - `events:read`: read the organizer's events
+/// - `sales:read`: read orders and sales of the organizer's events
+/// - `tickets:scan`: validate tickets at the door
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum ApiKeyScope {
+    #[serde(rename = "events:read")]
+    ReadEvents,
+    #[serde(rename = "sales:read")]
+    ReadSales,
+    #[serde(rename = "tickets:scan")]
+    ScanTickets,
+}
+
+impl ApiKeyScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyScope::ReadEvents => "events:read",
+            ApiKeyScope::ReadSales => "sales:read",
+            ApiKeyScope::ScanTickets => "tickets:scan",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "events:read" => Some(ApiKeyScope::ReadEvents),
+            "sales:read" => Some(ApiKeyScope::ReadSales),
+            "tickets:scan" => Some(ApiKeyScope::ScanTickets),
+            _ => None,
+        }
+    }
+}
+
+/// API key as listed to its owner (the secret itself is never stored).
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    pub id: Uuid,
+    #[schema(example = "Planilha de vendas")]
+    pub name: String,
+    /// Start of the key, to tell keys apart
+    #[schema(example = "nxl_k3J9aQ2b")]
+    pub prefix: String,
+    pub scopes: Vec<ApiKeyScope>,
+    #[schema(nullable = true)]
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[schema(nullable = true)]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Row returned from database for ApiKey (with scopes as strings)
+#[derive(Debug, Clone, FromRow)]
+pub struct ApiKeyRow {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ApiKeyRow {
+    /// Scopes this key grants (unknown stored values are ignored).
+    pub fn scopes(&self) -> Vec<ApiKeyScope> {
+        self.scopes
+            .iter()
+            .filter_map(|s| ApiKeyScope::from_str(s))
+            .collect()
+    }
+
+    pub fn into_api_key(self) -> ApiKey {
+        ApiKey {
+            scopes: self.scopes(),
+            id: self.id,
+            name: self.name,
+            prefix: self.prefix,
+            last_used_at: self.last_used_at,
+            expires_at: self.expires_at,
+            created_at: self.created_at,
+        }
+    }
+}
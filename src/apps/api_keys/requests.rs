@@ -0,0 +1,20 @@
+use utoipa::ToSchema;
+
+use super::models::ApiKeyScope;
+
+/// Request body for `POST /users/me/api-keys`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyRequest {
+    /// Label to recognise the key by
+    #[schema(nullable = false, example = "Planilha de vendas")]
+    pub name: String,
+
+    /// At least one scope
+    #[schema(nullable = false, example = json!(["events:read", "sales:read"]))]
+    pub scopes: Vec<ApiKeyScope>,
+
+    /// Optional expiry; the key never expires when omitted
+    #[schema(nullable = true, example = "2027-01-01T00:00:00Z")]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
@@ -0,0 +1,19 @@
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+
+use crate::AppState;
+
+use super::handlers;
+
+/// Key management, nested under `/users/me/api-keys` by the users router (which applies
+/// `require_auth` and `require_session`).
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(handlers::list_api_keys).post(handlers::create_api_key),
+        )
+        .route("/:id", delete(handlers::revoke_api_key))
+}
@@ -0,0 +1,89 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::models::ApiKeyRow;
+
+pub async fn insert_api_key(
+    db: &PgPool,
+    user_id: Uuid,
+    name: &str,
+    prefix: &str,
+    key_hash: &str,
+    scopes: &[&str],
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<ApiKeyRow, sqlx::Error> {
+    sqlx::query_as(
+        r#"INSERT INTO api_keys (user_id, name, prefix, key_hash, scopes, expires_at)
+           VALUES ($1, $2, $3, $4, $5, $6)
+           RETURNING id, user_id, name, prefix, scopes, last_used_at, expires_at, created_at"#,
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(prefix)
+    .bind(key_hash)
+    .bind(scopes)
+    .bind(expires_at)
+    .fetch_one(db)
+    .await
+}
+
+/// The user's keys that have not been revoked, newest first (expired ones included).
+pub async fn list_api_keys(db: &PgPool, user_id: Uuid) -> Result<Vec<ApiKeyRow>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT id, user_id, name, prefix, scopes, last_used_at, expires_at, created_at
+           FROM api_keys
+           WHERE user_id = $1 AND revoked_at IS NULL
+           ORDER BY created_at DESC"#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Revoke one of the user's keys. Returns `false` if there is no such active key.
+pub async fn revoke_api_key(db: &PgPool, user_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE api_keys
+           SET revoked_at = now()
+           WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Look up a usable (not revoked, not expired) key by its hash.
+pub async fn find_active_api_key(
+    db: &PgPool,
+    key_hash: &str,
+) -> Result<Option<ApiKeyRow>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT id, user_id, name, prefix, scopes, last_used_at, expires_at, created_at
+           FROM api_keys
+           WHERE key_hash = $1
+             AND revoked_at IS NULL
+             AND (expires_at IS NULL OR expires_at > now())"#,
+    )
+    .bind(key_hash)
+    .fetch_optional(db)
+    .await
+}
+
+/// Record that a key was used. Written at most once per `API_KEY_USE_INTERVAL` per key (see also
+/// `AppState::api_key_uses`).
+pub async fn touch_api_key(db: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+    let interval = chrono::Duration::from_std(super::API_KEY_USE_INTERVAL)
+        .expect("API_KEY_USE_INTERVAL fits a chrono::Duration");
+    sqlx::query(
+        r#"UPDATE api_keys
+           SET last_used_at = now()
+           WHERE id = $1 AND (last_used_at IS NULL OR last_used_at < now() - $2)"#,
+    )
+    .bind(id)
+    .bind(interval)
+    .execute(db)
+    .await?;
+    Ok(())
+}
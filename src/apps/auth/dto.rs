@@ -0,0 +1,45 @@
+use utoipa::ToSchema;
+
+#[derive(Debug, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenResponse {
+    /// Short-lived access token (JWT)
+    pub token: String,
+    /// Opaque refresh token; single use, rotated on every refresh
+    pub refresh_token: String,
+    /// Access token lifetime in seconds
+    #[schema(example = 900)]
+    pub expires_in: u64,
+}
+
+/// Returned by `POST /auth/mfa/totp/setup`.
+#[derive(Debug, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpSetupResponse {
+    /// Base32 secret, for manual entry
+    #[schema(example = "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP")]
+    pub secret: String,
+    /// `otpauth://` URI, to render as a QR code
+    pub otpauth_uri: String,
+}
+
+/// Returned by `POST /auth/mfa/totp/confirm`.
+#[derive(Debug, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaEnabledResponse {
+    /// Single-use recovery codes. Shown only once: the server keeps only their hashes.
+    pub recovery_codes: Vec<String>,
+}
+
+/// Returned by `POST /users/login` when the account has MFA enabled.
+#[derive(Debug, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaChallengeResponse {
+    /// Always `true`
+    pub mfa_required: bool,
+    /// Short-lived token to pass to `POST /auth/mfa/verify` with the second factor
+    pub mfa_token: String,
+    /// `mfaToken` lifetime in seconds
+    #[schema(example = 300)]
+    pub expires_in: u64,
+}
@@ -0,0 +1,208 @@
+//! Transactional emails carrying single-use tokens.
+
+use tracing::info;
+
+use crate::{
+    apps::users::{self, models::User},
+    mailer::{self, Email},
+    AppState,
+};
+
+use super::{sql, tokens};
+
+/// Lifetime of a password reset token.
+pub const PASSWORD_RESET_TTL_MINUTES: i64 = 30;
+
+/// Lifetime of an email verification token.
+pub const EMAIL_VERIFICATION_TTL_HOURS: i64 = 48;
+
+/// Lifetime of an email change token.
+pub const EMAIL_CHANGE_TTL_HOURS: i64 = 24;
+
+/// Lifetime of an account unlock token.
+pub const ACCOUNT_UNLOCK_TTL_HOURS: i64 = 24;
+
+/// Send a password reset link to `email`, if it belongs to an active account.
+/// Unknown addresses are silently ignored (callers must not reveal the difference).
+pub async fn send_password_reset(state: &AppState, email: &str) -> anyhow::Result<()> {
+    let Some(user) = users::sql::find_user_by_email(&state.db, email).await? else {
+        return Ok(());
+    };
+    if user.is_disabled() {
+        return Ok(());
+    }
+
+    let token = tokens::generate_opaque_token();
+    sql::create_password_reset_token(
+        &state.db,
+        user.id,
+        &tokens::hash_token(&token),
+        chrono::Duration::minutes(PASSWORD_RESET_TTL_MINUTES),
+    )
+    .await?;
+
+    let link = format!("{}/reset-password?token={}", mailer::app_base_url(), token);
+    state
+        .mailer
+        .send(Email {
+            to: user.email.clone(),
+            subject: "Redefinição de senha".to_string(),
+            body: format!(
+                "Olá, {}.\n\nPara redefinir sua senha, acesse: {}\n\n\
+                 O link expira em {} minutos e só pode ser usado uma vez. \
+                 Se você não pediu a redefinição, ignore este email.",
+                user.full_name, link, PASSWORD_RESET_TTL_MINUTES
+            ),
+        })
+        .await?;
+
+    info!(target: "api.auth.password", user_id = %user.id, "password reset email sent");
+    Ok(())
+}
+
+/// Send an email verification link for the user's current address.
+pub async fn send_email_verification(state: &AppState, user: &User) -> anyhow::Result<()> {
+    let token = tokens::generate_opaque_token();
+    sql::create_email_verification_token(
+        &state.db,
+        user.id,
+        &user.email,
+        &tokens::hash_token(&token),
+        chrono::Duration::hours(EMAIL_VERIFICATION_TTL_HOURS),
+    )
+    .await?;
+
+    let link = format!("{}/verify-email?token={}", mailer::app_base_url(), token);
+    state
+        .mailer
+        .send(Email {
+            to: user.email.clone(),
+            subject: "Confirme seu email".to_string(),
+            body: format!(
+                "Olá, {}.\n\nConfirme seu email acessando: {}\n\n\
+                 O link expira em {} horas.",
+                user.full_name, link, EMAIL_VERIFICATION_TTL_HOURS
+            ),
+        })
+        .await?;
+
+    info!(target: "api.auth.email", user_id = %user.id, "verification email sent");
+    Ok(())
+}
+
+/// Fire-and-forget `send_email_verification`, for flows that must not fail on mail errors.
+pub fn spawn_email_verification(state: &AppState, user: &User) {
+    let state = state.clone();
+    let user = user.clone();
+    tokio::spawn(async move {
+        if let Err(e) = send_email_verification(&state, &user).await {
+            tracing::error!(
+                target: "api.auth.email",
+                user_id = %user.id,
+                cause = %e,
+                "failed to send verification email"
+            );
+        }
+    });
+}
+
+/// Send the confirmation link for a change of the user's address to `new_email`.
+pub async fn send_email_change(
+    state: &AppState,
+    user: &User,
+    new_email: &str,
+) -> anyhow::Result<()> {
+    let token = tokens::generate_opaque_token();
+    sql::create_email_change_token(
+        &state.db,
+        user.id,
+        &user.email,
+        new_email,
+        &tokens::hash_token(&token),
+        chrono::Duration::hours(EMAIL_CHANGE_TTL_HOURS),
+    )
+    .await?;
+
+    let link = format!(
+        "{}/confirm-email-change?token={}",
+        mailer::app_base_url(),
+        token
+    );
+    state
+        .mailer
+        .send(Email {
+            to: new_email.to_string(),
+            subject: "Confirme seu novo email".to_string(),
+            body: format!(
+                "Olá, {}.\n\nPara usar este endereço na sua conta Noxel, acesse: {}\n\n\
+                 O link expira em {} horas. Até lá, o email da conta continua sendo o atual. \
+                 Se você não pediu a troca, ignore este email.",
+                user.full_name, link, EMAIL_CHANGE_TTL_HOURS
+            ),
+        })
+        .await?;
+
+    info!(target: "api.users.email", user_id = %user.id, "email change confirmation sent");
+    Ok(())
+}
+
+/// Let the previous address know the account's email was changed.
+pub async fn send_email_changed_notice(
+    state: &AppState,
+    user: &User,
+    old_email: &str,
+) -> anyhow::Result<()> {
+    state
+        .mailer
+        .send(Email {
+            to: old_email.to_string(),
+            subject: "O email da sua conta foi alterado".to_string(),
+            body: format!(
+                "Olá, {}.\n\nO email da sua conta Noxel foi alterado para {}.\n\n\
+                 Se não foi você, entre em contato com o suporte imediatamente.",
+                user.full_name, user.email
+            ),
+        })
+        .await?;
+
+    info!(target: "api.users.email", user_id = %user.id, "email changed notice sent");
+    Ok(())
+}
+
+/// Tell the user their account was locked after repeated failed logins, with a link that
+/// lifts the lockout early.
+pub async fn send_account_unlock(
+    state: &AppState,
+    user: &User,
+    locked_until: chrono::DateTime<chrono::Utc>,
+) -> anyhow::Result<()> {
+    let token = tokens::generate_opaque_token();
+    sql::create_unlock_token(
+        &state.db,
+        user.id,
+        &tokens::hash_token(&token),
+        chrono::Duration::hours(ACCOUNT_UNLOCK_TTL_HOURS),
+    )
+    .await?;
+
+    let link = format!("{}/unlock-account?token={}", mailer::app_base_url(), token);
+    state
+        .mailer
+        .send(Email {
+            to: user.email.clone(),
+            subject: "Sua conta foi bloqueada temporariamente".to_string(),
+            body: format!(
+                "Olá, {}.\n\nDetectamos várias tentativas de login sem sucesso na sua conta, \
+                 que ficará bloqueada até {} (UTC).\n\n\
+                 Se foi você, desbloqueie agora acessando: {}\n\n\
+                 Se não foi você, recomendamos redefinir sua senha.",
+                user.full_name,
+                locked_until.format("%d/%m/%Y %H:%M"),
+                link
+            ),
+        })
+        .await?;
+
+    info!(target: "api.auth.throttle", user_id = %user.id, "account unlock email sent");
+    Ok(())
+}
@@ -0,0 +1,622 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Extension, Json,
+};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    apps::users::{self, dto::SignupResponse},
+    middleware::{
+        auth::{AuthContext, Credential},
+        client_ip::{ClientInfo, ClientIp},
+        jwt,
+    },
+    results::{ApiError, ApiResult},
+    validation::ValidJson,
+    AppState,
+};
+
+use super::{
+    dto::{MfaEnabledResponse, TokenResponse, TotpSetupResponse},
+    emails,
+    requests::{
+        ConfirmEmailChangeRequest, ConfirmTotpRequest, DisableMfaRequest, ForgotPasswordRequest,
+        LogoutRequest, RefreshRequest, ResetPasswordRequest, UnlockAccountRequest,
+        VerifyEmailRequest, VerifyMfaRequest,
+    },
+    revocation::RevocationStore,
+    sql::{self, RefreshOutcome},
+    throttle, tokens, totp,
+};
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "refreshToken",
+    post,
+    path = "/auth/refresh",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "Rotated token pair", body = TokenResponse),
+        (status = 401, description = "Invalid, expired or reused refresh token", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn refresh(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    Json(req): Json<RefreshRequest>,
+) -> ApiResult<StatusCode, TokenResponse> {
+    let (user_id, session_id, refresh_token) =
+        match sql::rotate_refresh_token(&state.db, &req.refresh_token, &ip.to_string()).await? {
+            RefreshOutcome::Rotated {
+                user_id,
+                session_id,
+                new_token,
+            } => (user_id, session_id, new_token),
+            RefreshOutcome::Invalid => {
+                info!(target: "api.auth.refresh", status = 401, "refresh rejected");
+                return Err(ApiError::InvalidRefreshToken);
+            }
+            RefreshOutcome::Reused { user_id, family_id } => {
+                state.revocations.session_revoked(family_id);
+                warn!(
+                    target: "api.auth.refresh",
+                    %user_id,
+                    %family_id,
+                    "refresh token reuse detected, session revoked"
+                );
+                return Err(ApiError::RefreshTokenReused);
+            }
+        };
+
+    let user = state
+        .users
+        .get(&state.db, user_id)
+        .await?
+        .ok_or(ApiError::InvalidRefreshToken)?;
+    if user.is_disabled() {
+        return Err(ApiError::AccountDisabled);
+    }
+    let token = tokens::issue_access_token(&state.jwt, &user, session_id)?;
+
+    info!(target: "api.auth.refresh", user_id = %user.id, status = 200, "refresh response");
+
+    Ok((
+        StatusCode::OK,
+        Json(TokenResponse {
+            token,
+            refresh_token,
+            expires_in: jwt::access_token_ttl_secs(),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "logout",
+    post,
+    path = "/auth/logout",
+    security(("bearer_auth" = [])),
+    request_body(content = Option<LogoutRequest>, description = "Optionally also revoke the session of this refresh token"),
+    responses(
+        (status = 204, description = "Current session signed out"),
+        (status = 401, description = "Missing, expired, invalid or revoked token", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn logout(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    req: Option<Json<LogoutRequest>>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = auth_context.user.id;
+    // `require_session` keeps API keys out of this route.
+    let Credential::AccessToken { jti, exp, sid, .. } = auth_context.credential else {
+        return Err(ApiError::SessionRequired);
+    };
+
+    state
+        .revocations
+        .revoke(&state.db, jti, user_id, exp)
+        .await?;
+    state
+        .revocations
+        .revoke_session(&state.db, user_id, sid)
+        .await?;
+
+    let refresh_token = req.and_then(|Json(req)| req.refresh_token);
+    if let Some(refresh_token) = refresh_token {
+        if let Some(session_id) =
+            sql::revoke_refresh_family_by_token(&state.db, user_id, &refresh_token).await?
+        {
+            state.revocations.session_revoked(session_id);
+        }
+    }
+
+    info!(target: "api.auth.logout", %user_id, %jti, session_id = %sid, status = 204, "logout");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "logoutAll",
+    post,
+    path = "/auth/logout-all",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 204, description = "Every access and refresh token of the user revoked"),
+        (status = 401, description = "Missing, expired, invalid or revoked token", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn logout_all(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = auth_context.user.id;
+    state.revocations.revoke_all(&state.db, user_id).await?;
+
+    info!(target: "api.auth.logout", %user_id, status = 204, "logout everywhere");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "jwks",
+    get,
+    path = "/.well-known/jwks.json",
+    responses((status = 200, description = "Public keys that verify access tokens (JWK set)", content_type = "application/jwk-set+json"))
+)]
+pub async fn jwks(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [
+            (header::CONTENT_TYPE, "application/jwk-set+json"),
+            (header::CACHE_CONTROL, "public, max-age=300"),
+        ],
+        Json(state.jwt.jwks()),
+    )
+}
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "forgotPassword",
+    post,
+    path = "/auth/password/forgot",
+    request_body = ForgotPasswordRequest,
+    responses((status = 202, description = "If the email belongs to an account, a reset link is on its way"))
+)]
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    Json(req): Json<ForgotPasswordRequest>,
+) -> StatusCode {
+    info!(target: "api.auth.password", email = ?req.email, "forgot password request");
+
+    // Always 202, and do the work off the request path so neither the status nor the
+    // response time reveals whether the account exists.
+    tokio::spawn(async move {
+        if let Err(e) = emails::send_password_reset(&state, &req.email).await {
+            error!(target: "api.auth.password", cause = %e, "failed to send password reset");
+        }
+    });
+
+    StatusCode::ACCEPTED
+}
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "resetPassword",
+    post,
+    path = "/auth/password/reset",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 204, description = "Password changed; every existing session revoked"),
+        (status = 400, description = "Invalid, expired or already used token", body = crate::results::ApiErrorBody),
+        (status = 422, description = "Validation failed (see `fields`)", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn reset_password(
+    State(state): State<AppState>,
+    ValidJson(req): ValidJson<ResetPasswordRequest>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .password_policy
+        .check("newPassword", &req.new_password)
+        .await?;
+    let password_hash = users::sql::hash_password(&req.new_password).map_err(|e| {
+        error!(target: "api.auth.password", cause = %e, "password hashing failed");
+        ApiError::Internal
+    })?;
+
+    let mut tx = state.db.begin().await?;
+    let Some(user_id) =
+        sql::consume_password_reset_token(&mut *tx, &tokens::hash_token(&req.token)).await?
+    else {
+        info!(target: "api.auth.password", status = 400, "password reset rejected");
+        return Err(ApiError::InvalidResetToken);
+    };
+    users::sql::update_password_hash(&mut *tx, user_id, &password_hash).await?;
+    // Whoever knew the old password may still hold tokens: log out everywhere, in the same
+    // transaction so the new password never comes without it.
+    let cutoff = RevocationStore::revoke_all_in(&mut tx, user_id).await?;
+    tx.commit().await?;
+    state.revocations.all_revoked(user_id, cutoff);
+
+    // Proving access to the mailbox is enough to lift a lockout.
+    if let Some(user) = users::sql::get_user_by_id(&state.db, user_id).await? {
+        throttle::unlock(&state, &user).await?;
+    }
+
+    info!(target: "api.auth.password", %user_id, status = 204, "password reset");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "unlockAccount",
+    post,
+    path = "/auth/unlock",
+    request_body = UnlockAccountRequest,
+    responses(
+        (status = 204, description = "Account lockout lifted"),
+        (status = 400, description = "Invalid, expired or already used token", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn unlock_account(
+    State(state): State<AppState>,
+    Json(req): Json<UnlockAccountRequest>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = sql::consume_unlock_token(&state.db, &tokens::hash_token(&req.token))
+        .await?
+        .ok_or(ApiError::InvalidUnlockToken)?;
+    let user = users::sql::get_user_by_id(&state.db, user_id)
+        .await?
+        .ok_or(ApiError::InvalidUnlockToken)?;
+    throttle::unlock(&state, &user).await?;
+
+    info!(target: "api.auth.throttle", %user_id, status = 204, "account unlocked by email link");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "verifyEmail",
+    post,
+    path = "/auth/email/verify",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 204, description = "Email verified"),
+        (status = 400, description = "Invalid, expired or already used token", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(req): Json<VerifyEmailRequest>,
+) -> Result<StatusCode, ApiError> {
+    let Some(user_id) = sql::verify_email_token(&state.db, &tokens::hash_token(&req.token)).await?
+    else {
+        info!(target: "api.auth.email", status = 400, "email verification rejected");
+        return Err(ApiError::InvalidVerificationToken);
+    };
+    state.users.invalidate(user_id);
+
+    info!(target: "api.auth.email", %user_id, status = 204, "email verified");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "confirmEmailChange",
+    post,
+    path = "/auth/email/change/confirm",
+    request_body = ConfirmEmailChangeRequest,
+    responses(
+        (status = 204, description = "Email changed (and verified)"),
+        (status = 400, description = "Invalid, expired or already used token, or the account's email changed since", body = crate::results::ApiErrorBody),
+        (status = 409, description = "The new email was taken in the meantime", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn confirm_email_change(
+    State(state): State<AppState>,
+    Json(req): Json<ConfirmEmailChangeRequest>,
+) -> Result<StatusCode, ApiError> {
+    // A violation of `users_email_unique` comes out as `ApiError::EmailTaken`.
+    let change = sql::confirm_email_change(&state.db, &tokens::hash_token(&req.token)).await?;
+    let Some(change) = change else {
+        info!(target: "api.users.email", status = 400, "email change rejected");
+        return Err(ApiError::InvalidEmailChangeToken);
+    };
+    state.users.invalidate(change.user_id);
+
+    if let Some(user) = users::sql::get_user_by_id(&state.db, change.user_id).await? {
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) =
+                emails::send_email_changed_notice(&state, &user, &change.old_email).await
+            {
+                error!(target: "api.users.email", user_id = %user.id, cause = %e, "failed to send email changed notice");
+            }
+        });
+    }
+
+    info!(target: "api.users.email", user_id = %change.user_id, status = 204, "email changed");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "resendEmailVerification",
+    post,
+    path = "/auth/email/resend",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 202, description = "A new verification link is on its way"),
+        (status = 409, description = "Email already verified", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn resend_email_verification(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ApiError> {
+    let user = &auth_context.user;
+    if user.is_email_verified() {
+        return Err(ApiError::EmailAlreadyVerified);
+    }
+
+    emails::send_email_verification(&state, user)
+        .await
+        .map_err(|e| {
+            error!(target: "api.auth.email", user_id = %user.id, cause = %e, "failed to send verification email");
+            ApiError::Internal
+        })?;
+
+    info!(target: "api.auth.email", user_id = %user.id, status = 202, "verification email resent");
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "setupTotp",
+    post,
+    path = "/auth/mfa/totp/setup",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "New TOTP secret; confirm it with a code to enable MFA", body = TotpSetupResponse),
+        (status = 409, description = "MFA already enabled", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn setup_totp(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+) -> ApiResult<StatusCode, TotpSetupResponse> {
+    let user = &auth_context.user;
+    if user.is_mfa_enabled() {
+        return Err(ApiError::MfaAlreadyEnabled);
+    }
+
+    // Calling setup again restarts the enrollment with a new secret.
+    let secret = totp::generate_secret();
+    if !sql::upsert_pending_totp(&state.db, user.id, &secret).await? {
+        return Err(ApiError::MfaAlreadyEnabled);
+    }
+
+    info!(target: "api.auth.mfa", user_id = %user.id, status = 200, "totp enrollment started");
+    Ok((
+        StatusCode::OK,
+        Json(TotpSetupResponse {
+            otpauth_uri: totp::otpauth_uri(&secret, &user.email),
+            secret,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "confirmTotp",
+    post,
+    path = "/auth/mfa/totp/confirm",
+    security(("bearer_auth" = [])),
+    request_body = ConfirmTotpRequest,
+    responses(
+        (status = 200, description = "MFA enabled; recovery codes are only shown here", body = MfaEnabledResponse),
+        (status = 400, description = "No pending enrollment", body = crate::results::ApiErrorBody),
+        (status = 401, description = "Wrong code", body = crate::results::ApiErrorBody),
+        (status = 409, description = "MFA already enabled", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn confirm_totp(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    Json(req): Json<ConfirmTotpRequest>,
+) -> ApiResult<StatusCode, MfaEnabledResponse> {
+    let user = &auth_context.user;
+    if user.is_mfa_enabled() {
+        return Err(ApiError::MfaAlreadyEnabled);
+    }
+    let pending = sql::get_totp(&state.db, user.id)
+        .await?
+        .filter(|t| t.confirmed_at.is_none())
+        .ok_or_else(|| {
+            ApiError::BadRequest(
+                "no pending TOTP enrollment; call /auth/mfa/totp/setup first".to_string(),
+            )
+        })?;
+
+    let step = totp::verify_code(&pending.secret, &req.code, totp::current_step())
+        .ok_or(ApiError::InvalidMfaCode)?;
+    let recovery_codes = tokens::generate_recovery_codes();
+    let hashes: Vec<String> = recovery_codes
+        .iter()
+        .map(|c| tokens::hash_recovery_code(c))
+        .collect();
+    if !sql::confirm_totp(&state.db, user.id, step as i64, &hashes).await? {
+        return Err(ApiError::InvalidMfaCode);
+    }
+    state.users.invalidate(user.id);
+
+    info!(target: "api.auth.mfa", user_id = %user.id, status = 200, "mfa enabled");
+    Ok((StatusCode::OK, Json(MfaEnabledResponse { recovery_codes })))
+}
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "disableTotp",
+    post,
+    path = "/auth/mfa/totp/disable",
+    security(("bearer_auth" = [])),
+    request_body = DisableMfaRequest,
+    responses(
+        (status = 204, description = "MFA disabled; TOTP secret and recovery codes deleted"),
+        (status = 401, description = "Wrong code", body = crate::results::ApiErrorBody),
+        (status = 403, description = "MFA is mandatory for this role", body = crate::results::ApiErrorBody),
+        (status = 409, description = "MFA not enabled", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn disable_totp(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    Json(req): Json<DisableMfaRequest>,
+) -> Result<StatusCode, ApiError> {
+    let user = &auth_context.user;
+    if !user.is_mfa_enabled() {
+        return Err(ApiError::MfaNotEnabled);
+    }
+    if state.mfa_policy.is_required_for(&user.role) {
+        return Err(ApiError::MfaRequired);
+    }
+    check_second_factor(
+        &state,
+        user.id,
+        req.code.as_deref(),
+        req.recovery_code.as_deref(),
+    )
+    .await?;
+
+    sql::disable_mfa(&state.db, user.id).await?;
+    state.users.invalidate(user.id);
+
+    info!(target: "api.auth.mfa", user_id = %user.id, status = 204, "mfa disabled");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    tag = "auth",
+    operation_id = "verifyMfa",
+    post,
+    path = "/auth/mfa/verify",
+    request_body = VerifyMfaRequest,
+    responses(
+        (status = 200, description = "Second factor accepted; logged in", body = SignupResponse),
+        (status = 401, description = "Invalid or expired mfaToken, or wrong code", body = crate::results::ApiErrorBody),
+        (status = 429, description = "Too many failed attempts (see `Retry-After`)", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn verify_mfa(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(req): Json<VerifyMfaRequest>,
+) -> ApiResult<StatusCode, SignupResponse> {
+    let ip = client.ip;
+    let claims = jwt::verify_mfa_pending_token(&req.mfa_token, &state.jwt)
+        .map_err(|_| ApiError::InvalidMfaToken)?;
+    if state
+        .revocations
+        .is_revoked(&state.db, claims.jti, claims.sub, claims.iat_ms)
+        .await?
+    {
+        return Err(ApiError::InvalidMfaToken);
+    }
+    let user = state
+        .users
+        .get(&state.db, claims.sub)
+        .await?
+        .filter(|u| u.is_mfa_enabled())
+        .ok_or(ApiError::InvalidMfaToken)?;
+    if user.is_disabled() {
+        return Err(ApiError::AccountDisabled);
+    }
+
+    // Wrong codes count against the account like wrong passwords.
+    throttle::check(&state, &user.email, ip).await?;
+    if let Err(e) = check_second_factor(
+        &state,
+        user.id,
+        req.code.as_deref(),
+        req.recovery_code.as_deref(),
+    )
+    .await
+    {
+        info!(target: "api.auth.mfa", user_id = %user.id, %ip, code = ?e.code(), "second factor rejected");
+        if matches!(e, ApiError::InvalidMfaCode) {
+            throttle::record_failure(&state, &user.email, ip).await?;
+        }
+        return Err(e);
+    }
+    throttle::record_success(&state, &user.email).await?;
+
+    // The pending token is single use.
+    state
+        .revocations
+        .revoke(&state.db, claims.jti, user.id, claims.exp)
+        .await?;
+    let pair =
+        tokens::issue_token_pair(&state, &user, claims.device_label.as_deref(), &client).await?;
+
+    info!(
+        target: "api.auth.mfa",
+        user_id = %user.id,
+        recovery_code = req.recovery_code.is_some(),
+        status = 200,
+        "second factor accepted"
+    );
+    Ok((
+        StatusCode::OK,
+        Json(SignupResponse {
+            token: pair.token,
+            refresh_token: pair.refresh_token,
+            expires_in: pair.expires_in,
+            user,
+        }),
+    ))
+}
+
+/// Check a TOTP code or a recovery code (exactly one of them) for `user_id`.
+///
+/// Accepted TOTP steps and recovery codes are recorded, so neither can be replayed.
+async fn check_second_factor(
+    state: &AppState,
+    user_id: Uuid,
+    code: Option<&str>,
+    recovery_code: Option<&str>,
+) -> Result<(), ApiError> {
+    let accepted = match (code, recovery_code) {
+        (Some(code), None) => {
+            let secret = sql::get_totp(&state.db, user_id)
+                .await?
+                .filter(|t| t.confirmed_at.is_some())
+                .ok_or(ApiError::MfaNotEnabled)?
+                .secret;
+            match totp::verify_code(&secret, code, totp::current_step()) {
+                Some(step) => sql::record_totp_step(&state.db, user_id, step as i64).await?,
+                None => false,
+            }
+        }
+        (None, Some(recovery_code)) => {
+            sql::consume_recovery_code(
+                &state.db,
+                user_id,
+                &tokens::hash_recovery_code(recovery_code),
+            )
+            .await?
+        }
+        _ => {
+            return Err(ApiError::BadRequest(
+                "exactly one of code and recoveryCode is required".to_string(),
+            ))
+        }
+    };
+    if accepted {
+        Ok(())
+    } else {
+        Err(ApiError::InvalidMfaCode)
+    }
+}
@@ -0,0 +1,14 @@
+pub mod dto;
+pub mod emails;
+pub mod handlers;
+pub mod models;
+pub mod password_policy;
+pub mod requests;
+pub mod revocation;
+pub mod routes;
+pub mod sql;
+pub mod throttle;
+pub mod tokens;
+pub mod totp;
+
+pub use routes::router;
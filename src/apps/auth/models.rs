@@ -0,0 +1,21 @@
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Stored refresh token (the raw token is never persisted, only its hash).
+#[derive(Debug, Clone, FromRow)]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub device_label: Option<String>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// A user's TOTP secret (`user_totp`). Unconfirmed while enrollment is pending.
+#[derive(Debug, Clone, FromRow)]
+pub struct UserTotp {
+    pub secret: String,
+    pub confirmed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
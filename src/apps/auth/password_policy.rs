@@ -0,0 +1,373 @@
+//! Rules for new passwords (signup, password reset and password change).
+//!
+//! Besides length and character classes, passwords are checked against a list of known
+//! breached passwords, without any network access:
+//! - a small bundled list of the most common ones (`common_passwords.txt`), plus an
+//!   optional plain-text list (`PASSWORD_BREACHED_LIST`, one password per line);
+//! - optionally, a directory of k-anonymity range files in the Have I Been Pwned format
+//!   (`PASSWORD_BREACHED_HASHES_DIR`): one file per 5-hex-digit SHA-1 prefix, named
+//!   `<PREFIX>.txt`, with `SUFFIX:COUNT` lines. Only the file of the password's prefix
+//!   is read.
+
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
+
+use anyhow::Context;
+use sha1::{Digest, Sha1};
+
+use crate::{results::ApiError, validation::FieldError};
+
+const BUNDLED_LIST: &str = include_str!("common_passwords.txt");
+
+/// Character classes a policy can require.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    Symbol,
+}
+
+impl CharClass {
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "lower" | "lowercase" => Ok(Self::Lowercase),
+            "upper" | "uppercase" => Ok(Self::Uppercase),
+            "digit" => Ok(Self::Digit),
+            "symbol" => Ok(Self::Symbol),
+            other => anyhow::bail!("unknown character class `{other}`"),
+        }
+    }
+
+    fn matches(self, c: char) -> bool {
+        match self {
+            Self::Lowercase => c.is_lowercase(),
+            Self::Uppercase => c.is_uppercase(),
+            Self::Digit => c.is_ascii_digit(),
+            Self::Symbol => !c.is_alphanumeric() && !c.is_whitespace(),
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Lowercase => "a lowercase letter",
+            Self::Uppercase => "an uppercase letter",
+            Self::Digit => "a digit",
+            Self::Symbol => "a symbol",
+        }
+    }
+}
+
+/// Password policy configuration:
+/// - `PASSWORD_MIN_CHARS`: shortest accepted password (default 8)
+/// - `PASSWORD_MAX_CHARS`: longest accepted password, bounding the hashing cost
+///   (default 128)
+/// - `PASSWORD_REQUIRED_CLASSES`: comma-separated classes every password must contain,
+///   among `lower`, `upper`, `digit` and `symbol` (default none)
+/// - `PASSWORD_BREACHED_LIST`: extra plain-text list of rejected passwords
+/// - `PASSWORD_BREACHED_HASHES_DIR`: directory of SHA-1 range files (see the module docs)
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    min_chars: usize,
+    max_chars: usize,
+    required_classes: Vec<CharClass>,
+    /// Lowercased
+    common: Arc<HashSet<String>>,
+    hashes_dir: Option<PathBuf>,
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> anyhow::Result<Self> {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(default)
+        }
+        let min_chars = var("PASSWORD_MIN_CHARS", 8).max(1);
+        let max_chars = var("PASSWORD_MAX_CHARS", 128);
+        anyhow::ensure!(
+            max_chars >= min_chars,
+            "PASSWORD_MAX_CHARS ({max_chars}) is below PASSWORD_MIN_CHARS ({min_chars})"
+        );
+
+        let required_classes = std::env::var("PASSWORD_REQUIRED_CLASSES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(CharClass::from_str)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut common = parse_list(BUNDLED_LIST);
+        if let Ok(path) = std::env::var("PASSWORD_BREACHED_LIST") {
+            let list = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading PASSWORD_BREACHED_LIST ({path})"))?;
+            common.extend(parse_list(&list));
+        }
+
+        let hashes_dir = std::env::var("PASSWORD_BREACHED_HASHES_DIR")
+            .ok()
+            .map(PathBuf::from);
+        if let Some(dir) = &hashes_dir {
+            anyhow::ensure!(
+                dir.is_dir(),
+                "PASSWORD_BREACHED_HASHES_DIR ({}) is not a directory",
+                dir.display()
+            );
+        }
+
+        tracing::info!(
+            min_chars,
+            max_chars,
+            ?required_classes,
+            breached_list = common.len(),
+            breached_hashes = hashes_dir.is_some(),
+            "password policy"
+        );
+        Ok(Self {
+            min_chars,
+            max_chars,
+            required_classes,
+            common: Arc::new(common),
+            hashes_dir,
+        })
+    }
+
+    /// `ApiError::Validation` on the field `path` if `password` breaks the policy.
+    pub async fn check(&self, path: &str, password: &str) -> Result<(), ApiError> {
+        let failure = |code: &'static str, message: String| {
+            Err(ApiError::Validation(vec![FieldError {
+                path: path.to_string(),
+                code,
+                message,
+            }]))
+        };
+
+        let chars = password.chars().count();
+        if chars < self.min_chars {
+            return failure(
+                "too_short",
+                format!("must be at least {} characters", self.min_chars),
+            );
+        }
+        if chars > self.max_chars {
+            return failure(
+                "too_long",
+                format!("must be at most {} characters", self.max_chars),
+            );
+        }
+
+        let missing: Vec<_> = self
+            .required_classes
+            .iter()
+            .filter(|class| !password.chars().any(|c| class.matches(c)))
+            .map(|class| class.describe())
+            .collect();
+        if !missing.is_empty() {
+            return failure(
+                "missing_character_class",
+                format!("must contain {}", missing.join(", ")),
+            );
+        }
+
+        if self.is_breached(password).await {
+            return failure(
+                "breached_password",
+                "is a known breached or very common password; choose another".to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    async fn is_breached(&self, password: &str) -> bool {
+        if self.common.contains(&password.trim().to_lowercase()) {
+            return true;
+        }
+        let Some(dir) = &self.hashes_dir else {
+            return false;
+        };
+
+        let hash = hex::encode_upper(Sha1::digest(password.as_bytes()));
+        let (prefix, suffix) = hash.split_at(5);
+        let path = dir.join(format!("{prefix}.txt"));
+        match tokio::fs::read_to_string(&path).await {
+            Ok(range) => range.lines().any(|line| {
+                line.split(':')
+                    .next()
+                    .is_some_and(|s| s.trim().eq_ignore_ascii_case(suffix))
+            }),
+            // No file: no breached password has this prefix.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+            Err(e) => {
+                // Fail open: an unreadable range file should not block signups.
+                tracing::warn!(target: "api.auth.password", path = %path.display(), cause = %e, "breached password range unreadable");
+                false
+            }
+        }
+    }
+}
+
+/// Lowercased passwords of a list, skipping blank lines and `#` comments.
+fn parse_list(list: &str) -> HashSet<String> {
+    list.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(required_classes: Vec<CharClass>, hashes_dir: Option<PathBuf>) -> PasswordPolicy {
+        let mut common = parse_list(BUNDLED_LIST);
+        common.extend(parse_list("# deploy list\n\n  Noxel2026  \n"));
+        PasswordPolicy {
+            min_chars: 8,
+            max_chars: 16,
+            required_classes,
+            common: Arc::new(common),
+            hashes_dir,
+        }
+    }
+
+    /// Code of the single field error `check` returned, `None` if it passed.
+    async fn failure(policy: &PasswordPolicy, password: &str) -> Option<&'static str> {
+        match policy.check("password", password).await {
+            Ok(()) => None,
+            Err(ApiError::Validation(fields)) => {
+                assert_eq!(fields.len(), 1);
+                assert_eq!(fields[0].path, "password");
+                Some(fields[0].code)
+            }
+            Err(e) => panic!("unexpected error: {e:?}"),
+        }
+    }
+
+    /// Empty range file directory, removed on drop.
+    struct RangeDir(PathBuf);
+
+    impl RangeDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("pwned-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir(&dir).unwrap();
+            Self(dir)
+        }
+
+        /// Write the range file of `password`'s prefix, listing its suffix as `suffix_case`
+        /// formats it among other suffixes.
+        fn add(&self, password: &str, suffix_case: fn(&str) -> String) {
+            let hash = hex::encode_upper(Sha1::digest(password.as_bytes()));
+            let (prefix, suffix) = hash.split_at(5);
+            let range = format!(
+                "0018A45C4D1DEF81644B54AB7F969B88D65:1\n{}:42\nFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF:3\n",
+                suffix_case(suffix)
+            );
+            std::fs::write(self.0.join(format!("{prefix}.txt")), range).unwrap();
+        }
+    }
+
+    impl Drop for RangeDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn checks_length_in_characters() {
+        let policy = policy(vec![], None);
+        assert_eq!(failure(&policy, "short").await, Some("too_short"));
+        // 8 characters, 16 bytes.
+        assert_eq!(failure(&policy, "çãõéíóúâ").await, None);
+        assert_eq!(failure(&policy, "horsebattery1234").await, None);
+        assert_eq!(
+            failure(&policy, "horsebattery12345").await,
+            Some("too_long")
+        );
+    }
+
+    #[tokio::test]
+    async fn checks_required_classes() {
+        let strict = policy(
+            vec![CharClass::Uppercase, CharClass::Digit, CharClass::Symbol],
+            None,
+        );
+        assert_eq!(failure(&strict, "Horse-battery7").await, None);
+        assert_eq!(
+            failure(&strict, "horse-battery7").await,
+            Some("missing_character_class")
+        );
+        let Err(ApiError::Validation(fields)) = strict.check("password", "horsebattery").await
+        else {
+            panic!("expected a validation error");
+        };
+        assert_eq!(
+            fields[0].message,
+            "must contain an uppercase letter, a digit, a symbol"
+        );
+
+        let lower = policy(vec![CharClass::Lowercase], None);
+        assert_eq!(
+            failure(&lower, "HORSE-BATTERY").await,
+            Some("missing_character_class")
+        );
+        assert_eq!(failure(&lower, "HORSE-BATTERy").await, None);
+    }
+
+    #[test]
+    fn parses_class_names() {
+        assert_eq!(CharClass::from_str("lower").unwrap(), CharClass::Lowercase);
+        assert_eq!(
+            CharClass::from_str("uppercase").unwrap(),
+            CharClass::Uppercase
+        );
+        assert!(CharClass::from_str("emoji").is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_listed_passwords_case_insensitively() {
+        let policy = policy(vec![], None);
+        for password in [
+            "password",
+            "PassWord",
+            "12345678",
+            " qwerty123 ",
+            "noxel2026",
+        ] {
+            assert_eq!(
+                failure(&policy, password).await,
+                Some("breached_password"),
+                "{password}"
+            );
+        }
+        assert_eq!(failure(&policy, "# deploy list").await, None);
+    }
+
+    #[tokio::test]
+    async fn rejects_passwords_in_the_range_files() {
+        let dir = RangeDir::new();
+        dir.add("horse battery", |s| s.to_string());
+        dir.add("staple horse", |s| s.to_lowercase());
+        let policy = policy(vec![], Some(dir.0.clone()));
+
+        assert_eq!(
+            failure(&policy, "horse battery").await,
+            Some("breached_password")
+        );
+        assert_eq!(
+            failure(&policy, "staple horse").await,
+            Some("breached_password")
+        );
+        // No range file for its prefix: not breached.
+        assert_eq!(failure(&policy, "battery staple").await, None);
+    }
+
+    #[tokio::test]
+    async fn range_files_only_match_the_full_suffix() {
+        let dir = RangeDir::new();
+        dir.add("horse battery", |s| s[..s.len() - 1].to_string());
+        let policy = policy(vec![], Some(dir.0.clone()));
+        assert_eq!(failure(&policy, "horse battery").await, None);
+    }
+}
@@ -0,0 +1,120 @@
+use utoipa::ToSchema;
+
+use crate::validation::{Validate, Validator};
+
+/// Request body for rotating a refresh token.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshRequest {
+    #[schema(
+        nullable = false,
+        example = "q3Jv0m8c0q0yYy5G3b1n1xJc3cX9mVq3a7m2hQyqkS4"
+    )]
+    pub refresh_token: String,
+}
+
+/// Optional request body for `POST /auth/logout`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoutRequest {
+    /// Refresh token whose session is signed out too (the access token's own session
+    /// always is)
+    #[schema(nullable = true)]
+    pub refresh_token: Option<String>,
+}
+
+/// Request body for `POST /auth/password/forgot`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ForgotPasswordRequest {
+    #[schema(nullable = false, example = "johnson@noxel.com")]
+    pub email: String,
+}
+
+/// Request body for `POST /auth/password/reset`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetPasswordRequest {
+    /// Token from the reset email
+    #[schema(nullable = false)]
+    pub token: String,
+
+    /// New password (will be hashed with Argon2id); must satisfy the password policy
+    #[schema(nullable = false)]
+    pub new_password: String,
+}
+
+impl Validate for ResetPasswordRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("token", &self.token).required();
+        v.field("newPassword", &self.new_password).required();
+    }
+}
+
+/// Request body for `POST /auth/email/verify`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyEmailRequest {
+    /// Token from the verification email
+    #[schema(nullable = false)]
+    pub token: String,
+}
+
+/// Request body for `POST /auth/email/change/confirm`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmEmailChangeRequest {
+    /// Token from the email sent to the new address
+    #[schema(nullable = false)]
+    pub token: String,
+}
+
+/// Request body for `POST /auth/unlock`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UnlockAccountRequest {
+    /// Token from the lockout email
+    #[schema(nullable = false)]
+    pub token: String,
+}
+
+/// Request body for `POST /auth/mfa/totp/confirm`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmTotpRequest {
+    /// Current 6-digit code from the authenticator app
+    #[schema(nullable = false, example = "123456")]
+    pub code: String,
+}
+
+/// Request body for `POST /auth/mfa/verify`: the second login step.
+///
+/// Exactly one of `code` and `recoveryCode` must be given.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyMfaRequest {
+    /// `mfaToken` returned by `POST /users/login`
+    #[schema(nullable = false)]
+    pub mfa_token: String,
+
+    /// 6-digit TOTP code
+    #[schema(nullable = true, example = "123456")]
+    pub code: Option<String>,
+
+    /// One of the recovery codes handed out at enrollment (single use)
+    #[schema(nullable = true, example = "abcde-fghij")]
+    pub recovery_code: Option<String>,
+}
+
+/// Request body for `POST /auth/mfa/totp/disable`.
+///
+/// Exactly one of `code` and `recoveryCode` must be given.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DisableMfaRequest {
+    #[schema(nullable = true, example = "123456")]
+    pub code: Option<String>,
+
+    #[schema(nullable = true, example = "abcde-fghij")]
+    pub recovery_code: Option<String>,
+}
@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{apps::sessions, cache::TtlCache};
+
+use super::sql;
+
+/// Default lifetime of cached revocation lookups.
+const DEFAULT_REVOCATION_CACHE_TTL_SECS: u64 = 30;
+
+/// Access token revocation backed by Postgres, with an in-memory cache in front.
+///
+/// Revocations made through this instance take effect immediately. Revocations made by
+/// another instance are picked up once the cached lookup expires
+/// (`REVOCATION_CACHE_TTL_SECS`, default 30s).
+#[derive(Debug, Clone)]
+pub struct RevocationStore {
+    /// jti -> revoked?
+    revoked: TtlCache<Uuid, bool>,
+    /// session id -> revoked?
+    sessions: TtlCache<Uuid, bool>,
+    /// user id -> `tokens_valid_after` in milliseconds since the epoch
+    valid_after: TtlCache<Uuid, Option<i64>>,
+}
+
+impl RevocationStore {
+    pub fn new(cache_ttl: Duration) -> Self {
+        Self {
+            revoked: TtlCache::new(cache_ttl),
+            sessions: TtlCache::new(cache_ttl),
+            valid_after: TtlCache::new(cache_ttl),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let secs = std::env::var("REVOCATION_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_REVOCATION_CACHE_TTL_SECS);
+        Self::new(Duration::from_secs(secs))
+    }
+
+    /// Whether a token (identified by `jti`, issued to `user_id` at `iat_ms`) has been
+    /// revoked, either individually or by a "logout everywhere" issued after it.
+    pub async fn is_revoked(
+        &self,
+        db: &PgPool,
+        jti: Uuid,
+        user_id: Uuid,
+        iat_ms: u64,
+    ) -> Result<bool, sqlx::Error> {
+        let valid_after = match self.valid_after.get(&user_id) {
+            Some(v) => v,
+            None => {
+                let v = sql::get_tokens_valid_after(db, user_id)
+                    .await?
+                    .map(|t| t.timestamp_millis());
+                self.valid_after.insert(user_id, v);
+                v
+            }
+        };
+        // A token issued in the same millisecond as the cut-off is treated as issued
+        // before it.
+        if valid_after.is_some_and(|cutoff| iat_ms as i64 <= cutoff) {
+            return Ok(true);
+        }
+
+        let revoked = match self.revoked.get(&jti) {
+            Some(v) => v,
+            None => {
+                let v = sql::is_token_revoked(db, jti).await?;
+                self.revoked.insert(jti, v);
+                v
+            }
+        };
+        Ok(revoked)
+    }
+
+    /// Whether the session `session_id` has been signed out (or no longer exists).
+    pub async fn is_session_revoked(
+        &self,
+        db: &PgPool,
+        session_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        if let Some(v) = self.sessions.get(&session_id) {
+            return Ok(v);
+        }
+        let v = sessions::sql::is_session_revoked(db, session_id).await?;
+        self.sessions.insert(session_id, v);
+        Ok(v)
+    }
+
+    /// Sign out one of `user_id`'s sessions: its refresh tokens are revoked and its access
+    /// tokens rejected. Returns `false` if there is no such active session.
+    pub async fn revoke_session(
+        &self,
+        db: &PgPool,
+        user_id: Uuid,
+        session_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let revoked = sessions::sql::revoke_session(db, user_id, session_id).await?;
+        if revoked {
+            self.sessions.insert(session_id, true);
+        }
+        Ok(revoked)
+    }
+
+    /// Sign out every session of `user_id` but `keep` (the caller's own), as part of the
+    /// caller's transaction. Once it commits, pass each returned session id to
+    /// `session_revoked`.
+    pub async fn revoke_other_sessions_in(
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+        keep: Uuid,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        sessions::sql::revoke_other_sessions(&mut **tx, user_id, keep).await
+    }
+
+    /// Record a session revoked elsewhere (refresh token reuse, a committed
+    /// `revoke_other_sessions_in`) so this instance rejects its access tokens right away.
+    pub fn session_revoked(&self, session_id: Uuid) {
+        self.sessions.insert(session_id, true);
+    }
+
+    /// Revoke a single access token until its natural expiry.
+    pub async fn revoke(
+        &self,
+        db: &PgPool,
+        jti: Uuid,
+        user_id: Uuid,
+        exp: u64,
+    ) -> Result<(), sqlx::Error> {
+        sql::insert_revoked_token(db, jti, user_id, exp as i64).await?;
+        self.revoked.insert(jti, true);
+        Ok(())
+    }
+
+    /// Invalidate every access token issued to `user_id` so far, and every session.
+    pub async fn revoke_all(&self, db: &PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+        let mut tx = db.begin().await?;
+        let cutoff = Self::revoke_all_in(&mut tx, user_id).await?;
+        tx.commit().await?;
+        self.all_revoked(user_id, cutoff);
+        Ok(())
+    }
+
+    /// `revoke_all` as part of the caller's transaction. Once it commits, pass the returned
+    /// cut-off to `all_revoked` so this instance applies it right away.
+    pub async fn revoke_all_in(
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+    ) -> Result<DateTime<Utc>, sqlx::Error> {
+        let cutoff = sql::set_tokens_valid_after_now(&mut **tx, user_id).await?;
+        sql::revoke_user_refresh_tokens(&mut **tx, user_id).await?;
+        Ok(cutoff)
+    }
+
+    /// Record a committed `revoke_all_in` cut-off.
+    pub fn all_revoked(&self, user_id: Uuid, cutoff: DateTime<Utc>) {
+        self.valid_after
+            .insert(user_id, Some(cutoff.timestamp_millis()));
+    }
+
+    /// Delete revocation rows for tokens that have expired anyway and drop stale cache entries.
+    pub async fn purge_expired(&self, db: &PgPool) -> Result<u64, sqlx::Error> {
+        self.revoked.purge_expired();
+        self.sessions.purge_expired();
+        self.valid_after.purge_expired();
+        sql::purge_expired_revoked_tokens(db).await
+    }
+}
@@ -0,0 +1,77 @@
+use axum::{
+    middleware::{from_fn, from_fn_with_state},
+    routing::post,
+    Router,
+};
+
+use crate::{
+    middleware::{
+        auth::{require_auth, require_auth_allow_mfa_enrollment},
+        authz::require_session,
+        rate_limit::{rate_limit, KeyBy, Quota, RateLimit},
+    },
+    AppState,
+};
+
+use super::handlers;
+
+/// Rate limit shared by the authenticated `/auth` routers (`RATE_LIMIT_AUTH_PROTECTED`,
+/// default 60/min per user).
+fn protected_limit(state: &AppState) -> RateLimit {
+    RateLimit::for_group(
+        state,
+        "auth_protected",
+        Quota::per_minute(60),
+        KeyBy::UserOrIp,
+    )
+}
+
+/// Unauthenticated endpoints.
+///
+/// Rate limited per IP (`RATE_LIMIT_AUTH_PUBLIC`, default 30/min).
+pub fn public_router(state: AppState) -> Router<AppState> {
+    let limit = RateLimit::for_group(&state, "auth_public", Quota::per_minute(30), KeyBy::Ip);
+    Router::new()
+        .route("/refresh", post(handlers::refresh))
+        .route("/password/forgot", post(handlers::forgot_password))
+        .route("/password/reset", post(handlers::reset_password))
+        .route("/email/verify", post(handlers::verify_email))
+        .route(
+            "/email/change/confirm",
+            post(handlers::confirm_email_change),
+        )
+        .route("/unlock", post(handlers::unlock_account))
+        .route("/mfa/verify", post(handlers::verify_mfa))
+        .route_layer(from_fn_with_state(limit, rate_limit))
+}
+
+/// Authenticated endpoints. Session management: API keys are refused.
+pub fn protected_router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/email/resend", post(handlers::resend_email_verification))
+        .route("/mfa/totp/disable", post(handlers::disable_totp))
+        .route_layer(from_fn(require_session))
+        .route_layer(from_fn_with_state(protected_limit(&state), rate_limit))
+        .route_layer(from_fn_with_state(state, require_auth))
+}
+
+/// Authenticated endpoints that stay reachable for users who still have to enroll a
+/// second factor under the `MfaPolicy`.
+pub fn mfa_enrollment_router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/logout", post(handlers::logout))
+        .route("/logout-all", post(handlers::logout_all))
+        .route("/mfa/totp/setup", post(handlers::setup_totp))
+        .route("/mfa/totp/confirm", post(handlers::confirm_totp))
+        .route_layer(from_fn(require_session))
+        .route_layer(from_fn_with_state(protected_limit(&state), rate_limit))
+        .route_layer(from_fn_with_state(state, require_auth_allow_mfa_enrollment))
+}
+
+/// Token/session endpoints, mounted under `/auth`.
+pub fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .merge(public_router(state.clone()))
+        .merge(protected_router(state.clone()))
+        .merge(mfa_enrollment_router(state))
+}
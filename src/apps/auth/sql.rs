@@ -0,0 +1,709 @@
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use super::{
+    models::{RefreshToken, UserTotp},
+    tokens,
+};
+
+/// Outcome of presenting a refresh token.
+#[derive(Debug)]
+pub enum RefreshOutcome {
+    /// Token was valid; it is now marked used and `new_token` replaces it.
+    Rotated {
+        user_id: Uuid,
+        session_id: Uuid,
+        new_token: String,
+    },
+    /// Unknown, expired or revoked token.
+    Invalid,
+    /// Token was already used: the whole family has been revoked.
+    Reused { user_id: Uuid, family_id: Uuid },
+}
+
+pub async fn insert_refresh_token(
+    db: impl PgExecutor<'_>,
+    user_id: Uuid,
+    family_id: Uuid,
+    token_hash: &str,
+    device_label: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO refresh_tokens (user_id, family_id, token_hash, device_label, expires_at)
+           VALUES ($1, $2, $3, $4, $5)"#,
+    )
+    .bind(user_id)
+    .bind(family_id)
+    .bind(token_hash)
+    .bind(device_label)
+    .bind(chrono::Utc::now() + tokens::refresh_token_ttl())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Revoke every still-active token of a family, and its session.
+pub async fn revoke_refresh_family(
+    db: impl PgExecutor<'_>,
+    family_id: Uuid,
+) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query(
+        r#"WITH session AS (
+             UPDATE sessions SET revoked_at = now() WHERE id = $1 AND revoked_at IS NULL
+           )
+           UPDATE refresh_tokens
+           SET revoked_at = now()
+           WHERE family_id = $1 AND revoked_at IS NULL"#,
+    )
+    .bind(family_id)
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+/// Rotate a refresh token atomically.
+///
+/// The presented token row is locked, so two concurrent refreshes with the same token
+/// cannot both succeed: the second one sees `used_at` set and triggers reuse detection.
+/// A successful rotation also refreshes the session's `last_seen_at` and `ip`.
+pub async fn rotate_refresh_token(
+    db: &PgPool,
+    presented: &str,
+    ip: &str,
+) -> Result<RefreshOutcome, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let current: Option<RefreshToken> = sqlx::query_as(
+        r#"SELECT id, user_id, family_id, device_label, expires_at, used_at, revoked_at
+           FROM refresh_tokens
+           WHERE token_hash = $1
+           FOR UPDATE"#,
+    )
+    .bind(tokens::hash_token(presented))
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(current) = current else {
+        return Ok(RefreshOutcome::Invalid);
+    };
+
+    if current.revoked_at.is_some() {
+        return Ok(RefreshOutcome::Invalid);
+    }
+
+    if current.used_at.is_some() {
+        revoke_refresh_family(&mut *tx, current.family_id).await?;
+        tx.commit().await?;
+        return Ok(RefreshOutcome::Reused {
+            user_id: current.user_id,
+            family_id: current.family_id,
+        });
+    }
+
+    if current.expires_at <= chrono::Utc::now() {
+        return Ok(RefreshOutcome::Invalid);
+    }
+
+    sqlx::query(r#"UPDATE refresh_tokens SET used_at = now() WHERE id = $1"#)
+        .bind(current.id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(r#"UPDATE sessions SET last_seen_at = now(), ip = $2 WHERE id = $1"#)
+        .bind(current.family_id)
+        .bind(ip)
+        .execute(&mut *tx)
+        .await?;
+
+    let new_token = tokens::generate_opaque_token();
+    insert_refresh_token(
+        &mut *tx,
+        current.user_id,
+        current.family_id,
+        &tokens::hash_token(&new_token),
+        current.device_label.as_deref(),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(RefreshOutcome::Rotated {
+        user_id: current.user_id,
+        session_id: current.family_id,
+        new_token,
+    })
+}
+
+/// Revoke every still-active refresh token and session of a user.
+pub async fn revoke_user_refresh_tokens(
+    db: impl PgExecutor<'_>,
+    user_id: Uuid,
+) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query(
+        r#"WITH revoked_sessions AS (
+             UPDATE sessions SET revoked_at = now() WHERE user_id = $1 AND revoked_at IS NULL
+           )
+           UPDATE refresh_tokens
+           SET revoked_at = now()
+           WHERE user_id = $1 AND revoked_at IS NULL"#,
+    )
+    .bind(user_id)
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+/// Revoke the family (and session) of a refresh token, but only if it belongs to
+/// `user_id`. Returns the session id.
+pub async fn revoke_refresh_family_by_token(
+    db: &PgPool,
+    user_id: Uuid,
+    presented: &str,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let family_id: Option<Uuid> = sqlx::query_scalar(
+        r#"SELECT family_id FROM refresh_tokens WHERE token_hash = $1 AND user_id = $2"#,
+    )
+    .bind(tokens::hash_token(presented))
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?;
+    if let Some(family_id) = family_id {
+        revoke_refresh_family(db, family_id).await?;
+    }
+    Ok(family_id)
+}
+
+pub async fn insert_revoked_token(
+    db: &PgPool,
+    jti: Uuid,
+    user_id: Uuid,
+    exp: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO revoked_tokens (jti, user_id, expires_at)
+           VALUES ($1, $2, to_timestamp($3))
+           ON CONFLICT (jti) DO NOTHING"#,
+    )
+    .bind(jti)
+    .bind(user_id)
+    .bind(exp as f64)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn is_token_revoked(db: &PgPool, jti: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(r#"SELECT EXISTS (SELECT 1 FROM revoked_tokens WHERE jti = $1)"#)
+        .bind(jti)
+        .fetch_one(db)
+        .await
+}
+
+pub async fn get_tokens_valid_after(
+    db: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, sqlx::Error> {
+    let row: Option<Option<chrono::DateTime<chrono::Utc>>> =
+        sqlx::query_scalar(r#"SELECT tokens_valid_after FROM users WHERE id = $1"#)
+            .bind(user_id)
+            .fetch_optional(db)
+            .await?;
+    Ok(row.flatten())
+}
+
+/// Set `users.tokens_valid_after = now()` and return the new cut-off.
+pub async fn set_tokens_valid_after_now(
+    db: impl PgExecutor<'_>,
+    user_id: Uuid,
+) -> Result<chrono::DateTime<chrono::Utc>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"UPDATE users
+           SET tokens_valid_after = now()
+           WHERE id = $1
+           RETURNING tokens_valid_after"#,
+    )
+    .bind(user_id)
+    .fetch_one(db)
+    .await
+}
+
+pub async fn purge_expired_revoked_tokens(db: &PgPool) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query(r#"DELETE FROM revoked_tokens WHERE expires_at < now()"#)
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Store a new password reset token, invalidating any earlier unused one for the user.
+pub async fn create_password_reset_token(
+    db: &PgPool,
+    user_id: Uuid,
+    token_hash: &str,
+    ttl: chrono::Duration,
+) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+    sqlx::query(
+        r#"UPDATE password_reset_tokens
+           SET used_at = now()
+           WHERE user_id = $1 AND used_at IS NULL"#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO password_reset_tokens (user_id, token_hash, expires_at)
+           VALUES ($1, $2, $3)"#,
+    )
+    .bind(user_id)
+    .bind(token_hash)
+    .bind(chrono::Utc::now() + ttl)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
+
+/// Mark a password reset token as used, if it is still valid, and return its user.
+/// Single use is enforced by the conditional update itself.
+pub async fn consume_password_reset_token(
+    db: impl PgExecutor<'_>,
+    token_hash: &str,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"UPDATE password_reset_tokens
+           SET used_at = now()
+           WHERE token_hash = $1 AND used_at IS NULL AND expires_at > now()
+           RETURNING user_id"#,
+    )
+    .bind(token_hash)
+    .fetch_optional(db)
+    .await
+}
+
+/// Store a new email verification token, invalidating any earlier unused one for the user.
+pub async fn create_email_verification_token(
+    db: &PgPool,
+    user_id: Uuid,
+    email: &str,
+    token_hash: &str,
+    ttl: chrono::Duration,
+) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+    sqlx::query(
+        r#"UPDATE email_verification_tokens
+           SET used_at = now()
+           WHERE user_id = $1 AND used_at IS NULL"#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO email_verification_tokens (user_id, email, token_hash, expires_at)
+           VALUES ($1, $2, $3, $4)"#,
+    )
+    .bind(user_id)
+    .bind(email)
+    .bind(token_hash)
+    .bind(chrono::Utc::now() + ttl)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
+
+/// Consume a verification token and mark the address as verified, in one statement.
+///
+/// Returns the user id, or `None` when the token is unknown, used, expired, or was issued
+/// for an address the user no longer has.
+pub async fn verify_email_token(
+    db: &PgPool,
+    token_hash: &str,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"WITH consumed AS (
+             UPDATE email_verification_tokens
+             SET used_at = now()
+             WHERE token_hash = $1 AND used_at IS NULL AND expires_at > now()
+             RETURNING user_id, email
+           )
+           UPDATE users u
+           SET email_verified_at = COALESCE(u.email_verified_at, now())
+           FROM consumed c
+           WHERE u.id = c.user_id AND lower(u.email) = lower(c.email)
+           RETURNING u.id"#,
+    )
+    .bind(token_hash)
+    .fetch_optional(db)
+    .await
+}
+
+/// Store a new email change token, invalidating any earlier unused one for the user.
+pub async fn create_email_change_token(
+    db: &PgPool,
+    user_id: Uuid,
+    old_email: &str,
+    new_email: &str,
+    token_hash: &str,
+    ttl: chrono::Duration,
+) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+    sqlx::query(
+        r#"UPDATE email_change_tokens
+           SET used_at = now()
+           WHERE user_id = $1 AND used_at IS NULL"#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO email_change_tokens (user_id, old_email, new_email, token_hash, expires_at)
+           VALUES ($1, $2, $3, $4, $5)"#,
+    )
+    .bind(user_id)
+    .bind(old_email)
+    .bind(new_email)
+    .bind(token_hash)
+    .bind(chrono::Utc::now() + ttl)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
+
+/// Email change confirmed by `confirm_email_change`.
+#[derive(Debug, sqlx::FromRow)]
+pub struct EmailChange {
+    pub user_id: Uuid,
+    pub old_email: String,
+    pub new_email: String,
+}
+
+/// Consume an email change token and switch the address (marked verified: the link went
+/// to it), in one transaction.
+///
+/// Returns `None` when the token is unknown, used, expired, or the user's address changed
+/// since it was issued. Fails with the `users_email_unique` violation if the new address
+/// was taken in the meantime.
+pub async fn confirm_email_change(
+    db: &PgPool,
+    token_hash: &str,
+) -> Result<Option<EmailChange>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let change: Option<EmailChange> = sqlx::query_as(
+        r#"UPDATE email_change_tokens
+           SET used_at = now()
+           WHERE token_hash = $1 AND used_at IS NULL AND expires_at > now()
+           RETURNING user_id, old_email, new_email"#,
+    )
+    .bind(token_hash)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(change) = change else {
+        return Ok(None);
+    };
+
+    let updated = sqlx::query(
+        r#"UPDATE users
+           SET email = $3, email_verified_at = now(), updated_at = now()
+           WHERE id = $1 AND lower(email) = lower($2)"#,
+    )
+    .bind(change.user_id)
+    .bind(&change.old_email)
+    .bind(&change.new_email)
+    .execute(&mut *tx)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    tx.commit().await?;
+    Ok(Some(change))
+}
+
+/// Start (or restart) a TOTP enrollment with a new secret.
+///
+/// A confirmed secret is never overwritten; returns `false` in that case.
+pub async fn upsert_pending_totp(
+    db: &PgPool,
+    user_id: Uuid,
+    secret: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"INSERT INTO user_totp (user_id, secret)
+           VALUES ($1, $2)
+           ON CONFLICT (user_id) DO UPDATE
+           SET secret = EXCLUDED.secret, last_used_step = NULL, created_at = now()
+           WHERE user_totp.confirmed_at IS NULL"#,
+    )
+    .bind(user_id)
+    .bind(secret)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+pub async fn get_totp(db: &PgPool, user_id: Uuid) -> Result<Option<UserTotp>, sqlx::Error> {
+    sqlx::query_as(r#"SELECT secret, confirmed_at FROM user_totp WHERE user_id = $1"#)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+}
+
+/// Record `step` as the last accepted TOTP time step.
+///
+/// Returns `false` when a code for this step (or a later one) was already accepted, so a
+/// code can only ever be used once.
+pub async fn record_totp_step(
+    db: impl PgExecutor<'_>,
+    user_id: Uuid,
+    step: i64,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE user_totp
+           SET last_used_step = $2
+           WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)"#,
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Confirm a pending TOTP enrollment: mark the secret confirmed, enable MFA on the user and
+/// store a fresh set of recovery codes, in one transaction.
+///
+/// Returns `false` if there was no pending enrollment or `step` was already used.
+pub async fn confirm_totp(
+    db: &PgPool,
+    user_id: Uuid,
+    step: i64,
+    recovery_code_hashes: &[String],
+) -> Result<bool, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let confirmed = sqlx::query(
+        r#"UPDATE user_totp
+           SET confirmed_at = now(), last_used_step = $2
+           WHERE user_id = $1 AND confirmed_at IS NULL
+             AND (last_used_step IS NULL OR last_used_step < $2)"#,
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        == 1;
+    if !confirmed {
+        return Ok(false);
+    }
+    sqlx::query(r#"UPDATE users SET mfa_enabled_at = now() WHERE id = $1"#)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(r#"DELETE FROM mfa_recovery_codes WHERE user_id = $1"#)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        r#"INSERT INTO mfa_recovery_codes (user_id, code_hash)
+           SELECT $1, unnest($2::text[])"#,
+    )
+    .bind(user_id)
+    .bind(recovery_code_hashes)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Mark a recovery code as used, if it belongs to the user and is still unused.
+pub async fn consume_recovery_code(
+    db: &PgPool,
+    user_id: Uuid,
+    code_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE mfa_recovery_codes
+           SET used_at = now()
+           WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL"#,
+    )
+    .bind(user_id)
+    .bind(code_hash)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Remove the user's TOTP secret and recovery codes and turn MFA off.
+pub async fn disable_mfa(db: &PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+    sqlx::query(r#"DELETE FROM user_totp WHERE user_id = $1"#)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(r#"DELETE FROM mfa_recovery_codes WHERE user_id = $1"#)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(r#"UPDATE users SET mfa_enabled_at = NULL WHERE id = $1"#)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+/// Latest `blocked_until` still in the future among `keys`, if any.
+pub async fn throttle_blocked_until(
+    db: &PgPool,
+    keys: &[String],
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"SELECT max(blocked_until)
+           FROM login_throttle
+           WHERE key = ANY($1) AND blocked_until > now()"#,
+    )
+    .bind(keys)
+    .fetch_one(db)
+    .await
+}
+
+/// Count a failed attempt for `key` and return the number of recent failures.
+/// The count starts over when the previous failure is older than `window`.
+pub async fn record_throttle_failure(
+    db: &PgPool,
+    key: &str,
+    window: chrono::Duration,
+) -> Result<i32, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"INSERT INTO login_throttle (key, failures, last_failure_at)
+           VALUES ($1, 1, now())
+           ON CONFLICT (key) DO UPDATE
+           SET failures = CASE
+                 WHEN login_throttle.last_failure_at < now() - $2 THEN 1
+                 ELSE login_throttle.failures + 1
+               END,
+               last_failure_at = now()
+           RETURNING failures"#,
+    )
+    .bind(key)
+    .bind(window)
+    .fetch_one(db)
+    .await
+}
+
+pub async fn set_throttle_blocked_until(
+    db: &PgPool,
+    key: &str,
+    blocked_until: chrono::DateTime<chrono::Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"UPDATE login_throttle SET blocked_until = $2 WHERE key = $1"#)
+        .bind(key)
+        .bind(blocked_until)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn clear_throttle(db: impl PgExecutor<'_>, key: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"DELETE FROM login_throttle WHERE key = $1"#)
+        .bind(key)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Drop throttle rows that no longer block anything and whose failures are older than
+/// `window`. Returns how many rows were removed.
+pub async fn purge_login_throttle(
+    db: &PgPool,
+    window: chrono::Duration,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"DELETE FROM login_throttle
+           WHERE (blocked_until IS NULL OR blocked_until < now())
+             AND last_failure_at < now() - $1"#,
+    )
+    .bind(window)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Audit record of a lockout.
+pub async fn insert_lockout(
+    db: &PgPool,
+    scope: &str,
+    subject: &str,
+    user_id: Option<Uuid>,
+    ip: &str,
+    failures: i32,
+    locked_until: chrono::DateTime<chrono::Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO auth_lockouts (scope, subject, user_id, ip, failures, locked_until)
+           VALUES ($1, $2, $3, $4, $5, $6)"#,
+    )
+    .bind(scope)
+    .bind(subject)
+    .bind(user_id)
+    .bind(ip)
+    .bind(failures)
+    .bind(locked_until)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Store a new account unlock token, invalidating any earlier unused one for the user.
+pub async fn create_unlock_token(
+    db: &PgPool,
+    user_id: Uuid,
+    token_hash: &str,
+    ttl: chrono::Duration,
+) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+    sqlx::query(
+        r#"UPDATE account_unlock_tokens
+           SET used_at = now()
+           WHERE user_id = $1 AND used_at IS NULL"#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO account_unlock_tokens (user_id, token_hash, expires_at)
+           VALUES ($1, $2, $3)"#,
+    )
+    .bind(user_id)
+    .bind(token_hash)
+    .bind(chrono::Utc::now() + ttl)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
+
+/// Mark an unlock token as used, if it is still valid, and return its user.
+pub async fn consume_unlock_token(
+    db: &PgPool,
+    token_hash: &str,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"UPDATE account_unlock_tokens
+           SET used_at = now()
+           WHERE token_hash = $1 AND used_at IS NULL AND expires_at > now()
+           RETURNING user_id"#,
+    )
+    .bind(token_hash)
+    .fetch_optional(db)
+    .await
+}
+
+/// Lift an account lockout: clear its throttle row and close its open audit records.
+pub async fn unlock_account(db: &PgPool, user_id: Uuid, key: &str) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+    clear_throttle(&mut *tx, key).await?;
+    sqlx::query(
+        r#"UPDATE auth_lockouts
+           SET unlocked_at = now()
+           WHERE user_id = $1 AND unlocked_at IS NULL"#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
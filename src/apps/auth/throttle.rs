@@ -0,0 +1,262 @@
+//! Brute-force protection for password and second-factor checks.
+//!
+//! Failed attempts are counted per account (the email tried, whether or not it exists)
+//! and per client IP. Each account failure blocks further attempts for an exponentially
+//! growing delay; after too many failures the key is locked out, the lockout is recorded
+//! in `auth_lockouts` and the account owner gets an unlock link by email.
+
+use std::net::IpAddr;
+
+use tracing::{error, info, warn};
+
+use crate::{
+    apps::users::{self, models::User},
+    results::ApiError,
+    AppState,
+};
+
+use super::{emails, sql};
+
+/// Longest a single block may last, however many failures have piled up.
+const MAX_BLOCK_SECS: i64 = 60 * 60 * 24;
+
+/// Thresholds for one kind of key (account or IP).
+#[derive(Debug, Clone, Copy)]
+struct Limit {
+    /// Failures before a lockout
+    max_failures: i32,
+    /// Delay after the first failure, doubled on each further one (0: no backoff)
+    backoff_base_secs: i64,
+}
+
+/// Login throttling configuration:
+/// - `LOGIN_MAX_FAILURES`: failures per account before a lockout (default 5)
+/// - `LOGIN_IP_MAX_FAILURES`: failures per IP before a lockout (default 50)
+/// - `LOGIN_BACKOFF_BASE_SECS`: per-account delay after the first failure, doubled on
+///   each further one (default 1)
+/// - `LOGIN_LOCKOUT_SECS`: first lockout length, doubled on each failure after it
+///   (default 900)
+/// - `LOGIN_FAILURE_WINDOW_SECS`: failures older than this are forgotten (default 3600)
+#[derive(Debug, Clone)]
+pub struct LoginThrottle {
+    account: Limit,
+    ip: Limit,
+    lockout_secs: i64,
+    window: chrono::Duration,
+}
+
+impl LoginThrottle {
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(default)
+        }
+        Self {
+            account: Limit {
+                max_failures: var("LOGIN_MAX_FAILURES", 5),
+                backoff_base_secs: var("LOGIN_BACKOFF_BASE_SECS", 1),
+            },
+            ip: Limit {
+                max_failures: var("LOGIN_IP_MAX_FAILURES", 50),
+                backoff_base_secs: 0,
+            },
+            lockout_secs: var("LOGIN_LOCKOUT_SECS", 60 * 15),
+            window: chrono::Duration::seconds(var("LOGIN_FAILURE_WINDOW_SECS", 60 * 60)),
+        }
+    }
+
+    /// How long to block a key after its `failures`-th failure.
+    fn block_secs(&self, limit: Limit, failures: i32) -> i64 {
+        let secs = if failures >= limit.max_failures {
+            let exponent = (failures - limit.max_failures).clamp(0, 16) as u32;
+            self.lockout_secs.saturating_mul(1 << exponent)
+        } else if limit.backoff_base_secs > 0 {
+            let exponent = (failures - 1).clamp(0, 16) as u32;
+            limit.backoff_base_secs.saturating_mul(1 << exponent)
+        } else {
+            0
+        };
+        secs.min(MAX_BLOCK_SECS)
+    }
+
+    /// Forget throttle rows that no longer matter.
+    pub async fn purge_expired(&self, db: &sqlx::PgPool) -> Result<u64, sqlx::Error> {
+        sql::purge_login_throttle(db, self.window).await
+    }
+}
+
+fn account_key(email: &str) -> String {
+    format!("account:{}", email.trim().to_lowercase())
+}
+
+fn ip_key(ip: IpAddr) -> String {
+    format!("ip:{ip}")
+}
+
+/// `ApiError::TooManyAttempts` while the account or the IP is blocked.
+pub async fn check(state: &AppState, email: &str, ip: IpAddr) -> Result<(), ApiError> {
+    let keys = [account_key(email), ip_key(ip)];
+    let Some(blocked_until) = sql::throttle_blocked_until(&state.db, &keys).await? else {
+        return Ok(());
+    };
+    let remaining_ms = (blocked_until - chrono::Utc::now())
+        .num_milliseconds()
+        .max(0);
+    let retry_after_secs = ((remaining_ms + 999) / 1000).max(1) as u64;
+    info!(target: "api.auth.throttle", %ip, retry_after_secs, "attempt refused: throttled");
+    Err(ApiError::TooManyAttempts { retry_after_secs })
+}
+
+/// Count a failed password or second-factor check for `email` from `ip`.
+pub async fn record_failure(state: &AppState, email: &str, ip: IpAddr) -> Result<(), ApiError> {
+    let policy = &state.login_throttle;
+    let scopes = [
+        ("account", account_key(email), policy.account),
+        ("ip", ip_key(ip), policy.ip),
+    ];
+    for (scope, key, limit) in scopes {
+        let failures = sql::record_throttle_failure(&state.db, &key, policy.window).await?;
+        let block_secs = policy.block_secs(limit, failures);
+        if block_secs == 0 {
+            continue;
+        }
+        let blocked_until = chrono::Utc::now() + chrono::Duration::seconds(block_secs);
+        sql::set_throttle_blocked_until(&state.db, &key, blocked_until).await?;
+
+        if failures >= limit.max_failures {
+            lockout(state, scope, email, ip, failures, blocked_until).await?;
+        }
+    }
+    Ok(())
+}
+
+/// A successful check clears the account's failures (the IP's are left to expire).
+pub async fn record_success(state: &AppState, email: &str) -> Result<(), ApiError> {
+    sql::clear_throttle(&state.db, &account_key(email)).await?;
+    Ok(())
+}
+
+/// Lift a lockout on `user`'s account (unlock link, password reset).
+pub async fn unlock(state: &AppState, user: &User) -> Result<(), ApiError> {
+    sql::unlock_account(&state.db, user.id, &account_key(&user.email)).await?;
+    info!(target: "api.auth.throttle", user_id = %user.id, "account unlocked");
+    Ok(())
+}
+
+async fn lockout(
+    state: &AppState,
+    scope: &str,
+    email: &str,
+    ip: IpAddr,
+    failures: i32,
+    locked_until: chrono::DateTime<chrono::Utc>,
+) -> Result<(), ApiError> {
+    let (subject, user) = match scope {
+        "account" => (
+            email.trim().to_lowercase(),
+            users::sql::find_user_by_email(&state.db, email).await?,
+        ),
+        _ => (ip.to_string(), None),
+    };
+    sql::insert_lockout(
+        &state.db,
+        scope,
+        &subject,
+        user.as_ref().map(|u| u.id),
+        &ip.to_string(),
+        failures,
+        locked_until,
+    )
+    .await?;
+    warn!(
+        target: "api.auth.throttle",
+        scope,
+        %ip,
+        user_id = ?user.as_ref().map(|u| u.id),
+        failures,
+        %locked_until,
+        "lockout"
+    );
+
+    if let Some(user) = user.filter(|u| !u.is_disabled()) {
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = emails::send_account_unlock(&state, &user, locked_until).await {
+                error!(target: "api.auth.throttle", user_id = %user.id, cause = %e, "failed to send unlock email");
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT: Limit = Limit {
+        max_failures: 5,
+        backoff_base_secs: 1,
+    };
+    const IP: Limit = Limit {
+        max_failures: 50,
+        backoff_base_secs: 0,
+    };
+
+    fn throttle(lockout_secs: i64) -> LoginThrottle {
+        LoginThrottle {
+            account: ACCOUNT,
+            ip: IP,
+            lockout_secs,
+            window: chrono::Duration::hours(1),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_from_the_first_failure() {
+        let throttle = throttle(900);
+        let blocks: Vec<_> = (1..5).map(|n| throttle.block_secs(ACCOUNT, n)).collect();
+        assert_eq!(blocks, [1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn no_backoff_before_the_lockout_without_a_base() {
+        let throttle = throttle(900);
+        assert_eq!(throttle.block_secs(IP, 1), 0);
+        assert_eq!(throttle.block_secs(IP, 49), 0);
+        assert_eq!(throttle.block_secs(IP, 50), 900);
+    }
+
+    #[test]
+    fn lockout_starts_at_max_failures_and_doubles() {
+        let throttle = throttle(900);
+        assert_eq!(throttle.block_secs(ACCOUNT, 4), 8);
+        assert_eq!(throttle.block_secs(ACCOUNT, 5), 900);
+        assert_eq!(throttle.block_secs(ACCOUNT, 6), 1800);
+        assert_eq!(throttle.block_secs(ACCOUNT, 7), 3600);
+    }
+
+    #[test]
+    fn backoff_exponent_is_clamped() {
+        let throttle = throttle(900);
+        let limit = Limit {
+            max_failures: 100,
+            backoff_base_secs: 1,
+        };
+        assert_eq!(throttle.block_secs(limit, 17), 1 << 16);
+        assert_eq!(throttle.block_secs(limit, 99), 1 << 16);
+    }
+
+    #[test]
+    fn blocks_never_exceed_max_block_secs() {
+        let short = throttle(900);
+        // 900 * 2^7 is past a day.
+        assert_eq!(short.block_secs(ACCOUNT, 12), MAX_BLOCK_SECS);
+        assert_eq!(short.block_secs(ACCOUNT, 1000), MAX_BLOCK_SECS);
+
+        // No overflow with an absurd lockout length.
+        let long = throttle(i64::MAX / 2);
+        assert_eq!(long.block_secs(ACCOUNT, 30), MAX_BLOCK_SECS);
+    }
+}
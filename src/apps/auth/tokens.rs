@@ -0,0 +1,146 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use data_encoding::BASE32_NOPAD;
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    apps::{sessions, users::models::User},
+    middleware::{client_ip::ClientInfo, jwt, jwt_keys::JwtKeys},
+    results::ApiError,
+    AppState,
+};
+
+use super::{dto::TokenResponse, sql};
+
+/// Default refresh token lifetime: 30 days.
+const DEFAULT_REFRESH_TOKEN_TTL_SECS: i64 = 60 * 60 * 24 * 30;
+
+/// Generate a random, URL-safe opaque token (256 bits of entropy).
+pub fn generate_opaque_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Hash an opaque token for storage/lookup: hex(sha256(token)).
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Number of recovery codes handed out when MFA is enabled.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Generate MFA recovery codes, formatted `xxxxx-xxxxx` (50 bits each).
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 7];
+            OsRng.fill_bytes(&mut bytes);
+            let code = BASE32_NOPAD.encode(&bytes).to_lowercase();
+            format!("{}-{}", &code[..5], &code[5..10])
+        })
+        .collect()
+}
+
+/// Hash a recovery code for storage/lookup, ignoring case, spaces and dashes.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    hash_token(&normalized)
+}
+
+/// Refresh token lifetime, from `REFRESH_TOKEN_TTL_SECS` (default 30 days).
+pub fn refresh_token_ttl() -> chrono::Duration {
+    let secs = std::env::var("REFRESH_TOKEN_TTL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_REFRESH_TOKEN_TTL_SECS);
+    chrono::Duration::seconds(secs)
+}
+
+/// Sign a short-lived access token for `user`'s session `session_id` with the active
+/// signing key.
+pub fn issue_access_token(
+    keys: &JwtKeys,
+    user: &User,
+    session_id: Uuid,
+) -> Result<String, ApiError> {
+    jwt::generate_token(user, keys, jwt::access_token_ttl_secs(), session_id).map_err(|e| {
+        error!(target: "api.auth.token", cause = %e, "JWT token generation failed");
+        ApiError::Internal
+    })
+}
+
+/// Sign an access token letting the admin `admin_id` act as `user`, tied to the admin's
+/// own session `admin_session_id`. Same lifetime as regular access tokens.
+pub fn issue_impersonation_token(
+    keys: &JwtKeys,
+    user: &User,
+    admin_id: Uuid,
+    admin_session_id: Uuid,
+) -> Result<String, ApiError> {
+    jwt::generate_impersonation_token(
+        user,
+        keys,
+        jwt::access_token_ttl_secs(),
+        admin_session_id,
+        admin_id,
+    )
+    .map_err(|e| {
+        error!(target: "api.auth.token", cause = %e, "impersonation token generation failed");
+        ApiError::Internal
+    })
+}
+
+/// Sign the short-lived "MFA pending" token returned by login for accounts with MFA.
+pub fn issue_mfa_pending_token(
+    keys: &JwtKeys,
+    user: &User,
+    device_label: Option<&str>,
+) -> Result<String, ApiError> {
+    jwt::generate_mfa_pending_token(user, keys, device_label).map_err(|e| {
+        error!(target: "api.auth.token", cause = %e, "MFA pending token generation failed");
+        ApiError::Internal
+    })
+}
+
+/// Open a new session for `user` (a new refresh token family) and return its first
+/// refresh token with a fresh access token.
+/// Used by every flow that authenticates a user from scratch (signup, login).
+pub async fn issue_token_pair(
+    state: &AppState,
+    user: &User,
+    device_label: Option<&str>,
+    client: &ClientInfo,
+) -> Result<TokenResponse, ApiError> {
+    let refresh_token = generate_opaque_token();
+    let mut tx = state.db.begin().await?;
+    let session_id = sessions::sql::insert_session(
+        &mut *tx,
+        user.id,
+        device_label,
+        client.user_agent.as_deref(),
+        &client.ip.to_string(),
+    )
+    .await?;
+    sql::insert_refresh_token(
+        &mut *tx,
+        user.id,
+        session_id,
+        &hash_token(&refresh_token),
+        device_label,
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(TokenResponse {
+        token: issue_access_token(&state.jwt, user, session_id)?,
+        refresh_token,
+        expires_in: jwt::access_token_ttl_secs(),
+    })
+}
@@ -0,0 +1,166 @@
+//! TOTP (RFC 6238): HMAC-SHA1, 6 digits, 30 second steps, the defaults every
+//! authenticator app understands.
+
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use sha1::Sha1;
+
+/// Issuer shown by authenticator apps.
+const ISSUER: &str = "Noxel";
+const DIGITS: u32 = 6;
+const STEP_SECS: u64 = 30;
+/// Accepted clock drift, in steps, on either side of the current one.
+const SKEW_STEPS: u64 = 1;
+
+/// Generate a random 160-bit secret, base32 encoded (no padding).
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    OsRng.fill_bytes(&mut bytes);
+    BASE32_NOPAD.encode(&bytes)
+}
+
+/// `otpauth://` URI for QR codes / manual entry in authenticator apps.
+pub fn otpauth_uri(secret: &str, account: &str) -> String {
+    let label = urlencoding::encode(&format!("{ISSUER}:{account}")).into_owned();
+    format!(
+        "otpauth://totp/{label}?secret={secret}&issuer={ISSUER}&algorithm=SHA1&digits={DIGITS}&period={STEP_SECS}"
+    )
+}
+
+/// Current time step.
+pub fn current_step() -> u64 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    now / STEP_SECS
+}
+
+/// Check `code` against `secret` around `step` (± `SKEW_STEPS`).
+///
+/// Returns the matching time step, which the caller records to reject replays.
+pub fn verify_code(secret: &str, code: &str, step: u64) -> Option<u64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    (step.saturating_sub(SKEW_STEPS)..=step + SKEW_STEPS)
+        .find(|&candidate| constant_time_eq(hotp(&key, candidate).as_bytes(), code.as_bytes()))
+}
+
+/// HOTP (RFC 4226) value for `counter`, zero-padded to `DIGITS`.
+fn hotp(key: &[u8], counter: u64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 appendix B seed for HMAC-SHA1: ASCII "12345678901234567890".
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    /// RFC 6238 appendix B (SHA1): unix time and expected 8-digit TOTP. The 6-digit code
+    /// is its last six digits.
+    const RFC_VECTORS: &[(u64, &str)] = &[
+        (59, "94287082"),
+        (1111111109, "07081804"),
+        (1111111111, "14050471"),
+        (1234567890, "89005924"),
+        (2000000000, "69279037"),
+        (20000000000, "65353130"),
+    ];
+
+    fn code_at(step: u64) -> String {
+        hotp(&BASE32_NOPAD.decode(RFC_SECRET.as_bytes()).unwrap(), step)
+    }
+
+    #[test]
+    fn matches_rfc_6238_vectors() {
+        for &(time, expected) in RFC_VECTORS {
+            let step = time / STEP_SECS;
+            assert_eq!(code_at(step), expected[2..], "T = {time}");
+            assert_eq!(
+                verify_code(RFC_SECRET, &expected[2..], step),
+                Some(step),
+                "T = {time}"
+            );
+        }
+    }
+
+    #[test]
+    fn accepts_one_step_of_drift_either_way() {
+        let step = 1_000_000;
+        let code = code_at(step);
+        assert_eq!(verify_code(RFC_SECRET, &code, step - 1), Some(step));
+        assert_eq!(verify_code(RFC_SECRET, &code, step), Some(step));
+        assert_eq!(verify_code(RFC_SECRET, &code, step + 1), Some(step));
+        assert_eq!(verify_code(RFC_SECRET, &code, step - 2), None);
+        assert_eq!(verify_code(RFC_SECRET, &code, step + 2), None);
+    }
+
+    #[test]
+    fn returns_the_code_step_for_replay_checks() {
+        // The same code stays valid for the next step, but keeps reporting its own step:
+        // once that step is recorded as used (`last_used_step`), the replay is refused.
+        let step = 1_000_000;
+        let code = code_at(step);
+        let first = verify_code(RFC_SECRET, &code, step).unwrap();
+        let replay = verify_code(RFC_SECRET, &code, step + 1).unwrap();
+        assert_eq!(first, replay);
+
+        let next = verify_code(RFC_SECRET, &code_at(step + 1), step + 1).unwrap();
+        assert!(next > first);
+    }
+
+    #[test]
+    fn handles_the_first_step() {
+        assert_eq!(verify_code(RFC_SECRET, &code_at(0), 0), Some(0));
+        assert_eq!(verify_code(RFC_SECRET, &code_at(1), 0), Some(1));
+    }
+
+    #[test]
+    fn rejects_malformed_codes() {
+        let step = 1_000_000;
+        let code = code_at(step);
+        assert_eq!(
+            verify_code(RFC_SECRET, &format!(" {code} "), step),
+            Some(step)
+        );
+        assert_eq!(verify_code(RFC_SECRET, &code[..5], step), None);
+        assert_eq!(verify_code(RFC_SECRET, &format!("{code}0"), step), None);
+        assert_eq!(verify_code(RFC_SECRET, "12a456", step), None);
+        assert_eq!(verify_code(RFC_SECRET, "", step), None);
+        assert_eq!(verify_code("not base32!", &code, step), None);
+    }
+
+    #[test]
+    fn generated_secrets_round_trip() {
+        let secret = generate_secret();
+        assert_eq!(BASE32_NOPAD.decode(secret.as_bytes()).unwrap().len(), 20);
+        let step = current_step();
+        let code = hotp(&BASE32_NOPAD.decode(secret.as_bytes()).unwrap(), step);
+        assert_eq!(verify_code(&secret, &code, step), Some(step));
+    }
+}
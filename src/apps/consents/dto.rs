@@ -0,0 +1,20 @@
+use utoipa::ToSchema;
+
+use super::models::{ConsentRecord, LegalDocument};
+
+/// Response of `GET /users/me/consents`.
+#[derive(Debug, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsentsResponse {
+    /// Current document versions the user has not accepted yet: prompt for them
+    pub pending: Vec<LegalDocument>,
+    /// Latest marketing decision (`false` if the user never opted in)
+    pub marketing_opt_in: bool,
+    /// Every decision, oldest first
+    pub records: Vec<ConsentRecord>,
+}
+
+#[derive(Debug, serde::Serialize, ToSchema)]
+pub struct ListDocumentsResponse {
+    pub documents: Vec<LegalDocument>,
+}
@@ -0,0 +1,237 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::{
+    middleware::{auth::AuthContext, client_ip::ClientInfo},
+    results::{ApiError, ApiResult},
+    validation::{FieldError, ValidJson},
+    AppState,
+};
+
+use super::{
+    dto::{ConsentsResponse, ListDocumentsResponse},
+    models::{ConsentKind, ConsentRecordRow, DocumentKind, LegalDocumentRow, NewConsent},
+    requests::AcceptDocumentRequest,
+    sql,
+};
+
+#[utoipa::path(
+    tag = "consents",
+    operation_id = "listLegalDocuments",
+    get,
+    path = "/legal-documents",
+    responses(
+        (status = 200, description = "Current version of each legal document, to accept at signup", body = ListDocumentsResponse)
+    )
+)]
+pub async fn list_documents(
+    State(state): State<AppState>,
+) -> ApiResult<StatusCode, ListDocumentsResponse> {
+    let documents = sql::current_documents(&state.db)
+        .await?
+        .into_iter()
+        .map(LegalDocumentRow::into_document)
+        .collect();
+    Ok((StatusCode::OK, Json(ListDocumentsResponse { documents })))
+}
+
+#[utoipa::path(
+    tag = "consents",
+    operation_id = "getConsents",
+    get,
+    path = "/users/me/consents",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Documents awaiting acceptance, marketing choice and consent history", body = ConsentsResponse),
+        (status = 401, description = "Missing, expired, invalid or revoked token", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn get_consents(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+) -> ApiResult<StatusCode, ConsentsResponse> {
+    let user_id = auth_context.user.id;
+    let pending = sql::pending_documents(&state.db, user_id)
+        .await?
+        .into_iter()
+        .map(LegalDocumentRow::into_document)
+        .collect();
+    let marketing_opt_in = sql::marketing_opt_in(&state.db, user_id).await?;
+    let records = sql::list_consents(&state.db, user_id)
+        .await?
+        .into_iter()
+        .map(ConsentRecordRow::into_record)
+        .collect();
+    Ok((
+        StatusCode::OK,
+        Json(ConsentsResponse {
+            pending,
+            marketing_opt_in,
+            records,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    tag = "consents",
+    operation_id = "acceptLegalDocument",
+    post,
+    path = "/users/me/consents",
+    security(("bearer_auth" = [])),
+    request_body = AcceptDocumentRequest,
+    responses(
+        (status = 204, description = "Acceptance recorded"),
+        (status = 422, description = "Not the current version of the document (see `fields`)", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn accept_document(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    client: ClientInfo,
+    ValidJson(req): ValidJson<AcceptDocumentRequest>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = auth_context.user.id;
+    let document_id = current_document_id(&state.db, req.kind, &req.version, "version").await?;
+    sql::insert_consents(
+        &state.db,
+        user_id,
+        &[NewConsent {
+            kind: req.kind.into(),
+            document_id: Some(document_id),
+            granted: true,
+        }],
+        &client,
+    )
+    .await?;
+    info!(target: "api.consents", %user_id, kind = req.kind.as_str(), version = %req.version, "legal document accepted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    tag = "consents",
+    operation_id = "marketingOptIn",
+    post,
+    path = "/users/me/consents/marketing/opt-in",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 204, description = "Marketing communications allowed")
+    )
+)]
+pub async fn marketing_opt_in(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    client: ClientInfo,
+) -> Result<StatusCode, ApiError> {
+    record_marketing(&state, &auth_context, &client, true).await
+}
+
+#[utoipa::path(
+    tag = "consents",
+    operation_id = "marketingOptOut",
+    post,
+    path = "/users/me/consents/marketing/opt-out",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 204, description = "Marketing communications refused")
+    )
+)]
+pub async fn marketing_opt_out(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    client: ClientInfo,
+) -> Result<StatusCode, ApiError> {
+    record_marketing(&state, &auth_context, &client, false).await
+}
+
+async fn record_marketing(
+    state: &AppState,
+    auth_context: &AuthContext,
+    client: &ClientInfo,
+    granted: bool,
+) -> Result<StatusCode, ApiError> {
+    let user_id = auth_context.user.id;
+    sql::insert_consents(
+        &state.db,
+        user_id,
+        &[NewConsent {
+            kind: ConsentKind::Marketing,
+            document_id: None,
+            granted,
+        }],
+        client,
+    )
+    .await?;
+    info!(target: "api.consents", %user_id, granted, "marketing consent recorded");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Consents given with a signup: the accepted versions must be the current ones
+/// (`422` on `acceptedTermsVersion` / `acceptedPrivacyVersion` otherwise).
+pub(crate) async fn signup_consents(
+    db: &PgPool,
+    terms_version: &str,
+    privacy_version: &str,
+    marketing_opt_in: bool,
+) -> Result<Vec<NewConsent>, ApiError> {
+    let current = sql::current_documents(db).await?;
+    let mut consents = Vec::with_capacity(3);
+    let mut errors = Vec::new();
+    for (kind, version, path) in [
+        (DocumentKind::Terms, terms_version, "acceptedTermsVersion"),
+        (
+            DocumentKind::Privacy,
+            privacy_version,
+            "acceptedPrivacyVersion",
+        ),
+    ] {
+        match check_current(&current, kind, version, path) {
+            Ok(document_id) => consents.push(NewConsent {
+                kind: kind.into(),
+                document_id: Some(document_id),
+                granted: true,
+            }),
+            Err(e) => errors.push(e),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::Validation(errors));
+    }
+    consents.push(NewConsent {
+        kind: ConsentKind::Marketing,
+        document_id: None,
+        granted: marketing_opt_in,
+    });
+    Ok(consents)
+}
+
+/// Id of the current version of `kind`, if it is `version`.
+async fn current_document_id(
+    db: &PgPool,
+    kind: DocumentKind,
+    version: &str,
+    path: &str,
+) -> Result<uuid::Uuid, ApiError> {
+    let current = sql::current_documents(db).await?;
+    check_current(&current, kind, version, path).map_err(|e| ApiError::Validation(vec![e]))
+}
+
+fn check_current(
+    current: &[LegalDocumentRow],
+    kind: DocumentKind,
+    version: &str,
+    path: &str,
+) -> Result<uuid::Uuid, FieldError> {
+    let document = current.iter().find(|d| d.kind == kind.as_str());
+    match document {
+        Some(d) if d.version == version.trim() => Ok(d.id),
+        _ => Err(FieldError {
+            path: path.to_string(),
+            code: "outdated_version",
+            message: match document {
+                Some(d) => format!("must be the current version ({})", d.version),
+                None => format!("no {} document has been published", kind.as_str()),
+            },
+        }),
+    }
+}
@@ -0,0 +1,10 @@
+//! Legal documents (terms of service, privacy policy) and the consent ledger.
+
+pub mod dto;
+pub mod handlers;
+pub mod models;
+pub mod requests;
+pub mod routes;
+pub mod sql;
+
+pub use routes::{public_router, router};
@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Kinds of legal documents users must accept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentKind {
+    /// Terms of service
+    Terms,
+    /// Privacy policy
+    Privacy,
+}
+
+impl DocumentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentKind::Terms => "terms",
+            DocumentKind::Privacy => "privacy",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "terms" => Some(DocumentKind::Terms),
+            "privacy" => Some(DocumentKind::Privacy),
+            _ => None,
+        }
+    }
+}
+
+/// A published version of a legal document.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LegalDocument {
+    pub id: Uuid,
+    pub kind: DocumentKind,
+    #[schema(example = "2026-03")]
+    pub version: String,
+    #[schema(example = "Termos de Uso")]
+    pub title: String,
+    /// Full text
+    #[schema(nullable = true, example = "https://example.com/termos")]
+    pub url: Option<String>,
+    /// Current from this instant until a newer version is published
+    pub published_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Row returned from database for LegalDocument (with kind as string)
+#[derive(Debug, Clone, FromRow)]
+pub struct LegalDocumentRow {
+    pub id: Uuid,
+    pub kind: String,
+    pub version: String,
+    pub title: String,
+    pub url: Option<String>,
+    pub published_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl LegalDocumentRow {
+    pub fn into_document(self) -> LegalDocument {
+        LegalDocument {
+            id: self.id,
+            kind: DocumentKind::from_str(&self.kind).unwrap_or(DocumentKind::Terms),
+            version: self.version,
+            title: self.title,
+            url: self.url,
+            published_at: self.published_at,
+        }
+    }
+}
+
+/// What a consent record is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsentKind {
+    Terms,
+    Privacy,
+    /// Marketing communications (opt-in)
+    Marketing,
+}
+
+impl ConsentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsentKind::Terms => "terms",
+            ConsentKind::Privacy => "privacy",
+            ConsentKind::Marketing => "marketing",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "terms" => Some(ConsentKind::Terms),
+            "privacy" => Some(ConsentKind::Privacy),
+            "marketing" => Some(ConsentKind::Marketing),
+            _ => None,
+        }
+    }
+}
+
+impl From<DocumentKind> for ConsentKind {
+    fn from(kind: DocumentKind) -> Self {
+        match kind {
+            DocumentKind::Terms => ConsentKind::Terms,
+            DocumentKind::Privacy => ConsentKind::Privacy,
+        }
+    }
+}
+
+/// A decision to be recorded in the consent ledger.
+#[derive(Debug, Clone, Copy)]
+pub struct NewConsent {
+    pub kind: ConsentKind,
+    /// Accepted document version (terms and privacy only)
+    pub document_id: Option<Uuid>,
+    pub granted: bool,
+}
+
+/// An entry of the user's consent ledger.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsentRecord {
+    pub id: Uuid,
+    pub kind: ConsentKind,
+    /// Accepted document version (terms and privacy only)
+    #[schema(nullable = true, example = "2026-03")]
+    pub document_version: Option<String>,
+    /// `false` for a marketing opt-out
+    pub granted: bool,
+    #[schema(nullable = true)]
+    pub ip: Option<String>,
+    #[schema(nullable = true)]
+    pub user_agent: Option<String>,
+    pub accepted_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Row returned from database for ConsentRecord (with kind as string)
+#[derive(Debug, Clone, FromRow)]
+pub struct ConsentRecordRow {
+    pub id: Uuid,
+    pub kind: String,
+    pub document_version: Option<String>,
+    pub granted: bool,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub accepted_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ConsentRecordRow {
+    pub fn into_record(self) -> ConsentRecord {
+        ConsentRecord {
+            id: self.id,
+            kind: ConsentKind::from_str(&self.kind).unwrap_or(ConsentKind::Marketing),
+            document_version: self.document_version,
+            granted: self.granted,
+            ip: self.ip,
+            user_agent: self.user_agent,
+            accepted_at: self.accepted_at,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use utoipa::ToSchema;
+
+use crate::validation::{Validate, Validator};
+
+use super::models::DocumentKind;
+
+/// Longest `legal_documents.version` accepted.
+pub const MAX_VERSION_CHARS: usize = 64;
+/// Longest `legal_documents.title` accepted.
+pub const MAX_TITLE_CHARS: usize = 255;
+
+/// Request body for `POST /users/me/consents`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceptDocumentRequest {
+    pub kind: DocumentKind,
+
+    /// Must be the current version of the document
+    #[schema(nullable = false, example = "2026-03")]
+    pub version: String,
+}
+
+impl Validate for AcceptDocumentRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("version", &self.version).required();
+    }
+}
+
+/// Request body for `POST /admin/legal-documents`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishDocumentRequest {
+    pub kind: DocumentKind,
+
+    #[schema(nullable = false, max_length = 64, example = "2026-03")]
+    pub version: String,
+
+    #[schema(nullable = false, max_length = 255, example = "Termos de Uso")]
+    pub title: String,
+
+    /// Where the full text is published
+    #[schema(nullable = true, example = "https://example.com/termos")]
+    pub url: Option<String>,
+
+    /// When the version becomes current (default now). Users are asked to accept it
+    /// from then on.
+    #[schema(nullable = true)]
+    pub published_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Validate for PublishDocumentRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("version", &self.version)
+            .required()
+            .max_chars(MAX_VERSION_CHARS);
+        v.field("title", &self.title)
+            .required()
+            .max_chars(MAX_TITLE_CHARS);
+        v.field("url", &self.url).not_blank();
+    }
+}
@@ -0,0 +1,31 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::AppState;
+
+use super::handlers;
+
+/// Consents of the current user, merged into the users account router (which applies
+/// `require_auth` and `require_session`: only the user can consent).
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/me/consents",
+            get(handlers::get_consents).post(handlers::accept_document),
+        )
+        .route(
+            "/me/consents/marketing/opt-in",
+            post(handlers::marketing_opt_in),
+        )
+        .route(
+            "/me/consents/marketing/opt-out",
+            post(handlers::marketing_opt_out),
+        )
+}
+
+/// Current legal documents, public (signup forms need them), mounted at the top level.
+pub fn public_router() -> Router<AppState> {
+    Router::new().route("/legal-documents", get(handlers::list_documents))
+}
@@ -0,0 +1,135 @@
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::middleware::client_ip::ClientInfo;
+
+use super::models::{ConsentKind, ConsentRecordRow, DocumentKind, LegalDocumentRow, NewConsent};
+
+/// The current version of each kind of document (the latest already published).
+pub async fn current_documents(db: &PgPool) -> Result<Vec<LegalDocumentRow>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT DISTINCT ON (kind) id, kind, version, title, url, published_at
+           FROM legal_documents
+           WHERE published_at <= now()
+           ORDER BY kind, published_at DESC"#,
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// Every version of every document, newest first (scheduled ones included).
+pub async fn list_documents(db: &PgPool) -> Result<Vec<LegalDocumentRow>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT id, kind, version, title, url, published_at
+           FROM legal_documents
+           ORDER BY published_at DESC, kind"#,
+    )
+    .fetch_all(db)
+    .await
+}
+
+pub async fn insert_document(
+    db: impl PgExecutor<'_>,
+    kind: DocumentKind,
+    version: &str,
+    title: &str,
+    url: Option<&str>,
+    published_at: chrono::DateTime<chrono::Utc>,
+) -> Result<LegalDocumentRow, sqlx::Error> {
+    sqlx::query_as(
+        r#"INSERT INTO legal_documents (kind, version, title, url, published_at)
+           VALUES ($1, $2, $3, $4, $5)
+           RETURNING id, kind, version, title, url, published_at"#,
+    )
+    .bind(kind.as_str())
+    .bind(version)
+    .bind(title)
+    .bind(url)
+    .bind(published_at)
+    .fetch_one(db)
+    .await
+}
+
+/// Current documents the user has not accepted yet.
+pub async fn pending_documents(
+    db: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<LegalDocumentRow>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT d.id, d.kind, d.version, d.title, d.url, d.published_at
+           FROM (
+             SELECT DISTINCT ON (kind) id, kind, version, title, url, published_at
+             FROM legal_documents
+             WHERE published_at <= now()
+             ORDER BY kind, published_at DESC
+           ) d
+           WHERE NOT EXISTS (
+             SELECT 1 FROM consent_records c
+             WHERE c.user_id = $1 AND c.document_id = d.id AND c.granted
+           )
+           ORDER BY d.kind"#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Append decisions to the user's consent ledger.
+pub async fn insert_consents(
+    db: impl PgExecutor<'_>,
+    user_id: Uuid,
+    consents: &[NewConsent],
+    client: &ClientInfo,
+) -> Result<(), sqlx::Error> {
+    let kinds: Vec<&str> = consents.iter().map(|c| c.kind.as_str()).collect();
+    let document_ids: Vec<Option<Uuid>> = consents.iter().map(|c| c.document_id).collect();
+    let granted: Vec<bool> = consents.iter().map(|c| c.granted).collect();
+    sqlx::query(
+        r#"INSERT INTO consent_records (user_id, kind, document_id, granted, ip, user_agent)
+           SELECT $1, kind, document_id, granted, $5, $6
+           FROM unnest($2::text[], $3::uuid[], $4::boolean[]) AS c (kind, document_id, granted)"#,
+    )
+    .bind(user_id)
+    .bind(&kinds)
+    .bind(&document_ids)
+    .bind(&granted)
+    .bind(client.ip.to_string())
+    .bind(&client.user_agent)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// The user's ledger, oldest first.
+pub async fn list_consents(
+    db: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<ConsentRecordRow>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT c.id, c.kind, d.version AS document_version, c.granted, c.ip, c.user_agent,
+                  c.accepted_at
+           FROM consent_records c
+           LEFT JOIN legal_documents d ON d.id = c.document_id
+           WHERE c.user_id = $1
+           ORDER BY c.accepted_at, c.id"#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Whether the user's latest marketing decision is an opt-in (no decision: opted out).
+pub async fn marketing_opt_in(db: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let granted: Option<bool> = sqlx::query_scalar(
+        r#"SELECT granted
+           FROM consent_records
+           WHERE user_id = $1 AND kind = $2
+           ORDER BY accepted_at DESC, id DESC
+           LIMIT 1"#,
+    )
+    .bind(user_id)
+    .bind(ConsentKind::Marketing.as_str())
+    .fetch_optional(db)
+    .await?;
+    Ok(granted.unwrap_or(false))
+}
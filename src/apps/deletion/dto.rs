@@ -0,0 +1,35 @@
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A pending deletion request of the current user.
+#[derive(Debug, Clone, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletionStatus {
+    pub requested_at: chrono::DateTime<chrono::Utc>,
+    /// The account is anonymized after this; until then the request can be cancelled
+    pub scheduled_for: chrono::DateTime<chrono::Utc>,
+}
+
+/// A pending deletion, as listed to admins.
+#[derive(Debug, Clone, serde::Serialize, ToSchema, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingDeletion {
+    pub user_id: Uuid,
+    #[schema(example = "johnson@noxel.com")]
+    pub email: String,
+    pub requested_at: chrono::DateTime<chrono::Utc>,
+    pub scheduled_for: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, serde::Serialize, ToSchema)]
+pub struct ListPendingDeletionsResponse {
+    /// Oldest scheduled first
+    pub deletions: Vec<PendingDeletion>,
+}
+
+/// Response of `POST /admin/deletions/process`.
+#[derive(Debug, serde::Serialize, ToSchema)]
+pub struct ProcessDeletionsResponse {
+    /// Users anonymized by this run
+    pub anonymized: Vec<Uuid>,
+}
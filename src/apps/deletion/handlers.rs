@@ -0,0 +1,114 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use tracing::{error, info};
+
+use crate::{
+    apps::users::{self, models::User},
+    mailer::Email,
+    middleware::{auth::AuthContext, client_ip::ClientIp},
+    results::{ApiError, ApiResult},
+    validation::ValidJson,
+    AppState,
+};
+
+use super::{dto::DeletionStatus, grace_period, requests::RequestDeletionRequest, sql};
+
+#[utoipa::path(
+    tag = "users",
+    operation_id = "getAccountDeletion",
+    get,
+    path = "/users/me/deletion",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Pending deletion request of the current user", body = DeletionStatus),
+        (status = 404, description = "No pending deletion request", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn get_deletion(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+) -> ApiResult<StatusCode, DeletionStatus> {
+    let status = sql::get_deletion(&state.db, auth_context.user.id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok((StatusCode::OK, Json(status)))
+}
+
+#[utoipa::path(
+    tag = "users",
+    operation_id = "requestAccountDeletion",
+    post,
+    path = "/users/me/deletion",
+    security(("bearer_auth" = [])),
+    request_body = RequestDeletionRequest,
+    responses(
+        (status = 202, description = "Deletion scheduled: the account's personal data is anonymized \
+            once the grace period ends, unless cancelled before. Repeating the request keeps the \
+            original schedule", body = DeletionStatus),
+        (status = 403, description = "Wrong current password, or called with an API key", body = crate::results::ApiErrorBody),
+        (status = 422, description = "Validation failed (see `fields`)", body = crate::results::ApiErrorBody),
+        (status = 429, description = "Too many failed attempts (see `Retry-After`)", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn request_deletion(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    ValidJson(req): ValidJson<RequestDeletionRequest>,
+) -> ApiResult<StatusCode, DeletionStatus> {
+    let user = auth_context.user;
+    users::handlers::check_current_password(&state, &user, &req.current_password, ip).await?;
+
+    let status = sql::schedule_deletion(&state.db, user.id, grace_period()).await?;
+    info!(target: "api.deletion", user_id = %user.id, scheduled_for = %status.scheduled_for, status = 202, "account deletion requested");
+    spawn_deletion_notice(&state, user, status.clone());
+    Ok((StatusCode::ACCEPTED, Json(status)))
+}
+
+#[utoipa::path(
+    tag = "users",
+    operation_id = "cancelAccountDeletion",
+    delete,
+    path = "/users/me/deletion",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 204, description = "Deletion request cancelled"),
+        (status = 404, description = "No pending deletion request", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn cancel_deletion(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = auth_context.user.id;
+    if !sql::cancel_deletion(&state.db, user_id).await? {
+        return Err(ApiError::NotFound);
+    }
+    info!(target: "api.deletion", %user_id, status = 204, "account deletion cancelled");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Tell the user when their account will be anonymized and how to cancel.
+fn spawn_deletion_notice(state: &AppState, user: User, status: DeletionStatus) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        let sent = state
+            .mailer
+            .send(Email {
+                to: user.email.clone(),
+                subject: "Exclusão de conta agendada".to_string(),
+                body: format!(
+                    "Olá, {}.\n\nRecebemos seu pedido de exclusão de conta. Em {} (UTC) seus \
+                     dados pessoais serão anonimizados de forma irreversível; ingressos e \
+                     registros financeiros são mantidos sem identificá-lo.\n\n\
+                     Até lá, você pode cancelar o pedido entrando na sua conta. \
+                     Se você não fez este pedido, entre e cancele-o e troque sua senha.",
+                    user.full_name,
+                    status.scheduled_for.format("%d/%m/%Y %H:%M")
+                ),
+            })
+            .await;
+        if let Err(e) = sent {
+            error!(target: "api.deletion", user_id = %user.id, cause = %e, "failed to send account deletion notice");
+        }
+    });
+}
@@ -0,0 +1,38 @@
+//! Processing of deletions whose grace period has ended, run by admins
+//! (`POST /admin/deletions/process`) or from the command line (`process-deletions`).
+
+use std::net::IpAddr;
+
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::apps::{auth::tokens, users};
+
+use super::sql;
+
+/// Anonymize every user whose deletion is due. Returns the anonymized user ids.
+///
+/// Each user is anonymized in its own transaction, together with its `admin_audit_log`
+/// entry (on behalf of `admin`, id and IP; `None` from the command line): a failure is
+/// logged and the run goes on with the next user (the failed one stays pending for the
+/// next run).
+pub async fn process_due(db: &PgPool, admin: Option<(Uuid, IpAddr)>) -> anyhow::Result<Vec<Uuid>> {
+    let mut anonymized = Vec::new();
+    for user_id in sql::list_due(db).await? {
+        // Nobody knows this password: the account can never be logged into again.
+        let password_hash = users::sql::hash_password(&tokens::generate_opaque_token())
+            .map_err(|e| anyhow::anyhow!("password hashing failed: {e}"))?;
+        match sql::anonymize_user(db, user_id, &password_hash, admin).await {
+            Ok(true) => {
+                info!(target: "api.deletion", %user_id, "account anonymized");
+                anonymized.push(user_id);
+            }
+            Ok(false) => {}
+            Err(e) => {
+                error!(target: "api.deletion", %user_id, cause = %e, "account anonymization failed")
+            }
+        }
+    }
+    Ok(anonymized)
+}
@@ -0,0 +1,21 @@
+//! Account deletion (LGPD right to erasure): a request, a grace period during which it can
+//! be cancelled, then irreversible anonymization of the account's personal data.
+
+pub mod dto;
+pub mod handlers;
+pub mod jobs;
+pub mod requests;
+pub mod routes;
+pub mod sql;
+
+pub use routes::router;
+
+/// Time between a deletion request and the anonymization of the account
+/// (`ACCOUNT_DELETION_GRACE_DAYS`, default 30).
+pub fn grace_period() -> chrono::Duration {
+    let days = std::env::var("ACCOUNT_DELETION_GRACE_DAYS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(30);
+    chrono::Duration::days(days)
+}
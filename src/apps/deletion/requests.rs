@@ -0,0 +1,18 @@
+use utoipa::ToSchema;
+
+use crate::validation::{Validate, Validator};
+
+/// Request body for `POST /users/me/deletion`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestDeletionRequest {
+    #[schema(nullable = false, example = "correct horse battery staple")]
+    pub current_password: String,
+}
+
+impl Validate for RequestDeletionRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("currentPassword", &self.current_password)
+            .required();
+    }
+}
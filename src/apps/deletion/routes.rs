@@ -0,0 +1,16 @@
+use axum::{routing::get, Router};
+
+use crate::AppState;
+
+use super::handlers;
+
+/// Deletion request of the current user, merged into the users account router (which
+/// applies `require_auth` and `require_session`).
+pub fn router() -> Router<AppState> {
+    Router::new().route(
+        "/me/deletion",
+        get(handlers::get_deletion)
+            .post(handlers::request_deletion)
+            .delete(handlers::cancel_deletion),
+    )
+}
@@ -0,0 +1,233 @@
+use std::net::IpAddr;
+
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::apps::admin::{self, models::AdminAction};
+
+use super::dto::{DeletionStatus, PendingDeletion};
+
+/// Placeholder CPF of anonymized accounts: all digits equal, so never a real CPF.
+const ANONYMIZED_GOV_ID: &str = "00000000000";
+
+/// Placeholder birth date of anonymized attendees (the column is required).
+const ANONYMIZED_BIRTH_DATE: &str = "1900-01-01";
+
+/// The user's pending deletion request, if any.
+pub async fn get_deletion(
+    db: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<DeletionStatus>, sqlx::Error> {
+    let row: Option<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> =
+        sqlx::query_as(
+            r#"SELECT deletion_requested_at, deletion_scheduled_for
+               FROM users
+               WHERE id = $1
+                 AND deletion_scheduled_for IS NOT NULL
+                 AND anonymized_at IS NULL"#,
+        )
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+    Ok(row.map(|(requested_at, scheduled_for)| DeletionStatus {
+        requested_at,
+        scheduled_for,
+    }))
+}
+
+/// Schedule the user's anonymization after `grace`. A pending request keeps its dates.
+pub async fn schedule_deletion(
+    db: &PgPool,
+    user_id: Uuid,
+    grace: chrono::Duration,
+) -> Result<DeletionStatus, sqlx::Error> {
+    let (requested_at, scheduled_for): (
+        chrono::DateTime<chrono::Utc>,
+        chrono::DateTime<chrono::Utc>,
+    ) = sqlx::query_as(
+        r#"UPDATE users
+           SET deletion_requested_at = COALESCE(deletion_requested_at, now()),
+               deletion_scheduled_for = COALESCE(deletion_scheduled_for, $2)
+           WHERE id = $1
+           RETURNING deletion_requested_at, deletion_scheduled_for"#,
+    )
+    .bind(user_id)
+    .bind(chrono::Utc::now() + grace)
+    .fetch_one(db)
+    .await?;
+    Ok(DeletionStatus {
+        requested_at,
+        scheduled_for,
+    })
+}
+
+/// Cancel the user's pending deletion. Returns `false` if there was none.
+pub async fn cancel_deletion(db: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE users
+           SET deletion_requested_at = NULL, deletion_scheduled_for = NULL
+           WHERE id = $1 AND deletion_scheduled_for IS NOT NULL AND anonymized_at IS NULL"#,
+    )
+    .bind(user_id)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Every pending deletion, oldest scheduled first.
+pub async fn list_pending(db: &PgPool) -> Result<Vec<PendingDeletion>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT id AS user_id, email, deletion_requested_at AS requested_at,
+                  deletion_scheduled_for AS scheduled_for
+           FROM users
+           WHERE deletion_scheduled_for IS NOT NULL AND anonymized_at IS NULL
+           ORDER BY deletion_scheduled_for, id"#,
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// Users whose grace period has ended.
+pub async fn list_due(db: &PgPool) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"SELECT id
+           FROM users
+           WHERE deletion_scheduled_for <= now() AND anonymized_at IS NULL
+           ORDER BY deletion_scheduled_for, id"#,
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// Whether the account has been anonymized.
+pub async fn is_anonymized(db: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"SELECT EXISTS (SELECT 1 FROM users WHERE id = $1 AND anonymized_at IS NOT NULL)"#,
+    )
+    .bind(user_id)
+    .fetch_one(db)
+    .await
+}
+
+/// Irreversibly anonymize a user whose deletion is due, in one transaction.
+///
+/// The `users` row and its role data stay (tickets and events reference them) with every
+/// personal field replaced; the addresses, sessions, credentials and pending tokens are
+/// deleted. The erasure is recorded in `admin_audit_log` (by `admin`, or with no admin from
+/// the command line). Returns `false` if the deletion was cancelled or already processed
+/// meanwhile.
+pub async fn anonymize_user(
+    db: &PgPool,
+    user_id: Uuid,
+    password_hash: &str,
+    admin: Option<(Uuid, IpAddr)>,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let email: Option<String> = sqlx::query_scalar(
+        r#"SELECT email
+           FROM users
+           WHERE id = $1 AND deletion_scheduled_for <= now() AND anonymized_at IS NULL
+           FOR UPDATE"#,
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(email) = email else {
+        return Ok(false);
+    };
+
+    sqlx::query(
+        r#"UPDATE users
+           SET full_name = 'Usuário removido',
+               email = 'removido-' || id || '@anonimizado.invalid',
+               gov_identification = $2,
+               password_hash = $3,
+               email_verified_at = NULL,
+               mfa_enabled_at = NULL,
+               disabled_at = COALESCE(disabled_at, now()),
+               tokens_valid_after = now(),
+               anonymized_at = now(),
+               updated_at = now()
+           WHERE id = $1"#,
+    )
+    .bind(user_id)
+    .bind(ANONYMIZED_GOV_ID)
+    .bind(password_hash)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"UPDATE consumer_data
+           SET phone = '', birth_date = $2::date
+           WHERE user_id = $1"#,
+    )
+    .bind(user_id)
+    .bind(ANONYMIZED_BIRTH_DATE)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(r#"UPDATE organizer_data SET apelido = NULL WHERE user_id = $1"#)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for table in [
+        "user_address",
+        "refresh_tokens",
+        "sessions",
+        "api_keys",
+        "user_totp",
+        "mfa_recovery_codes",
+        "password_reset_tokens",
+        "email_verification_tokens",
+        "email_change_tokens",
+        "account_unlock_tokens",
+        "data_exports",
+    ] {
+        delete_user_rows(&mut tx, table, user_id).await?;
+    }
+
+    // The consent ledger is kept as proof of what was accepted, without who accepted it.
+    sqlx::query(r#"UPDATE consent_records SET ip = NULL, user_agent = NULL WHERE user_id = $1"#)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    // Throttling and lockout records are keyed by the email address.
+    sqlx::query(r#"DELETE FROM login_throttle WHERE key = 'account:' || lower($1)"#)
+        .bind(&email)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        r#"UPDATE auth_lockouts
+           SET subject = 'anonimizado', ip = NULL
+           WHERE user_id = $1"#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    admin::sql::insert_audit_entry(
+        &mut *tx,
+        admin.map(|(id, _)| id),
+        Some(user_id),
+        AdminAction::Anonymize,
+        serde_json::json!({}),
+        admin.map(|(_, ip)| ip),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+async fn delete_user_rows(
+    tx: &mut Transaction<'_, Postgres>,
+    table: &str,
+    user_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!("DELETE FROM {table} WHERE user_id = $1"))
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,162 @@
+//! Serialization of a `UserExport` into the downloadable file.
+
+use std::io::Write;
+
+use serde_json::{Map, Value};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use super::models::{ExportFormat, UserExport};
+
+/// The export as a file of the given format.
+pub fn render(export: &UserExport, format: ExportFormat) -> anyhow::Result<Vec<u8>> {
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_vec_pretty(export)?),
+        ExportFormat::Zip => zip_of_csvs(export),
+    }
+}
+
+/// Suggested file name for the download.
+pub fn file_name(format: ExportFormat) -> String {
+    format!(
+        "noxel-dados-{}.{}",
+        chrono::Utc::now().format("%Y%m%d"),
+        format.as_str()
+    )
+}
+
+/// One CSV per section of the export (`user.csv`, `tickets.csv`, ...), plus the full JSON
+/// document. Sections without data are left out.
+fn zip_of_csvs(export: &UserExport) -> anyhow::Result<Vec<u8>> {
+    let Value::Object(sections) = serde_json::to_value(export)? else {
+        anyhow::bail!("export did not serialize to an object");
+    };
+
+    let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    for (name, value) in &sections {
+        let rows: Vec<&Map<String, Value>> = match value {
+            Value::Object(row) => vec![row],
+            Value::Array(items) => items.iter().filter_map(Value::as_object).collect(),
+            _ => continue,
+        };
+        if rows.is_empty() {
+            continue;
+        }
+        zip.start_file(format!("{}.csv", snake_case(name)), options)?;
+        zip.write_all(to_csv(&rows).as_bytes())?;
+    }
+    zip.start_file("export.json", options)?;
+    zip.write_all(&serde_json::to_vec_pretty(export)?)?;
+    Ok(zip.finish()?.into_inner())
+}
+
+/// RFC 4180 CSV with a header row made of every key found in `rows`, in first-seen order.
+fn to_csv(rows: &[&Map<String, Value>]) -> String {
+    let mut columns: Vec<&str> = Vec::new();
+    for row in rows {
+        for key in row.keys() {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+
+    let mut csv = String::new();
+    push_record(&mut csv, columns.iter().map(|c| snake_case(c)));
+    for row in rows {
+        push_record(
+            &mut csv,
+            columns.iter().map(|c| match row.get(*c) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(s)) => s.clone(),
+                // Nested values (e.g. API key scopes) stay JSON.
+                Some(other) => other.to_string(),
+            }),
+        );
+    }
+    csv
+}
+
+fn push_record(csv: &mut String, fields: impl Iterator<Item = String>) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            csv.push(',');
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(&field);
+        }
+    }
+    csv.push_str("\r\n");
+}
+
+/// `organizerData` -> `organizer_data`.
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            out.push('_');
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn rows(values: &[Value]) -> Vec<&Map<String, Value>> {
+        values.iter().map(|v| v.as_object().unwrap()).collect()
+    }
+
+    #[test]
+    fn quotes_fields_per_rfc_4180() {
+        let values = [json!({
+            "a": "plain",
+            "b": "with, comma",
+            "c": "with \"quotes\"",
+            "d": "two\nlines",
+            "e": "carriage\rreturn",
+        })];
+        assert_eq!(
+            to_csv(&rows(&values)),
+            "a,b,c,d,e\r\n\
+             plain,\"with, comma\",\"with \"\"quotes\"\"\",\"two\nlines\",\"carriage\rreturn\"\r\n"
+        );
+    }
+
+    #[test]
+    fn header_covers_every_key() {
+        let values = [
+            json!({ "id": 1, "name": "a" }),
+            json!({ "id": 2, "lastUsedAt": "x" }),
+        ];
+        assert_eq!(
+            to_csv(&rows(&values)),
+            "id,name,last_used_at\r\n1,a,\r\n2,,x\r\n"
+        );
+    }
+
+    #[test]
+    fn nulls_are_empty_and_nested_values_stay_json() {
+        let values = [json!({ "a": null, "b": ["events:read", "sales:read"], "c": true })];
+        assert_eq!(
+            to_csv(&rows(&values)),
+            "a,b,c\r\n,\"[\"\"events:read\"\",\"\"sales:read\"\"]\",true\r\n"
+        );
+    }
+
+    #[test]
+    fn snake_cases_names() {
+        assert_eq!(snake_case("organizerData"), "organizer_data");
+        assert_eq!(snake_case("user"), "user");
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+/// Data export configuration:
+/// - `EXPORT_LINK_SECRET`: HMAC key of the download links of background exports. When
+///   unset a random key is used, so links stop working on restart.
+/// - `EXPORT_INLINE_MAX_ROWS`: accounts with more tickets and sessions than this get a
+///   background export instead of an immediate download (default 500)
+/// - `EXPORT_TTL_HOURS`: how long a background export can be downloaded (default 24)
+/// - `EXPORT_MAX_JOBS`: background exports built at the same time; the others wait
+///   (default 2)
+/// - `API_BASE_URL`: public base URL of this API, used to build download links
+///   (default `http://localhost:8080`)
+#[derive(Clone)]
+pub struct ExportConfig {
+    link_secret: Arc<[u8]>,
+    pub inline_max_rows: i64,
+    pub ttl: chrono::Duration,
+    /// One permit per background export being built
+    pub jobs: Arc<Semaphore>,
+    base_url: String,
+}
+
+impl ExportConfig {
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(default)
+        }
+        let link_secret: Arc<[u8]> = match std::env::var("EXPORT_LINK_SECRET") {
+            Ok(secret) if !secret.is_empty() => secret.into_bytes().into(),
+            _ => {
+                tracing::warn!(
+                    "EXPORT_LINK_SECRET not set, export download links will not survive a restart"
+                );
+                let mut secret = [0u8; 32];
+                rand::thread_rng().fill_bytes(&mut secret);
+                secret.to_vec().into()
+            }
+        };
+        let base_url = std::env::var("API_BASE_URL")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| s.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| "http://localhost:8080".to_string());
+
+        Self {
+            link_secret,
+            inline_max_rows: var("EXPORT_INLINE_MAX_ROWS", 500),
+            ttl: chrono::Duration::hours(var("EXPORT_TTL_HOURS", 24)),
+            jobs: Arc::new(Semaphore::new(var("EXPORT_MAX_JOBS", 2usize).max(1))),
+            base_url,
+        }
+    }
+
+    /// Signed, unauthenticated download link of export `id`, valid until `expires_at`.
+    pub fn download_url(&self, id: Uuid, expires_at: chrono::DateTime<chrono::Utc>) -> String {
+        let expires = expires_at.timestamp();
+        format!(
+            "{}/exports/{}/download?expires={}&signature={}",
+            self.base_url,
+            id,
+            expires,
+            hex::encode(self.mac(id, expires).finalize().into_bytes())
+        )
+    }
+
+    /// Whether `signature` (hex) signs export `id` with expiry `expires`.
+    pub fn verify(&self, id: Uuid, expires: i64, signature: &str) -> bool {
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+        self.mac(id, expires).verify_slice(&signature).is_ok()
+    }
+
+    fn mac(&self, id: Uuid, expires: i64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.link_secret)
+            .expect("HMAC accepts keys of any length");
+        mac.update(id.as_bytes());
+        mac.update(&expires.to_be_bytes());
+        mac
+    }
+}
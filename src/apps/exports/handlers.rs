@@ -0,0 +1,168 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    apps::users::models::User,
+    middleware::auth::AuthContext,
+    results::{ApiError, ApiResult},
+    AppState,
+};
+
+use super::{
+    archive, jobs,
+    models::{DataExport, DataExportRow, ExportFormat},
+    requests::{DownloadQuery, ExportQuery},
+    sql,
+};
+
+#[utoipa::path(
+    tag = "exports",
+    operation_id = "exportMe",
+    get,
+    path = "/users/me/export",
+    security(("bearer_auth" = [])),
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Everything stored about the current user, as an attachment: \
+            a `UserExport` JSON document, or with `format=zip` a zip of one CSV per section",
+            content(("application/json" = UserExport), ("application/zip" = String))),
+        (status = 202, description = "Large account: the export is built in the background and its \
+            download link emailed when ready; poll `GET /users/me/exports/{id}`. While an export is in \
+            progress, or one in the same format can still be downloaded, that one is returned instead", body = DataExport),
+        (status = 401, description = "Missing, expired, invalid or revoked token", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn export_me(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let format = query.format.unwrap_or_default();
+    let user = auth_context.user;
+
+    if sql::count_rows(&state.db, user.id).await? > state.exports.inline_max_rows {
+        let row = background_export(&state, user, format).await?;
+        let location = format!("/users/me/exports/{}", row.id);
+        let url = state.exports.download_url(row.id, row.expires_at);
+        return Ok((
+            StatusCode::ACCEPTED,
+            [(header::LOCATION, location)],
+            Json(row.into_export(Some(url))),
+        )
+            .into_response());
+    }
+
+    let user_id = user.id;
+    let export = sql::collect(&state.db, user).await?;
+    let content = archive::render(&export, format).map_err(|e| {
+        error!(target: "api.exports", %user_id, cause = %e, "rendering data export failed");
+        ApiError::Internal
+    })?;
+    info!(target: "api.exports", %user_id, format = format.as_str(), "data export downloaded");
+    Ok(attachment(format, content))
+}
+
+#[utoipa::path(
+    tag = "exports",
+    operation_id = "getExport",
+    get,
+    path = "/users/me/exports/{id}",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "Export id")),
+    responses(
+        (status = 200, description = "Status of a background export, with its download link once ready", body = DataExport),
+        (status = 404, description = "No such export, or it has expired", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn get_export(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<StatusCode, DataExport> {
+    let row = sql::get_export(&state.db, auth_context.user.id, id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let url = state.exports.download_url(row.id, row.expires_at);
+    Ok((StatusCode::OK, Json(row.into_export(Some(url)))))
+}
+
+#[utoipa::path(
+    tag = "exports",
+    operation_id = "downloadExport",
+    get,
+    path = "/exports/{id}/download",
+    params(("id" = Uuid, Path, description = "Export id"), DownloadQuery),
+    responses(
+        (status = 200, description = "The export file, as an attachment",
+            content(("application/json" = UserExport), ("application/zip" = String))),
+        (status = 403, description = "Bad signature or expired link", body = crate::results::ApiErrorBody),
+        (status = 404, description = "No such ready export", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn download_export(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DownloadQuery>,
+) -> Result<Response, ApiError> {
+    // The link is the credential: no bearer token, so it works straight from the email.
+    if query.expires < chrono::Utc::now().timestamp()
+        || !state.exports.verify(id, query.expires, &query.signature)
+    {
+        return Err(ApiError::InvalidDownloadLink);
+    }
+    let (format, content) = sql::get_ready_content(&state.db, id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let format = ExportFormat::from_str(&format).unwrap_or_default();
+    info!(target: "api.exports", export_id = %id, "background data export downloaded");
+    Ok(attachment(format, content))
+}
+
+/// The user's current background export (see `sql::current_export`), or a new one.
+async fn background_export(
+    state: &AppState,
+    user: User,
+    format: ExportFormat,
+) -> Result<DataExportRow, ApiError> {
+    if let Some(row) = sql::current_export(&state.db, user.id, format).await? {
+        return Ok(row);
+    }
+    match sql::create_export(&state.db, user.id, format, state.exports.ttl).await? {
+        Some(row) => {
+            info!(target: "api.exports", user_id = %user.id, export_id = %row.id, format = format.as_str(), "background data export requested");
+            jobs::spawn_export(state, row.id, user, format);
+            Ok(row)
+        }
+        // A concurrent request started one first.
+        None => sql::current_export(&state.db, user.id, format)
+            .await?
+            .ok_or(ApiError::Internal),
+    }
+}
+
+/// `200` response downloading `content` as a file.
+fn attachment(format: ExportFormat, content: Vec<u8>) -> Response {
+    let disposition = format!(r#"attachment; filename="{}""#, archive::file_name(format));
+    (
+        StatusCode::OK,
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(format.content_type()),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                HeaderValue::from_str(&disposition).expect("file names are ASCII"),
+            ),
+            (header::CACHE_CONTROL, HeaderValue::from_static("no-store")),
+        ],
+        content,
+    )
+        .into_response()
+}
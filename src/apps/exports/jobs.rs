@@ -0,0 +1,74 @@
+//! Background exports for accounts too large to export within a request.
+
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{apps::users::models::User, mailer::Email, AppState};
+
+use super::{archive, models::ExportFormat, sql};
+
+/// Build export `id` in the background, then email its download link to the user.
+///
+/// Exports are built in memory: at most `EXPORT_MAX_JOBS` run at once, the others wait for
+/// a slot.
+pub fn spawn_export(state: &AppState, id: Uuid, user: User, format: ExportFormat) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        let user_id = user.id;
+        let _permit = state
+            .exports
+            .jobs
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the export semaphore is never closed");
+        if let Err(e) = run_export(&state, id, user, format).await {
+            error!(target: "api.exports", %user_id, export_id = %id, cause = %e, "data export failed");
+            if let Err(e) = sql::fail_export(&state.db, id).await {
+                error!(target: "api.exports", export_id = %id, cause = %e, "failed to mark data export as failed");
+            }
+        }
+    });
+}
+
+async fn run_export(
+    state: &AppState,
+    id: Uuid,
+    user: User,
+    format: ExportFormat,
+) -> anyhow::Result<()> {
+    let email = user.email.clone();
+    let full_name = user.full_name.clone();
+    let export = sql::collect(&state.db, user).await?;
+    let content = archive::render(&export, format)?;
+    let row = sql::complete_export(&state.db, id, &content, state.exports.ttl).await?;
+    info!(
+        target: "api.exports",
+        user_id = %export.user.id,
+        export_id = %id,
+        bytes = content.len(),
+        "data export ready"
+    );
+
+    // The file is ready either way: a mail failure must not mark the export as failed.
+    let link = state.exports.download_url(id, row.expires_at);
+    let sent = state
+        .mailer
+        .send(Email {
+            to: email,
+            subject: "Seus dados estão prontos".to_string(),
+            body: format!(
+                "Olá, {}.\n\nA cópia dos seus dados pessoais que você pediu está pronta: {}\n\n\
+                 O link expira em {} horas. Não compartilhe este link: qualquer pessoa com \
+                 ele pode baixar o arquivo.",
+                full_name,
+                link,
+                state.exports.ttl.num_hours()
+            ),
+        })
+        .await;
+    if let Err(e) = sent {
+        error!(target: "api.exports", export_id = %id, cause = %e, "failed to send data export link");
+    }
+    Ok(())
+}
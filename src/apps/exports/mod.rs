@@ -0,0 +1,12 @@
+//! Personal data exports (LGPD right of access).
+
+pub mod archive;
+pub mod config;
+pub mod handlers;
+pub mod jobs;
+pub mod models;
+pub mod requests;
+pub mod routes;
+pub mod sql;
+
+pub use routes::{download_router, router};
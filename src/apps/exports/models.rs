@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::apps::{
+    addresses::models::UserAddress,
+    api_keys::models::ApiKey,
+    consents::models::ConsentRecord,
+    users::models::{AttendeeData, OrganizerData, User},
+};
+
+/// Everything stored about a user, as returned by `GET /users/me/export`.
+///
+/// There is no orders table yet: tickets are the purchase records.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UserExport {
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    pub user: User,
+    pub addresses: Vec<UserAddress>,
+    #[schema(nullable = true)]
+    pub organizer_data: Option<OrganizerData>,
+    #[schema(nullable = true)]
+    pub attendee_data: Option<AttendeeData>,
+    pub tickets: Vec<ExportedTicket>,
+    /// Every session, signed out ones included
+    pub sessions: Vec<ExportedSession>,
+    /// Organizer API keys (never the secrets, which are not stored)
+    pub api_keys: Vec<ApiKey>,
+    /// Consent ledger: accepted legal documents and marketing choices
+    pub consents: Vec<ConsentRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedTicket {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    #[schema(example = "Festival de Verão")]
+    pub event_name: String,
+    #[schema(nullable = true)]
+    pub event_starts_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[schema(nullable = true)]
+    pub lot_id: Option<Uuid>,
+    pub qr_code: String,
+    pub is_active: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedSession {
+    pub id: Uuid,
+    #[schema(nullable = true)]
+    pub device_label: Option<String>,
+    #[schema(nullable = true)]
+    pub user_agent: Option<String>,
+    #[schema(nullable = true)]
+    pub ip: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_seen_at: chrono::DateTime<chrono::Utc>,
+    #[schema(nullable = true)]
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// File format of an export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// A single JSON document (`UserExport`)
+    #[default]
+    Json,
+    /// A zip archive with one CSV file per section
+    Zip,
+}
+
+impl ExportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Zip => "zip",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "json" => Some(ExportFormat::Json),
+            "zip" => Some(ExportFormat::Zip),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Zip => "application/zip",
+        }
+    }
+}
+
+/// State of a background export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportStatus {
+    Pending,
+    Ready,
+    Failed,
+}
+
+impl ExportStatus {
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "ready" => ExportStatus::Ready,
+            "failed" => ExportStatus::Failed,
+            _ => ExportStatus::Pending,
+        }
+    }
+}
+
+/// A background export, as shown to its owner.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DataExport {
+    pub id: Uuid,
+    pub format: ExportFormat,
+    pub status: ExportStatus,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[schema(nullable = true)]
+    pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// The file is deleted after this
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    /// Signed link to the file, once `ready` (no `Authorization` header needed)
+    #[schema(nullable = true)]
+    pub download_url: Option<String>,
+}
+
+/// Row returned from database for DataExport (without the content)
+#[derive(Debug, Clone, FromRow)]
+pub struct DataExportRow {
+    pub id: Uuid,
+    pub format: String,
+    pub status: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl DataExportRow {
+    pub fn into_export(self, download_url: Option<String>) -> DataExport {
+        let status = ExportStatus::from_str(&self.status);
+        DataExport {
+            id: self.id,
+            format: ExportFormat::from_str(&self.format).unwrap_or_default(),
+            download_url: download_url.filter(|_| status == ExportStatus::Ready),
+            status,
+            created_at: self.created_at,
+            completed_at: self.completed_at,
+            expires_at: self.expires_at,
+        }
+    }
+}
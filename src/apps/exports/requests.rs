@@ -0,0 +1,21 @@
+use utoipa::IntoParams;
+
+use super::models::ExportFormat;
+
+/// Query of `GET /users/me/export`.
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// `json` (default) or `zip` (one CSV per section)
+    pub format: Option<ExportFormat>,
+}
+
+/// Query of the signed download link sent for background exports.
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DownloadQuery {
+    /// Unix time after which the link stops working
+    pub expires: i64,
+    /// Hex HMAC of the export id and `expires`
+    pub signature: String,
+}
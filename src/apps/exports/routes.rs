@@ -0,0 +1,32 @@
+use axum::{middleware::from_fn_with_state, routing::get, Router};
+
+use crate::{
+    middleware::rate_limit::{rate_limit, KeyBy, Quota, RateLimit},
+    AppState,
+};
+
+use super::handlers;
+
+/// Export endpoints of the current user, merged into the users account router (which
+/// applies `require_auth` and `require_session`).
+///
+/// Rate limited per user (`RATE_LIMIT_EXPORTS`, default 5/min): every export reads the
+/// whole account.
+pub fn router(state: &AppState) -> Router<AppState> {
+    let limit = RateLimit::for_group(state, "exports", Quota::per_minute(5), KeyBy::UserOrIp);
+    Router::new()
+        .route("/me/export", get(handlers::export_me))
+        .route("/me/exports/:id", get(handlers::get_export))
+        .route_layer(from_fn_with_state(limit, rate_limit))
+}
+
+/// Signed download links of background exports, mounted at the top level. The signature
+/// authenticates the request.
+///
+/// Rate limited per IP (`RATE_LIMIT_EXPORTS_DOWNLOAD`, default 10/min).
+pub fn download_router(state: &AppState) -> Router<AppState> {
+    let limit = RateLimit::for_group(state, "exports_download", Quota::per_minute(10), KeyBy::Ip);
+    Router::new()
+        .route("/exports/:id/download", get(handlers::download_export))
+        .route_layer(from_fn_with_state(limit, rate_limit))
+}
@@ -0,0 +1,210 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::apps::{
+    addresses,
+    api_keys::{self, models::ApiKeyRow},
+    consents::{self, models::ConsentRecordRow},
+    users::models::{AttendeeData, OrganizerData, User},
+};
+
+use super::models::{DataExportRow, ExportFormat, ExportedSession, ExportedTicket, UserExport};
+
+/// Number of tickets and sessions of the user, the bulk of an export.
+pub async fn count_rows(db: &PgPool, user_id: Uuid) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"SELECT (SELECT count(*) FROM tickets WHERE owner_user_id = $1)
+                + (SELECT count(*) FROM sessions WHERE user_id = $1)"#,
+    )
+    .bind(user_id)
+    .fetch_one(db)
+    .await
+}
+
+/// Gather everything stored about `user`.
+pub async fn collect(db: &PgPool, user: User) -> Result<UserExport, sqlx::Error> {
+    let addresses = addresses::sql::list_addresses(db, user.id).await?;
+    let organizer_data: Option<OrganizerData> =
+        sqlx::query_as(r#"SELECT * FROM organizer_data WHERE user_id = $1"#)
+            .bind(user.id)
+            .fetch_optional(db)
+            .await?;
+    let attendee_data: Option<AttendeeData> =
+        sqlx::query_as(r#"SELECT * FROM consumer_data WHERE user_id = $1"#)
+            .bind(user.id)
+            .fetch_optional(db)
+            .await?;
+
+    let tickets: Vec<ExportedTicket> = sqlx::query_as(
+        r#"SELECT t.id, t.event_id, e.name AS event_name, e.starts_at AS event_starts_at,
+                  t.lot_id, t.qr_code, t.is_active, t.created_at
+           FROM tickets t
+           JOIN events e ON e.id = t.event_id
+           WHERE t.owner_user_id = $1
+           ORDER BY t.created_at"#,
+    )
+    .bind(user.id)
+    .fetch_all(db)
+    .await?;
+
+    let sessions: Vec<ExportedSession> = sqlx::query_as(
+        r#"SELECT id, device_label, user_agent, ip, created_at, last_seen_at, revoked_at
+           FROM sessions
+           WHERE user_id = $1
+           ORDER BY created_at"#,
+    )
+    .bind(user.id)
+    .fetch_all(db)
+    .await?;
+
+    let api_keys = api_keys::sql::list_api_keys(db, user.id)
+        .await?
+        .into_iter()
+        .map(ApiKeyRow::into_api_key)
+        .collect();
+
+    let consents = consents::sql::list_consents(db, user.id)
+        .await?
+        .into_iter()
+        .map(ConsentRecordRow::into_record)
+        .collect();
+
+    Ok(UserExport {
+        generated_at: chrono::Utc::now(),
+        user,
+        addresses,
+        organizer_data,
+        attendee_data,
+        tickets,
+        sessions,
+        api_keys,
+        consents,
+    })
+}
+
+/// The user's export in progress, or else their newest downloadable export in `format`.
+pub async fn current_export(
+    db: &PgPool,
+    user_id: Uuid,
+    format: ExportFormat,
+) -> Result<Option<DataExportRow>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT id, format, status, created_at, completed_at, expires_at
+           FROM data_exports
+           WHERE user_id = $1
+             AND expires_at > now()
+             AND (status = 'pending' OR (status = 'ready' AND format = $2))
+           ORDER BY status = 'pending' DESC, created_at DESC
+           LIMIT 1"#,
+    )
+    .bind(user_id)
+    .bind(format.as_str())
+    .fetch_optional(db)
+    .await
+}
+
+/// Start a background export. `None` if the user already has one in progress
+/// (`data_exports_one_pending_idx`).
+pub async fn create_export(
+    db: &PgPool,
+    user_id: Uuid,
+    format: ExportFormat,
+    ttl: chrono::Duration,
+) -> Result<Option<DataExportRow>, sqlx::Error> {
+    sqlx::query_as(
+        r#"INSERT INTO data_exports (user_id, format, expires_at)
+           VALUES ($1, $2, $3)
+           ON CONFLICT (user_id) WHERE status = 'pending' DO NOTHING
+           RETURNING id, format, status, created_at, completed_at, expires_at"#,
+    )
+    .bind(user_id)
+    .bind(format.as_str())
+    .bind(chrono::Utc::now() + ttl)
+    .fetch_optional(db)
+    .await
+}
+
+/// Store the finished file. The download window starts now.
+pub async fn complete_export(
+    db: &PgPool,
+    id: Uuid,
+    content: &[u8],
+    ttl: chrono::Duration,
+) -> Result<DataExportRow, sqlx::Error> {
+    sqlx::query_as(
+        r#"UPDATE data_exports
+           SET status = 'ready', content = $2, completed_at = now(), expires_at = $3
+           WHERE id = $1
+           RETURNING id, format, status, created_at, completed_at, expires_at"#,
+    )
+    .bind(id)
+    .bind(content)
+    .bind(chrono::Utc::now() + ttl)
+    .fetch_one(db)
+    .await
+}
+
+pub async fn fail_export(db: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE data_exports
+           SET status = 'failed', completed_at = now()
+           WHERE id = $1"#,
+    )
+    .bind(id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// One of the user's exports that has not expired.
+pub async fn get_export(
+    db: &PgPool,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<Option<DataExportRow>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT id, format, status, created_at, completed_at, expires_at
+           FROM data_exports
+           WHERE id = $1 AND user_id = $2 AND expires_at > now()"#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Format and file of a ready, unexpired export.
+pub async fn get_ready_content(
+    db: &PgPool,
+    id: Uuid,
+) -> Result<Option<(String, Vec<u8>)>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT format, content
+           FROM data_exports
+           WHERE id = $1 AND status = 'ready' AND content IS NOT NULL AND expires_at > now()"#,
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Mark every export still in progress as failed. Run at startup: their jobs died with the
+/// previous process. Returns the number of exports marked.
+pub async fn fail_interrupted(db: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"UPDATE data_exports
+           SET status = 'failed', completed_at = now()
+           WHERE status = 'pending'"#,
+    )
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Delete expired exports (and their files). Returns the number of deleted rows.
+pub async fn purge_expired(db: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(r#"DELETE FROM data_exports WHERE expires_at <= now()"#)
+        .execute(db)
+        .await?;
+    Ok(result.rows_affected())
+}
@@ -1,2 +1,10 @@
+pub mod addresses;
+pub mod admin;
+pub mod api_keys;
+pub mod auth;
+pub mod consents;
+pub mod deletion;
+pub mod exports;
+pub mod sessions;
 pub mod tickets;
 pub mod users;
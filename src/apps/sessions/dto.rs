@@ -0,0 +1,8 @@
+use utoipa::ToSchema;
+
+use super::models::Session;
+
+#[derive(Debug, serde::Serialize, ToSchema)]
+pub struct ListSessionsResponse {
+    pub sessions: Vec<Session>,
+}
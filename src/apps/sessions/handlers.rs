@@ -0,0 +1,71 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth::{AuthContext, Credential},
+    results::{ApiError, ApiResult},
+    AppState,
+};
+
+use super::{dto::ListSessionsResponse, sql};
+
+#[utoipa::path(
+    tag = "sessions",
+    operation_id = "listSessions",
+    get,
+    path = "/users/me/sessions",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Active sessions of the current user", body = ListSessionsResponse),
+        (status = 401, description = "Missing, expired, invalid or revoked token", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn list_sessions(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+) -> ApiResult<StatusCode, ListSessionsResponse> {
+    let current = match auth_context.credential {
+        Credential::AccessToken { sid, .. } => Some(sid),
+        Credential::ApiKey { .. } => None,
+    };
+    let sessions = sql::list_active_sessions(&state.db, auth_context.user.id)
+        .await?
+        .into_iter()
+        .map(|row| row.into_session(current))
+        .collect();
+    Ok((StatusCode::OK, Json(ListSessionsResponse { sessions })))
+}
+
+#[utoipa::path(
+    tag = "sessions",
+    operation_id = "revokeSession",
+    delete,
+    path = "/users/me/sessions/{id}",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "Session id")),
+    responses(
+        (status = 204, description = "Session signed out: its access and refresh tokens stop working"),
+        (status = 404, description = "No such active session", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn revoke_session(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = auth_context.user.id;
+    if !state
+        .revocations
+        .revoke_session(&state.db, user_id, id)
+        .await?
+    {
+        return Err(ApiError::NotFound);
+    }
+    info!(target: "api.sessions", %user_id, session_id = %id, status = 204, "session revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,7 @@
+pub mod dto;
+pub mod handlers;
+pub mod models;
+pub mod routes;
+pub mod sql;
+
+pub use routes::router;
@@ -0,0 +1,52 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A place where the user is signed in: one login and the tokens refreshed from it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub id: Uuid,
+    /// Label given by the client at login
+    #[schema(nullable = true, example = "iPhone 15")]
+    pub device_label: Option<String>,
+    #[schema(
+        nullable = true,
+        example = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X)"
+    )]
+    pub user_agent: Option<String>,
+    /// Client IP at the latest refresh
+    #[schema(nullable = true, example = "177.12.34.56")]
+    pub ip: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Latest login or token refresh
+    pub last_seen_at: chrono::DateTime<chrono::Utc>,
+    /// Whether this is the session making the request
+    pub current: bool,
+}
+
+/// Row returned from database for Session
+#[derive(Debug, Clone, FromRow)]
+pub struct SessionRow {
+    pub id: Uuid,
+    pub device_label: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_seen_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl SessionRow {
+    pub fn into_session(self, current_id: Option<Uuid>) -> Session {
+        Session {
+            current: current_id == Some(self.id),
+            id: self.id,
+            device_label: self.device_label,
+            user_agent: self.user_agent,
+            ip: self.ip,
+            created_at: self.created_at,
+            last_seen_at: self.last_seen_at,
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+
+use crate::AppState;
+
+use super::handlers;
+
+/// Session management, nested under `/users/me/sessions` by the users router (which
+/// applies `require_auth` and `require_session`).
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_sessions))
+        .route("/:id", delete(handlers::revoke_session))
+}
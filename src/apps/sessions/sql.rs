@@ -0,0 +1,108 @@
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use super::models::SessionRow;
+
+/// Record a new login; its id becomes the refresh token family id.
+pub async fn insert_session(
+    db: impl PgExecutor<'_>,
+    user_id: Uuid,
+    device_label: Option<&str>,
+    user_agent: Option<&str>,
+    ip: &str,
+) -> Result<Uuid, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"INSERT INTO sessions (user_id, device_label, user_agent, ip)
+           VALUES ($1, $2, $3, $4)
+           RETURNING id"#,
+    )
+    .bind(user_id)
+    .bind(device_label)
+    .bind(user_agent)
+    .bind(ip)
+    .fetch_one(db)
+    .await
+}
+
+/// Sessions that can still be refreshed, most recently used first.
+pub async fn list_active_sessions(
+    db: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<SessionRow>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT s.id, s.device_label, s.user_agent, s.ip, s.created_at, s.last_seen_at
+           FROM sessions s
+           WHERE s.user_id = $1
+             AND s.revoked_at IS NULL
+             AND EXISTS (
+               SELECT 1 FROM refresh_tokens r
+               WHERE r.family_id = s.id
+                 AND r.used_at IS NULL
+                 AND r.revoked_at IS NULL
+                 AND r.expires_at > now()
+             )
+           ORDER BY s.last_seen_at DESC"#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Revoke one of the user's sessions and its refresh tokens. Returns `false` if there is
+/// no such active session.
+pub async fn revoke_session(db: &PgPool, user_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let revoked: Option<Uuid> = sqlx::query_scalar(
+        r#"WITH session AS (
+             UPDATE sessions
+             SET revoked_at = now()
+             WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+             RETURNING id
+           ), tokens AS (
+             UPDATE refresh_tokens
+             SET revoked_at = now()
+             WHERE family_id IN (SELECT id FROM session) AND revoked_at IS NULL
+           )
+           SELECT id FROM session"#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?;
+    Ok(revoked.is_some())
+}
+
+/// Revoke every active session of the user except `keep`, with their refresh tokens.
+/// Returns the revoked session ids.
+pub async fn revoke_other_sessions(
+    db: impl PgExecutor<'_>,
+    user_id: Uuid,
+    keep: Uuid,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"WITH revoked AS (
+             UPDATE sessions
+             SET revoked_at = now()
+             WHERE user_id = $1 AND id <> $2 AND revoked_at IS NULL
+             RETURNING id
+           ), tokens AS (
+             UPDATE refresh_tokens
+             SET revoked_at = now()
+             WHERE user_id = $1 AND family_id <> $2 AND revoked_at IS NULL
+           )
+           SELECT id FROM revoked"#,
+    )
+    .bind(user_id)
+    .bind(keep)
+    .fetch_all(db)
+    .await
+}
+
+/// Whether a session is gone or revoked.
+pub async fn is_session_revoked(db: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"SELECT NOT EXISTS (SELECT 1 FROM sessions WHERE id = $1 AND revoked_at IS NULL)"#,
+    )
+    .bind(id)
+    .fetch_one(db)
+    .await
+}
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::cache::TtlCache;
+
+use super::{models::User, sql};
+
+/// Default lifetime of a cached user.
+const DEFAULT_USER_CACHE_TTL_SECS: u64 = 15;
+
+/// Per-request user lookup used by `require_auth`, cached for a few seconds.
+///
+/// Code that updates a user row should call `invalidate` so the change applies to the
+/// next request on this instance. Other instances (and direct database edits) pick it
+/// up once the cached entry expires (`USER_CACHE_TTL_SECS`, default 15s).
+#[derive(Debug, Clone)]
+pub struct UserCache {
+    users: TtlCache<Uuid, User>,
+}
+
+impl UserCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            users: TtlCache::new(ttl),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let secs = std::env::var("USER_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_USER_CACHE_TTL_SECS);
+        Self::new(Duration::from_secs(secs))
+    }
+
+    /// Load a user, from cache when fresh. Returns `None` if the user no longer exists.
+    pub async fn get(&self, db: &PgPool, id: Uuid) -> Result<Option<User>, sqlx::Error> {
+        if let Some(user) = self.users.get(&id) {
+            return Ok(Some(user));
+        }
+        let user = sql::get_user_by_id(db, id).await?;
+        if let Some(user) = &user {
+            self.users.insert(id, user.clone());
+        }
+        Ok(user)
+    }
+
+    pub fn invalidate(&self, id: Uuid) {
+        self.users.remove(&id);
+    }
+}
@@ -1,22 +1,54 @@
 use utoipa::ToSchema;
 
-use crate::apps::users::models::RelatedData;
+use crate::apps::{
+    addresses::models::UserAddress, auth::dto::MfaChallengeResponse,
+    consents::models::LegalDocument, users::models::RelatedData,
+};
 
 use super::models::User;
 
 #[derive(Debug, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct ListUsersResponse {
     pub users: Vec<User>,
+    /// Users matching the filters, across all pages
+    #[schema(example = 42)]
+    pub total: i64,
+    #[schema(example = 1)]
+    pub page: u32,
+    #[schema(example = 20)]
+    pub per_page: u32,
 }
 
 #[derive(Debug, serde::Serialize, ToSchema)]
 pub struct UserWithRelatedData {
     pub user: User,
     pub related_data: Option<RelatedData>,
+    /// Default address (every address: `GET /users/me/addresses`)
+    pub address: Option<UserAddress>,
+    /// Current legal documents the user has not accepted yet; clients should ask for
+    /// acceptance (`POST /users/me/consents`) when this is not empty
+    pub pending_consents: Vec<LegalDocument>,
 }
 
 #[derive(Debug, serde::Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct SignupResponse {
+    /// Short-lived access token (JWT)
     pub token: String,
+    /// Opaque refresh token for `POST /auth/refresh`
+    pub refresh_token: String,
+    /// Access token lifetime in seconds
+    #[schema(example = 900)]
+    pub expires_in: u64,
     pub user: User,
 }
+
+/// Response of `POST /users/login`: either a full session, or a challenge for the second
+/// factor when the account has MFA enabled.
+#[derive(Debug, serde::Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Authenticated(SignupResponse),
+    MfaRequired(MfaChallengeResponse),
+}
@@ -0,0 +1,274 @@
+//! Brazilian taxpayer ids: CPF (people, 11 digits) and CNPJ (companies, 14 digits).
+
+use std::fmt;
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+use thiserror::Error;
+
+/// Which kind of id a `GovId` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GovIdKind {
+    Cpf,
+    Cnpj,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum GovIdError {
+    #[error("must be a CPF (11 digits) or a CNPJ (14 digits)")]
+    Length,
+    #[error("contains characters other than digits and . - /")]
+    Characters,
+    #[error("all digits are the same")]
+    RepeatedDigits,
+    #[error("check digits do not match")]
+    CheckDigits,
+}
+
+/// A valid CPF or CNPJ, kept as its digits only (leading zeros included).
+///
+/// Parsed from formatted (`123.456.789-09`, `12.345.678/0001-95`) or raw input, with the
+/// mod-11 check digits verified. Serialized and stored in canonical form: digits only.
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, sqlx::Type)]
+#[serde(into = "String")]
+#[sqlx(transparent)]
+pub struct GovId(String);
+
+impl GovId {
+    pub fn parse(input: &str) -> Result<Self, GovIdError> {
+        let mut digits = Vec::with_capacity(14);
+        for c in input.trim().chars() {
+            match c {
+                '0'..='9' => digits.push(c as u8 - b'0'),
+                '.' | '-' | '/' | ' ' => {}
+                _ => return Err(GovIdError::Characters),
+            }
+        }
+        let kind = match digits.len() {
+            11 => GovIdKind::Cpf,
+            14 => GovIdKind::Cnpj,
+            _ => return Err(GovIdError::Length),
+        };
+        if digits.iter().all(|&d| d == digits[0]) {
+            return Err(GovIdError::RepeatedDigits);
+        }
+        let valid = match kind {
+            GovIdKind::Cpf => cpf_check_digits(&digits[..9]) == [digits[9], digits[10]],
+            GovIdKind::Cnpj => cnpj_check_digits(&digits[..12]) == [digits[12], digits[13]],
+        };
+        if !valid {
+            return Err(GovIdError::CheckDigits);
+        }
+        Ok(GovId(digits.iter().map(|d| (b'0' + d) as char).collect()))
+    }
+
+    pub fn kind(&self) -> GovIdKind {
+        if self.0.len() == 11 {
+            GovIdKind::Cpf
+        } else {
+            GovIdKind::Cnpj
+        }
+    }
+
+    /// Digits only, as stored.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Human-readable form: `123.456.789-09` or `12.345.678/0001-95`.
+    pub fn formatted(&self) -> String {
+        let d = &self.0;
+        match self.kind() {
+            GovIdKind::Cpf => format!("{}.{}.{}-{}", &d[..3], &d[3..6], &d[6..9], &d[9..]),
+            GovIdKind::Cnpj => format!(
+                "{}.{}.{}/{}-{}",
+                &d[..2],
+                &d[2..5],
+                &d[5..8],
+                &d[8..12],
+                &d[12..]
+            ),
+        }
+    }
+}
+
+/// Mod-11 check digit over `digits` with `weights`, as CPF and CNPJ define it.
+fn mod11(digits: &[u8], weights: impl Iterator<Item = u32>) -> u8 {
+    let sum: u32 = digits.iter().zip(weights).map(|(&d, w)| d as u32 * w).sum();
+    match sum % 11 {
+        0 | 1 => 0,
+        r => (11 - r) as u8,
+    }
+}
+
+fn cpf_check_digits(base: &[u8]) -> [u8; 2] {
+    let first = mod11(base, (2..=10).rev());
+    let with_first = [base, &[first]].concat();
+    [first, mod11(&with_first, (2..=11).rev())]
+}
+
+fn cnpj_check_digits(base: &[u8]) -> [u8; 2] {
+    // Weights run 2..=9 from the right, then wrap around.
+    let weights = |len: usize| (0..len).rev().map(|i| (i % 8) as u32 + 2);
+    let first = mod11(base, weights(12));
+    let with_first = [base, &[first]].concat();
+    [first, mod11(&with_first, weights(13))]
+}
+
+impl fmt::Display for GovId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Only the kind and the last two digits, so ids do not end up in logs.
+impl fmt::Debug for GovId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "GovId({:?}, ***{})",
+            self.kind(),
+            &self.0[self.0.len() - 2..]
+        )
+    }
+}
+
+impl From<GovId> for String {
+    fn from(id: GovId) -> Self {
+        id.0
+    }
+}
+
+impl std::str::FromStr for GovId {
+    type Err = GovIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GovId::parse(s)
+    }
+}
+
+/// Accepts a string (formatted or raw) or, for older clients, a JSON number.
+impl<'de> Deserialize<'de> for GovId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Text(String),
+            Number(u64),
+        }
+        let raw = match Raw::deserialize(deserializer)? {
+            Raw::Text(s) => s,
+            Raw::Number(n) => n.to_string(),
+        };
+        GovId::parse(&raw).map_err(|e| de::Error::custom(format!("invalid CPF/CNPJ: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_cpfs() {
+        for cpf in ["52998224725", "11144477735", "12345678909"] {
+            let id = GovId::parse(cpf).unwrap();
+            assert_eq!(id.kind(), GovIdKind::Cpf);
+            assert_eq!(id.as_str(), cpf);
+        }
+    }
+
+    #[test]
+    fn accepts_valid_cnpjs() {
+        for cnpj in ["11222333000181", "12345678000195"] {
+            let id = GovId::parse(cnpj).unwrap();
+            assert_eq!(id.kind(), GovIdKind::Cnpj);
+            assert_eq!(id.as_str(), cnpj);
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_check_digits() {
+        for id in [
+            "52998224724",
+            "52998224715",
+            "12345678900",
+            "11222333000182",
+            "11222333000191",
+        ] {
+            assert_eq!(GovId::parse(id), Err(GovIdError::CheckDigits), "{id}");
+        }
+    }
+
+    #[test]
+    fn rejects_repeated_digits() {
+        // Their check digits work out, but they are not real ids.
+        for id in [
+            "00000000000",
+            "11111111111",
+            "99999999999",
+            "00000000000000",
+        ] {
+            assert_eq!(GovId::parse(id), Err(GovIdError::RepeatedDigits), "{id}");
+        }
+    }
+
+    #[test]
+    fn strips_formatting() {
+        assert_eq!(
+            GovId::parse("529.982.247-25").unwrap().as_str(),
+            "52998224725"
+        );
+        assert_eq!(
+            GovId::parse(" 11.222.333/0001-81 ").unwrap().as_str(),
+            "11222333000181"
+        );
+        assert_eq!(
+            GovId::parse("529 982 247 25").unwrap().as_str(),
+            "52998224725"
+        );
+    }
+
+    #[test]
+    fn rejects_other_characters_and_lengths() {
+        assert_eq!(GovId::parse("529.982.247_25"), Err(GovIdError::Characters));
+        assert_eq!(GovId::parse("5299822472a"), Err(GovIdError::Characters));
+        assert_eq!(GovId::parse(""), Err(GovIdError::Length));
+        assert_eq!(GovId::parse("5299822472"), Err(GovIdError::Length));
+        assert_eq!(GovId::parse("529982247250"), Err(GovIdError::Length));
+        assert_eq!(GovId::parse("112223330001811"), Err(GovIdError::Length));
+    }
+
+    #[test]
+    fn keeps_leading_zeros() {
+        let cpf = GovId::parse("000.000.001-91").unwrap();
+        assert_eq!(cpf.as_str(), "00000000191");
+        assert_eq!(cpf.formatted(), "000.000.001-91");
+
+        let cnpj = GovId::parse("00.000.000/0001-91").unwrap();
+        assert_eq!(cnpj.as_str(), "00000000000191");
+        assert_eq!(cnpj.formatted(), "00.000.000/0001-91");
+    }
+
+    #[test]
+    fn formats() {
+        let cpf = GovId::parse("52998224725").unwrap();
+        assert_eq!(cpf.formatted(), "529.982.247-25");
+        let cnpj = GovId::parse("12345678000195").unwrap();
+        assert_eq!(cnpj.formatted(), "12.345.678/0001-95");
+    }
+
+    #[test]
+    fn deserializes_strings_and_numbers() {
+        let id: GovId = serde_json::from_str(r#""529.982.247-25""#).unwrap();
+        assert_eq!(id.as_str(), "52998224725");
+        let id: GovId = serde_json::from_str("52998224725").unwrap();
+        assert_eq!(id.as_str(), "52998224725");
+        assert!(serde_json::from_str::<GovId>(r#""52998224724""#).is_err());
+        assert_eq!(serde_json::to_string(&id).unwrap(), r#""52998224725""#);
+    }
+
+    #[test]
+    fn debug_hides_the_id() {
+        let id = GovId::parse("52998224725").unwrap();
+        assert_eq!(format!("{id:?}"), "GovId(Cpf, ***25)");
+    }
+}
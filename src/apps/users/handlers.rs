@@ -1,17 +1,38 @@
+use std::net::IpAddr;
+
 use axum::{extract::State, http::StatusCode, Extension, Json};
 use tracing::{error, info};
 
 use crate::{
+    apps::addresses,
+    apps::api_keys::models::ApiKeyScope,
+    apps::auth::{
+        dto::MfaChallengeResponse, emails, revocation::RevocationStore, throttle, tokens,
+    },
+    apps::consents::{self, models::LegalDocumentRow},
     apps::users::{
-        dto::{SignupResponse, UserWithRelatedData},
+        dto::{LoginResponse, SignupResponse, UserWithRelatedData},
         models::{AttendeeData, OrganizerData, RelatedData, UserRole},
     },
-    middleware::{auth::AuthContext, jwt},
+    middleware::{
+        auth::{AuthContext, Credential},
+        authz,
+        client_ip::{ClientInfo, ClientIp},
+        jwt,
+    },
     results::{ApiError, ApiResult},
+    validation::ValidJson,
     AppState,
 };
 
-use super::{models::User, requests::{SignupAttendeeRequest, SignupOrganizerRequest}};
+use super::{
+    models::User,
+    requests::{
+        ChangeEmailRequest, ChangePasswordRequest, LoginRequest, SignupAttendeeRequest,
+        SignupOrganizerRequest, UpdateMeRequest,
+    },
+    sql,
+};
 
 #[utoipa::path(
     tag = "users",
@@ -19,11 +40,16 @@ use super::{models::User, requests::{SignupAttendeeRequest, SignupOrganizerReque
     post,
     path = "/users/signup/organizer",
     request_body = SignupOrganizerRequest,
-    responses((status = 201, description = "Organizer signup", body = SignupResponse))
+    responses(
+        (status = 201, description = "Organizer signup", body = SignupResponse),
+        (status = 409, description = "Email already in use", body = crate::results::ApiErrorBody),
+        (status = 422, description = "Validation failed (see `fields`)", body = crate::results::ApiErrorBody)
+    )
 )]
 pub async fn signup_organizer(
     State(state): State<AppState>,
-    Json(req): Json<SignupOrganizerRequest>,
+    client: ClientInfo,
+    ValidJson(req): ValidJson<SignupOrganizerRequest>,
 ) -> ApiResult<StatusCode, SignupResponse> {
     // Never log raw passwords.
     info!(
@@ -33,22 +59,30 @@ pub async fn signup_organizer(
         email = ?req.email,
         gov_identification = ?req.gov_identification,
         cep = %req.address.cep,
-        cidade = %req.address.cidade,
-        estado = %req.address.estado,
+        cidade = ?req.address.cidade,
+        estado = ?req.address.estado,
         password_len = req.password.len(),
         "signup request"
     );
 
-    let (user, _org) = super::sql::create_organizer_with_data(&state.db, req).await?;
+    state
+        .password_policy
+        .check("password", &req.password)
+        .await?;
+    let consents = consents::handlers::signup_consents(
+        &state.db,
+        &req.accepted_terms_version,
+        &req.accepted_privacy_version,
+        req.marketing_opt_in,
+    )
+    .await?;
+    let address = addresses::handlers::new_address(&state, "address", &req.address).await?;
+    let (user, _org) =
+        super::sql::create_organizer_with_data(&state.db, req, &address, &consents, &client)
+            .await?;
 
-    let secret = std::env::var("JWT_SECRET").map_err(|e| {
-        error!(target: "api.users.signup", cause = %e, "JWT_SECRET env var not set");
-        ApiError::MissingJwtSecret
-    })?;
-    let token = jwt::generate_token(&user, &secret, 60 * 60 * 24).map_err(|e| {
-        error!(target: "api.users.signup", cause = %e, "JWT token generation failed");
-        ApiError::Internal
-    })?;
+    let pair = tokens::issue_token_pair(&state, &user, None, &client).await?;
+    emails::spawn_email_verification(&state, &user);
 
     info!(
         target: "api.users.signup",
@@ -61,7 +95,15 @@ pub async fn signup_organizer(
         "signup response"
     );
 
-    Ok((StatusCode::CREATED, Json(SignupResponse { token, user })))
+    Ok((
+        StatusCode::CREATED,
+        Json(SignupResponse {
+            token: pair.token,
+            refresh_token: pair.refresh_token,
+            expires_in: pair.expires_in,
+            user,
+        }),
+    ))
 }
 
 #[utoipa::path(
@@ -69,11 +111,16 @@ pub async fn signup_organizer(
     post,
     path = "/users/signup/attendee",
     request_body = SignupAttendeeRequest,
-    responses((status = 201, description = "Attendee signup", body = SignupResponse))
+    responses(
+        (status = 201, description = "Attendee signup", body = SignupResponse),
+        (status = 409, description = "Email already in use", body = crate::results::ApiErrorBody),
+        (status = 422, description = "Validation failed (see `fields`)", body = crate::results::ApiErrorBody)
+    )
 )]
 pub async fn signup_attendee(
     State(state): State<AppState>,
-    Json(req): Json<SignupAttendeeRequest>,
+    client: ClientInfo,
+    ValidJson(req): ValidJson<SignupAttendeeRequest>,
 ) -> ApiResult<StatusCode, SignupResponse> {
     // Never log raw passwords.
     info!(
@@ -84,22 +131,29 @@ pub async fn signup_attendee(
         gov_identification = ?req.gov_identification,
         birth_date = ?req.birth_date,
         cep = %req.address.cep,
-        cidade = %req.address.cidade,
-        estado = %req.address.estado,
+        cidade = ?req.address.cidade,
+        estado = ?req.address.estado,
         password_len = req.password.len(),
         "signup request"
     );
 
-    let (user, attendee_data) = super::sql::create_attendee_with_data(&state.db, req).await?;
+    state
+        .password_policy
+        .check("password", &req.password)
+        .await?;
+    let consents = consents::handlers::signup_consents(
+        &state.db,
+        &req.accepted_terms_version,
+        &req.accepted_privacy_version,
+        req.marketing_opt_in,
+    )
+    .await?;
+    let address = addresses::handlers::new_address(&state, "address", &req.address).await?;
+    let (user, attendee_data) =
+        super::sql::create_attendee_with_data(&state.db, req, &address, &consents, &client).await?;
 
-    let secret = std::env::var("JWT_SECRET").map_err(|e| {
-        error!(target: "api.users.signup", cause = %e, "JWT_SECRET env var not set");
-        ApiError::MissingJwtSecret
-    })?;
-    let token = jwt::generate_token(&user, &secret, 60 * 60 * 24).map_err(|e| {
-        error!(target: "api.users.signup", cause = %e, "JWT token generation failed");
-        ApiError::Internal
-    })?;
+    let pair = tokens::issue_token_pair(&state, &user, None, &client).await?;
+    emails::spawn_email_verification(&state, &user);
 
     info!(
         target: "api.users.signup",
@@ -114,20 +168,114 @@ pub async fn signup_attendee(
         "signup response"
     );
 
-    Ok((StatusCode::CREATED, Json(SignupResponse { token, user })))
+    Ok((
+        StatusCode::CREATED,
+        Json(SignupResponse {
+            token: pair.token,
+            refresh_token: pair.refresh_token,
+            expires_in: pair.expires_in,
+            user,
+        }),
+    ))
 }
 
 #[utoipa::path(
+    tag = "users",
+    operation_id = "login",
+    post,
+    path = "/users/login",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Logged in, or a second factor is required (`mfaRequired`)", body = LoginResponse),
+        (status = 401, description = "Invalid email or password", body = crate::results::ApiErrorBody),
+        (status = 429, description = "Too many failed attempts (see `Retry-After`)", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn login(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(req): Json<LoginRequest>,
+) -> ApiResult<StatusCode, LoginResponse> {
+    let ip = client.ip;
+    // Never log raw passwords.
+    info!(
+        target: "api.users.login",
+        email = ?req.email,
+        password_len = req.password.len(),
+        "login request"
+    );
+
+    throttle::check(&state, &req.email, ip).await?;
+    let Some(user) = super::sql::authenticate(&state.db, &req.email, &req.password).await? else {
+        info!(target: "api.users.login", email = ?req.email, %ip, status = 401, "login rejected");
+        throttle::record_failure(&state, &req.email, ip).await?;
+        return Err(ApiError::InvalidCredentials);
+    };
+    if user.is_disabled() {
+        info!(target: "api.users.login", user_id = %user.id, status = 403, "login rejected: account disabled");
+        return Err(ApiError::AccountDisabled);
+    }
+
+    throttle::record_success(&state, &req.email).await?;
+
+    if user.is_mfa_enabled() {
+        let mfa_token =
+            tokens::issue_mfa_pending_token(&state.jwt, &user, req.device_label.as_deref())?;
+        info!(
+            target: "api.users.login",
+            user_id = %user.id,
+            status = 200,
+            "login pending second factor"
+        );
+        return Ok((
+            StatusCode::OK,
+            Json(LoginResponse::MfaRequired(MfaChallengeResponse {
+                mfa_required: true,
+                mfa_token,
+                expires_in: jwt::MFA_PENDING_TOKEN_TTL_SECS,
+            })),
+        ));
+    }
+
+    let pair =
+        tokens::issue_token_pair(&state, &user, req.device_label.as_deref(), &client).await?;
+
+    info!(
+        target: "api.users.login",
+        user_id = %user.id,
+        role = %user.role.as_str(),
+        status = 200,
+        "login response"
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(LoginResponse::Authenticated(SignupResponse {
+            token: pair.token,
+            refresh_token: pair.refresh_token,
+            expires_in: pair.expires_in,
+            user,
+        })),
+    ))
+}
+
+#[utoipa::path(
+    tag = "users",
+    operation_id = "getMe",
     get,
     path = "/users/me",
+    security(("bearer_auth" = []), ("api_key" = ["events:read"])),
     responses(
-        (status = 200, description = "Get current user", body = User)
+        (status = 200, description = "Get current user", body = UserWithRelatedData),
+        (status = 401, description = "Missing, expired, invalid or revoked token or API key", body = crate::results::ApiErrorBody),
+        (status = 403, description = "API key without the `events:read` scope", body = crate::results::ApiErrorBody)
     )
 )]
 pub async fn get_me(
     Extension(auth_context): Extension<AuthContext>,
     State(state): State<AppState>,
 ) -> ApiResult<StatusCode, UserWithRelatedData> {
+    authz::ensure_scope(&auth_context, ApiKeyScope::ReadEvents)?;
     info!(
         target: "api.users.me",
         user_id = %auth_context.user.id,
@@ -135,29 +283,243 @@ pub async fn get_me(
         "get_me request"
     );
 
-    let related_data = match auth_context.user.role {
-        UserRole::Organizer => Some(RelatedData::Organizer(
-            OrganizerData::get_data(&state.db, auth_context.user.id).await?,
-        )),
-        UserRole::Attendee => Some(RelatedData::Attendee(
-            AttendeeData::get_data(&state.db, auth_context.user.id).await?,
-        )),
-        _ => None,
-    };
+    let profile = load_profile(&state, auth_context.user.clone()).await?;
 
     info!(
         target: "api.users.me",
         user_id = %auth_context.user.id,
         status = 200,
-        has_related_data = related_data.is_some(),
+        has_related_data = profile.related_data.is_some(),
         "get_me response"
     );
 
-    Ok((
-        StatusCode::OK,
-        Json(UserWithRelatedData {
-            user: auth_context.user.clone(),
-            related_data,
-        }),
-    ))
+    Ok((StatusCode::OK, Json(profile)))
+}
+
+#[utoipa::path(
+    tag = "users",
+    operation_id = "updateMe",
+    patch,
+    path = "/users/me",
+    security(("bearer_auth" = [])),
+    request_body = UpdateMeRequest,
+    responses(
+        (status = 200, description = "Updated profile", body = UserWithRelatedData),
+        (status = 400, description = "Field not applicable to the user's role", body = crate::results::ApiErrorBody),
+        (status = 401, description = "Missing, expired, invalid or revoked token", body = crate::results::ApiErrorBody),
+        (status = 403, description = "Called with an API key", body = crate::results::ApiErrorBody),
+        (status = 422, description = "Validation failed (see `fields`)", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn update_me(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    ValidJson(req): ValidJson<UpdateMeRequest>,
+) -> ApiResult<StatusCode, UserWithRelatedData> {
+    let user_id = auth_context.user.id;
+    let mut req = validate_update(&auth_context.user, req)?;
+    let address = match req.address.take() {
+        Some(patch) => {
+            let current = addresses::sql::default_address(&state.db, user_id).await?;
+            Some(
+                addresses::handlers::updated_address(&state, "address", current.as_ref(), patch)
+                    .await?,
+            )
+        }
+        None => None,
+    };
+
+    let user = sql::update_profile(&state.db, user_id, &req, address.as_ref()).await?;
+    state.users.invalidate(user_id);
+
+    info!(
+        target: "api.users.me",
+        %user_id,
+        full_name = req.full_name.is_some(),
+        phone = req.phone.is_some(),
+        birth_date = req.birth_date.is_some(),
+        apelido = req.apelido.is_some(),
+        address = address.is_some(),
+        status = 200,
+        "profile updated"
+    );
+
+    Ok((StatusCode::OK, Json(load_profile(&state, user).await?)))
+}
+
+#[utoipa::path(
+    tag = "users",
+    operation_id = "changePassword",
+    post,
+    path = "/users/me/password",
+    security(("bearer_auth" = [])),
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 204, description = "Password changed; every other session signed out"),
+        (status = 403, description = "Wrong current password, or called with an API key", body = crate::results::ApiErrorBody),
+        (status = 422, description = "Validation failed (see `fields`)", body = crate::results::ApiErrorBody),
+        (status = 429, description = "Too many failed attempts (see `Retry-After`)", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn change_password(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    ValidJson(req): ValidJson<ChangePasswordRequest>,
+) -> Result<StatusCode, ApiError> {
+    let user = &auth_context.user;
+    // `require_session` keeps API keys out of this route.
+    let Credential::AccessToken { sid, .. } = auth_context.credential else {
+        return Err(ApiError::SessionRequired);
+    };
+    state
+        .password_policy
+        .check("newPassword", &req.new_password)
+        .await?;
+    check_current_password(&state, user, &req.current_password, ip).await?;
+
+    let password_hash = sql::hash_password(&req.new_password).map_err(|e| {
+        error!(target: "api.users.password", cause = %e, "password hashing failed");
+        ApiError::Internal
+    })?;
+    let mut tx = state.db.begin().await?;
+    sql::update_password_hash(&mut *tx, user.id, &password_hash).await?;
+    // Whoever knew the old password may be signed in elsewhere; this session stays.
+    let revoked = RevocationStore::revoke_other_sessions_in(&mut tx, user.id, sid).await?;
+    tx.commit().await?;
+    for &session_id in &revoked {
+        state.revocations.session_revoked(session_id);
+    }
+
+    info!(target: "api.users.password", user_id = %user.id, revoked_sessions = revoked.len(), status = 204, "password changed");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    tag = "users",
+    operation_id = "changeEmail",
+    post,
+    path = "/users/me/email",
+    security(("bearer_auth" = [])),
+    request_body = ChangeEmailRequest,
+    responses(
+        (status = 202, description = "Confirmation link sent to the new address; the email changes once it is used"),
+        (status = 400, description = "Same as the current email", body = crate::results::ApiErrorBody),
+        (status = 403, description = "Wrong current password, or called with an API key", body = crate::results::ApiErrorBody),
+        (status = 409, description = "Email already in use", body = crate::results::ApiErrorBody),
+        (status = 422, description = "Validation failed (see `fields`)", body = crate::results::ApiErrorBody),
+        (status = 429, description = "Too many failed attempts (see `Retry-After`)", body = crate::results::ApiErrorBody)
+    )
+)]
+pub async fn change_email(
+    Extension(auth_context): Extension<AuthContext>,
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    ValidJson(req): ValidJson<ChangeEmailRequest>,
+) -> Result<StatusCode, ApiError> {
+    let user = &auth_context.user;
+    let new_email = req.new_email.trim();
+    if new_email.eq_ignore_ascii_case(&user.email) {
+        return Err(ApiError::BadRequest(
+            "newEmail is the current email".to_string(),
+        ));
+    }
+    check_current_password(&state, user, &req.current_password, ip).await?;
+
+    // Checked again (by `users_email_unique`) when the change is confirmed.
+    if sql::find_user_by_email(&state.db, new_email)
+        .await?
+        .is_some()
+    {
+        return Err(ApiError::EmailTaken);
+    }
+
+    emails::send_email_change(&state, user, new_email)
+        .await
+        .map_err(|e| {
+            error!(target: "api.users.email", user_id = %user.id, cause = %e, "failed to send email change confirmation");
+            ApiError::Internal
+        })?;
+
+    info!(target: "api.users.email", user_id = %user.id, status = 202, "email change requested");
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Re-check the password of a signed-in user before a sensitive change. Failures count
+/// against the account and IP like failed logins.
+pub(crate) async fn check_current_password(
+    state: &AppState,
+    user: &User,
+    password: &str,
+    ip: IpAddr,
+) -> Result<(), ApiError> {
+    throttle::check(state, &user.email, ip).await?;
+    if sql::authenticate(&state.db, &user.email, password)
+        .await?
+        .is_none()
+    {
+        info!(target: "api.users.password", user_id = %user.id, %ip, "current password rejected");
+        throttle::record_failure(state, &user.email, ip).await?;
+        return Err(ApiError::InvalidPassword);
+    }
+    throttle::record_success(state, &user.email).await
+}
+
+/// The user with their role data and default address.
+pub(crate) async fn load_profile(
+    state: &AppState,
+    user: User,
+) -> Result<UserWithRelatedData, ApiError> {
+    let related_data = match user.role {
+        UserRole::Organizer => Some(RelatedData::Organizer(
+            OrganizerData::get_data(&state.db, user.id).await?,
+        )),
+        UserRole::Attendee => Some(RelatedData::Attendee(
+            AttendeeData::get_data(&state.db, user.id).await?,
+        )),
+        _ => None,
+    };
+    let address = addresses::sql::default_address(&state.db, user.id).await?;
+    let pending_consents = consents::sql::pending_documents(&state.db, user.id)
+        .await?
+        .into_iter()
+        .map(LegalDocumentRow::into_document)
+        .collect();
+    Ok(UserWithRelatedData {
+        user,
+        related_data,
+        address,
+        pending_consents,
+    })
+}
+
+/// Check a `PATCH /users/me` body against the user's role and trim its strings (the
+/// field rules already ran in `ValidJson`).
+fn validate_update(user: &User, mut req: UpdateMeRequest) -> Result<UpdateMeRequest, ApiError> {
+    let bad = |msg: &str| ApiError::BadRequest(msg.to_string());
+
+    if let Some(full_name) = &mut req.full_name {
+        *full_name = full_name.trim().to_string();
+    }
+
+    if (req.phone.is_some() || req.birth_date.is_some()) && user.role != UserRole::Attendee {
+        return Err(bad("phone and birthDate only apply to attendees"));
+    }
+    if let Some(phone) = &mut req.phone {
+        *phone = phone.trim().to_string();
+    }
+
+    if let Some(apelido) = &mut req.apelido {
+        if user.role != UserRole::Organizer {
+            return Err(bad("apelido only applies to organizers"));
+        }
+        // An empty apelido clears it.
+        *apelido = apelido
+            .as_deref()
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(str::to_string);
+    }
+
+    Ok(req)
 }
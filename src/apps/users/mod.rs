@@ -1,8 +1,10 @@
+pub mod cache;
 pub mod dto;
+pub mod gov_id;
 pub mod handlers;
 pub mod models;
 pub mod requests;
 pub mod routes;
 pub mod sql;
 
-pub use routes::{public_router, protected_router, router};
+pub use routes::{protected_router, public_router, router};
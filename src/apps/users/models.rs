@@ -5,6 +5,8 @@ use uuid::Uuid;
 
 use crate::results::ApiError;
 
+use super::gov_id::GovId;
+
 /// Roles supported by the system.
 ///
 /// Roles:
@@ -13,7 +15,7 @@ use crate::results::ApiError;
 /// - `Admin`: Administrator of the system
 /// - `Promoter`: Promoter of the event
 /// - `Colaborator`: QR code reader in the event
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum UserRole {
     Organizer,   // Producer
@@ -66,11 +68,42 @@ pub struct User {
     #[schema(nullable = false, example = "johnson@noxel.com")]
     pub email: String,
 
-    #[schema(nullable = false, example = 12345678901_i64)]
-    pub gov_identification: i64,
+    /// CPF (11 digits) or CNPJ (14 digits), digits only
+    #[schema(value_type = String, nullable = false, example = "52998224725")]
+    pub gov_identification: GovId,
 
     #[schema(nullable = false, example = "2026-01-01T00:00:00Z")]
     pub created_at: chrono::DateTime<chrono::Utc>,
+
+    /// Last profile change
+    #[schema(nullable = false, example = "2026-01-01T00:00:00Z")]
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+
+    /// Set when the account has been disabled
+    #[schema(nullable = true, example = json!(null))]
+    pub disabled_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Set once the user has confirmed their email address
+    #[schema(nullable = true, example = "2026-01-01T00:05:00Z")]
+    pub email_verified_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Set once the user has enrolled a second factor (TOTP)
+    #[schema(nullable = true, example = json!(null))]
+    pub mfa_enabled_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl User {
+    pub fn is_disabled(&self) -> bool {
+        self.disabled_at.is_some()
+    }
+
+    pub fn is_email_verified(&self) -> bool {
+        self.email_verified_at.is_some()
+    }
+
+    pub fn is_mfa_enabled(&self) -> bool {
+        self.mfa_enabled_at.is_some()
+    }
 }
 
 /// Row returned from database for User (with role as string)
@@ -80,8 +113,12 @@ pub struct UserRow {
     pub full_name: String,
     pub role: String,
     pub email: String,
-    pub gov_identification: i64,
+    pub gov_identification: GovId,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub disabled_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub email_verified_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub mfa_enabled_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl UserRow {
@@ -93,16 +130,31 @@ impl UserRow {
             email: self.email,
             gov_identification: self.gov_identification,
             created_at: self.created_at,
+            updated_at: self.updated_at,
+            disabled_at: self.disabled_at,
+            email_verified_at: self.email_verified_at,
+            mfa_enabled_at: self.mfa_enabled_at,
         }
     }
 }
 
+/// Row used by the login flow: user columns plus the stored Argon2id PHC string.
+#[derive(Debug, Clone, FromRow)]
+pub struct UserCredentialsRow {
+    #[sqlx(flatten)]
+    pub user: UserRow,
+    pub password_hash: String,
+}
+
 /// Organizer-specific data (1:1 with users where role = organizer)
 #[derive(Debug, Clone, Serialize, ToSchema, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct OrganizerData {
     pub id: Uuid,
     pub user_id: Uuid,
+    /// Public display name
+    #[schema(nullable = true, example = "Noxel Produções")]
+    pub apelido: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
@@ -124,30 +176,3 @@ pub enum RelatedData {
     Organizer(OrganizerData),
     Attendee(AttendeeData),
 }
-
-#[derive(Debug, serde::Deserialize, ToSchema)]
-#[serde(rename_all = "camelCase")]
-pub struct UserAddress {
-    /// CEP (Brazilian postal code)
-    #[schema(nullable = false, example = "01001-000")]
-    pub cep: String,
-
-    #[schema(nullable = false, example = "Avenida Paulista")]
-    pub logradouro: String,
-
-    #[schema(nullable = false, example = "123")]
-    pub numero: String,
-
-    #[schema(nullable = true, example = "Apto 12")]
-    pub complemento: Option<String>,
-
-    #[schema(nullable = true, example = "Centro")]
-    pub bairro: Option<String>,
-
-    #[schema(nullable = false, example = "São Paulo")]
-    pub cidade: String,
-
-    /// State abbreviation (e.g. SP)
-    #[schema(nullable = false, example = "SP")]
-    pub estado: String,
-}
@@ -1,28 +1,61 @@
+use serde::{Deserialize, Deserializer};
 use utoipa::ToSchema;
 
-use crate::apps::users::models::UserAddress;
+use crate::{
+    apps::{
+        addresses::requests::{CreateAddressRequest, UpdateAddressRequest},
+        users::gov_id::GovId,
+    },
+    validation::{Validate, Validator},
+};
+
+/// Longest `users.full_name` accepted.
+pub const MAX_FULL_NAME_CHARS: usize = 255;
+/// Longest `organizer_data.apelido` (`varchar(64)`).
+pub const MAX_APELIDO_CHARS: usize = 64;
 
 /// Request body for public signup endpoints.
 /// Role is inferred from the endpoint (organizer or attendee).
 #[derive(Debug, serde::Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SignupOrganizerRequest {
-    #[schema(nullable = false, example = "Johnson Smith")]
+    #[schema(
+        nullable = false,
+        min_length = 1,
+        max_length = 255,
+        example = "Johnson Smith"
+    )]
     pub full_name: String,
 
-    /// User's password (will be hashed with Argon2id)
-    #[schema(nullable = false, example = "123456")]
+    /// User's password (will be hashed with Argon2id). Must satisfy the password policy:
+    /// by default at least 8 characters and not a known breached password.
+    #[schema(nullable = false, example = "correct horse battery staple")]
     pub password: String,
 
-    #[schema(nullable = false, example = "johnson@noxel.com")]
+    #[schema(nullable = false, format = "email", example = "johnson@noxel.com")]
     pub email: String,
 
-    /// CPF (11 digits) or CNPJ (14 digits)
-    #[schema(nullable = false, example = 12345678901_i64)]
-    pub gov_identification: i64,
+    /// CPF (11 digits) or CNPJ (14 digits), with or without formatting
+    #[schema(value_type = String, nullable = false, example = "529.982.247-25")]
+    pub gov_identification: GovId,
 
+    /// Becomes the default address
     #[schema(nullable = false)]
-    pub address: UserAddress,
+    pub address: CreateAddressRequest,
+
+    /// Version of the terms of service the user accepted; must be the current one
+    /// (`GET /legal-documents`)
+    #[schema(nullable = false, example = "1")]
+    pub accepted_terms_version: String,
+
+    /// Version of the privacy policy the user accepted; must be the current one
+    #[schema(nullable = false, example = "1")]
+    pub accepted_privacy_version: String,
+
+    /// Whether the user agreed to receive marketing communications (default `false`)
+    #[serde(default)]
+    #[schema(nullable = false, example = false)]
+    pub marketing_opt_in: bool,
 }
 
 /// Request body for public signup endpoints.
@@ -30,26 +63,198 @@ pub struct SignupOrganizerRequest {
 #[derive(Debug, serde::Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SignupAttendeeRequest {
-    #[schema(nullable = false, example = "Robert Johnson Smith Junior the Third")]
+    #[schema(
+        nullable = false,
+        min_length = 1,
+        max_length = 255,
+        example = "Robert Johnson Smith Junior the Third"
+    )]
     pub full_name: String,
 
-    /// User's password (will be hashed with Argon2id)
-    #[schema(nullable = false, example = "123456")]
+    /// User's password (will be hashed with Argon2id). Must satisfy the password policy:
+    /// by default at least 8 characters and not a known breached password.
+    #[schema(nullable = false, example = "correct horse battery staple")]
     pub password: String,
 
-    #[schema(nullable = false, example = "+5511999999999")]
+    /// Digits with an optional leading `+`
+    #[schema(
+        nullable = false,
+        max_length = 15,
+        pattern = r"^\+?[0-9]{1,15}$",
+        example = "+5511999999999"
+    )]
     pub phone: String,
 
-    #[schema(nullable = false, example = "robert@noxel.com")]
+    #[schema(nullable = false, format = "email", example = "robert@noxel.com")]
     pub email: String,
 
-    /// CPF (11 digits) or CNPJ (14 digits)
-    #[schema(nullable = false, example = 12345678901_i64)]
-    pub gov_identification: i64,
+    /// CPF (11 digits) or CNPJ (14 digits), with or without formatting
+    #[schema(value_type = String, nullable = false, example = "529.982.247-25")]
+    pub gov_identification: GovId,
 
+    /// Cannot be in the future
     #[schema(nullable = false, example = "1990-01-31")]
     pub birth_date: chrono::NaiveDate,
 
+    /// Becomes the default address
     #[schema(nullable = false)]
-    pub address: UserAddress,
+    pub address: CreateAddressRequest,
+
+    /// Version of the terms of service the user accepted; must be the current one
+    /// (`GET /legal-documents`)
+    #[schema(nullable = false, example = "1")]
+    pub accepted_terms_version: String,
+
+    /// Version of the privacy policy the user accepted; must be the current one
+    #[schema(nullable = false, example = "1")]
+    pub accepted_privacy_version: String,
+
+    /// Whether the user agreed to receive marketing communications (default `false`)
+    #[serde(default)]
+    #[schema(nullable = false, example = false)]
+    pub marketing_opt_in: bool,
+}
+
+impl Validate for SignupOrganizerRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("fullName", &self.full_name)
+            .required()
+            .max_chars(MAX_FULL_NAME_CHARS);
+        v.field("password", &self.password).required();
+        v.field("email", &self.email).required().email();
+        v.nested("address", &self.address);
+        v.field("acceptedTermsVersion", &self.accepted_terms_version)
+            .required();
+        v.field("acceptedPrivacyVersion", &self.accepted_privacy_version)
+            .required();
+    }
+}
+
+impl Validate for SignupAttendeeRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("fullName", &self.full_name)
+            .required()
+            .max_chars(MAX_FULL_NAME_CHARS);
+        v.field("password", &self.password).required();
+        v.field("phone", &self.phone).required().phone();
+        v.field("email", &self.email).required().email();
+        v.date("birthDate", self.birth_date).not_in_future();
+        v.nested("address", &self.address);
+        v.field("acceptedTermsVersion", &self.accepted_terms_version)
+            .required();
+        v.field("acceptedPrivacyVersion", &self.accepted_privacy_version)
+            .required();
+    }
+}
+
+/// Request body for email/password login.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginRequest {
+    #[schema(nullable = false, example = "johnson@noxel.com")]
+    pub email: String,
+
+    #[schema(nullable = false, example = "correct horse battery staple")]
+    pub password: String,
+
+    /// Optional label shown in the session list (e.g. "iPhone 15")
+    #[schema(nullable = true, example = "iPhone 15")]
+    pub device_label: Option<String>,
+}
+
+/// Request body for `POST /users/me/password`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordRequest {
+    #[schema(nullable = false, example = "correct horse battery staple")]
+    pub current_password: String,
+
+    /// New password (will be hashed with Argon2id); must satisfy the password policy
+    #[schema(nullable = false, example = "correct horse battery staple")]
+    pub new_password: String,
+}
+
+impl Validate for ChangePasswordRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("currentPassword", &self.current_password)
+            .required();
+        v.field("newPassword", &self.new_password).required();
+    }
+}
+
+/// Request body for `POST /users/me/email`.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEmailRequest {
+    #[schema(nullable = false, example = "correct horse battery staple")]
+    pub current_password: String,
+
+    /// Address to switch to, once confirmed from the link sent there
+    #[schema(
+        nullable = false,
+        format = "email",
+        example = "johnson.smith@noxel.com"
+    )]
+    pub new_email: String,
+}
+
+impl Validate for ChangeEmailRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("currentPassword", &self.current_password)
+            .required();
+        v.field("newEmail", &self.new_email).required().email();
+    }
+}
+
+/// Deserialize a nullable field of a partial update: absent -> `None` (left unchanged),
+/// `null` -> `Some(None)` (cleared), a value -> `Some(Some(value))`.
+pub(crate) fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Request body for `PATCH /users/me`. Only the fields present are changed.
+///
+/// `phone` and `birthDate` apply to attendees, `apelido` to organizers.
+#[derive(Debug, serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdateMeRequest {
+    #[schema(min_length = 1, max_length = 255, example = "Johnson Smith")]
+    pub full_name: Option<String>,
+
+    /// Digits with an optional leading `+`
+    #[schema(
+        max_length = 15,
+        pattern = r"^\+?[0-9]{1,15}$",
+        example = "+5511999999999"
+    )]
+    pub phone: Option<String>,
+
+    /// Cannot be in the future
+    #[schema(example = "1990-01-31")]
+    pub birth_date: Option<chrono::NaiveDate>,
+
+    /// Public display name (at most 64 characters); `null` clears it
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>, nullable = true, max_length = 64, example = "Noxel Produções")]
+    pub apelido: Option<Option<String>>,
+
+    /// Changes the default address (created if the user has none)
+    pub address: Option<UpdateAddressRequest>,
+}
+
+impl Validate for UpdateMeRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("fullName", self.full_name.as_ref())
+            .not_blank()
+            .max_chars(MAX_FULL_NAME_CHARS);
+        v.field("phone", self.phone.as_ref()).phone();
+        v.date("birthDate", self.birth_date).not_in_future();
+        v.field("apelido", self.apelido.as_ref().and_then(Option::as_ref))
+            .max_chars(MAX_APELIDO_CHARS);
+        v.nested("address", self.address.as_ref());
+    }
 }
@@ -1,30 +1,74 @@
 use axum::{
-    middleware::from_fn,
-    routing::{get, post},
+    middleware::{from_fn, from_fn_with_state},
+    routing::{get, patch, post},
     Router,
 };
 
-use crate::{middleware::auth::require_auth, AppState};
+use crate::{
+    middleware::{
+        auth::require_auth,
+        authz::require_session,
+        rate_limit::{rate_limit, KeyBy, Quota, RateLimit},
+    },
+    AppState,
+};
 
 use super::handlers;
 
 /// Unauthenticated endpoints.
-pub fn public_router() -> Router<AppState> {
+///
+/// Rate limited per IP (`RATE_LIMIT_USERS_PUBLIC`, default 10/min): signup and login are
+/// the cheapest endpoints to abuse.
+pub fn public_router(state: AppState) -> Router<AppState> {
+    let limit = RateLimit::for_group(&state, "users_public", Quota::per_minute(10), KeyBy::Ip);
     Router::new()
         .route("/signup/organizer", post(handlers::signup_organizer))
         .route("/signup/attendee", post(handlers::signup_attendee))
+        .route("/login", post(handlers::login))
+        .route_layer(from_fn_with_state(limit, rate_limit))
+}
+
+/// Read-only profile: also open to API keys with the `events:read` scope.
+fn profile_router() -> Router<AppState> {
+    Router::new().route("/me", get(handlers::get_me))
+}
+
+/// Profile and account management: needs a full session, API keys are refused.
+fn account_router(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/me", patch(handlers::update_me))
+        .route("/me/password", post(handlers::change_password))
+        .route("/me/email", post(handlers::change_email))
+        .nest("/me/api-keys", crate::apps::api_keys::router())
+        .nest("/me/sessions", crate::apps::sessions::router())
+        .merge(crate::apps::exports::router(state))
+        .merge(crate::apps::deletion::router())
+        .merge(crate::apps::consents::router())
+        .merge(crate::apps::addresses::router())
+        .route_layer(from_fn(require_session))
 }
 
-/// Authenticated endpoints.
-pub fn protected_router() -> Router<AppState> {
+/// Authenticated endpoints (only `GET /me` accepts API keys).
+///
+/// Rate limited per user (`RATE_LIMIT_USERS_PROTECTED`, default 120/min).
+pub fn protected_router(state: AppState) -> Router<AppState> {
+    let limit = RateLimit::for_group(
+        &state,
+        "users_protected",
+        Quota::per_minute(120),
+        KeyBy::UserOrIp,
+    );
     Router::new()
-        .route("/me", get(handlers::get_me))
-        .route_layer(from_fn(require_auth))
+        .merge(profile_router())
+        .merge(account_router(&state))
+        // Runs inside `require_auth`, so buckets are keyed by user.
+        .route_layer(from_fn_with_state(limit, rate_limit))
+        .route_layer(from_fn_with_state(state, require_auth))
 }
 
-/// Convenience router (no auth layers applied here).
-pub fn router() -> Router<AppState> {
+/// Convenience router (auth is applied by `protected_router` only).
+pub fn router(state: AppState) -> Router<AppState> {
     Router::new()
-        .merge(public_router())
-        .merge(protected_router())
+        .merge(public_router(state.clone()))
+        .merge(protected_router(state))
 }
@@ -1,14 +1,22 @@
-use crate::apps::users::models::UserAddress;
+use crate::{
+    apps::{
+        addresses::{self, models::AddressFields},
+        consents::{self, models::NewConsent},
+    },
+    middleware::client_ip::ClientInfo,
+};
 
 use super::{
-    models::{AttendeeData, OrganizerData, User, UserRole, UserRow},
-    requests::{SignupAttendeeRequest, SignupOrganizerRequest},
+    gov_id::GovId,
+    models::{AttendeeData, OrganizerData, User, UserCredentialsRow, UserRole, UserRow},
+    requests::{SignupAttendeeRequest, SignupOrganizerRequest, UpdateMeRequest},
 };
 use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
-use sqlx::{PgPool, Postgres, Transaction};
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
+use std::sync::OnceLock;
 use uuid::Uuid;
 
 /// Common accessor interface for different signup request payloads.
@@ -16,7 +24,7 @@ trait SignupRequestLike {
     fn full_name(&self) -> &str;
     fn password(&self) -> &str;
     fn email(&self) -> &str;
-    fn gov_identification(&self) -> i64;
+    fn gov_identification(&self) -> &GovId;
 }
 
 impl SignupRequestLike for SignupOrganizerRequest {
@@ -32,8 +40,8 @@ impl SignupRequestLike for SignupOrganizerRequest {
         &self.email
     }
 
-    fn gov_identification(&self) -> i64 {
-        self.gov_identification
+    fn gov_identification(&self) -> &GovId {
+        &self.gov_identification
     }
 }
 
@@ -50,19 +58,77 @@ impl SignupRequestLike for SignupAttendeeRequest {
         &self.email
     }
 
-    fn gov_identification(&self) -> i64 {
-        self.gov_identification
+    fn gov_identification(&self) -> &GovId {
+        &self.gov_identification
     }
 }
 
 /// Hash a password using Argon2id and return the PHC string.
-fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
     let salt = SaltString::generate(&mut OsRng);
     let argon2 = Argon2::default(); // Uses Argon2id by default
     let password_hash = argon2.hash_password(password.as_bytes(), &salt)?;
     Ok(password_hash.to_string())
 }
 
+/// Verify a password against a stored Argon2id PHC string.
+///
+/// Returns `false` for a wrong password and for a malformed PHC string.
+pub fn verify_password(password: &str, phc: &str) -> bool {
+    match PasswordHash::new(phc) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(e) => {
+            tracing::error!(target: "api.users.login", cause = %e, "stored password hash is not a valid PHC string");
+            false
+        }
+    }
+}
+
+/// PHC string verified against when the email is unknown, so both paths cost one Argon2 run.
+fn dummy_password_hash() -> &'static str {
+    static DUMMY: OnceLock<String> = OnceLock::new();
+    DUMMY.get_or_init(|| {
+        hash_password("noxel-dummy-password").expect("hashing a constant password cannot fail")
+    })
+}
+
+/// Check email/password credentials.
+///
+/// The email is matched case-insensitively (same expression as `users_email_unique`).
+/// Returns `None` for both an unknown email and a wrong password; an Argon2 verification
+/// runs in either case so the two are indistinguishable by timing.
+pub async fn authenticate(
+    db: &PgPool,
+    email: &str,
+    password: &str,
+) -> Result<Option<User>, sqlx::Error> {
+    let row: Option<UserCredentialsRow> = sqlx::query_as(
+        r#"SELECT id, full_name, role, email, gov_identification, created_at, updated_at,
+                  disabled_at, email_verified_at, mfa_enabled_at, password_hash
+           FROM users
+           WHERE lower(email) = lower($1)"#,
+    )
+    .bind(email.trim())
+    .fetch_optional(db)
+    .await?;
+
+    match row {
+        Some(row) => {
+            if verify_password(password, &row.password_hash) {
+                Ok(Some(row.user.into_user()))
+            } else {
+                Ok(None)
+            }
+        }
+        None => {
+            verify_password(password, dummy_password_hash());
+            Ok(None)
+        }
+    }
+}
+
 async fn insert_user<R: SignupRequestLike>(
     tx: &mut Transaction<'_, Postgres>,
     role: UserRole,
@@ -75,7 +141,8 @@ async fn insert_user<R: SignupRequestLike>(
     let row: UserRow = sqlx::query_as(
         r#"INSERT INTO users (full_name, role, email, gov_identification, password_hash)
            VALUES ($1, $2, $3, $4, $5)
-           RETURNING id, full_name, role, email, gov_identification"#,
+           RETURNING id, full_name, role, email, gov_identification, created_at, updated_at,
+                  disabled_at, email_verified_at, mfa_enabled_at"#,
     )
     .bind(req.full_name())
     .bind(role.as_str())
@@ -88,50 +155,23 @@ async fn insert_user<R: SignupRequestLike>(
     Ok(row.into_user())
 }
 
-async fn insert_user_address(
-    tx: &mut Transaction<'_, Postgres>,
-    user_id: Uuid,
-    address: &UserAddress,
-) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        r#"INSERT INTO user_address (
-              user_id,
-              cep,
-              logradouro,
-              numero,
-              complemento,
-              bairro,
-              cidade,
-              estado
-           ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
-    )
-    .bind(user_id)
-    .bind(&address.cep)
-    .bind(&address.logradouro)
-    .bind(&address.numero)
-    .bind(&address.complemento)
-    .bind(&address.bairro)
-    .bind(&address.cidade)
-    .bind(&address.estado)
-    .execute(&mut **tx)
-    .await?;
-
-    Ok(())
-}
-
 pub async fn create_organizer_with_data(
     db: &PgPool,
     req: SignupOrganizerRequest,
+    address: &AddressFields,
+    consents: &[NewConsent],
+    client: &ClientInfo,
 ) -> Result<(User, OrganizerData), sqlx::Error> {
     let mut tx = db.begin().await?;
     let user = insert_user(&mut tx, UserRole::Organizer, &req).await?;
 
-    insert_user_address(&mut tx, user.id, &req.address).await?;
+    addresses::sql::insert_address(&mut tx, user.id, address, true).await?;
+    consents::sql::insert_consents(&mut *tx, user.id, consents, client).await?;
 
     let org: OrganizerData = sqlx::query_as(
         r#"INSERT INTO organizer_data (user_id)
            VALUES ($1)
-           RETURNING id, user_id, created_at"#,
+           RETURNING id, user_id, apelido, created_at"#,
     )
     .bind(user.id)
     .fetch_one(&mut *tx)
@@ -144,11 +184,15 @@ pub async fn create_organizer_with_data(
 pub async fn create_attendee_with_data(
     db: &PgPool,
     req: SignupAttendeeRequest,
+    address: &AddressFields,
+    consents: &[NewConsent],
+    client: &ClientInfo,
 ) -> Result<(User, AttendeeData), sqlx::Error> {
     let mut tx = db.begin().await?;
     let user = insert_user(&mut tx, UserRole::Attendee, &req).await?;
 
-    insert_user_address(&mut tx, user.id, &req.address).await?;
+    addresses::sql::insert_address(&mut tx, user.id, address, true).await?;
+    consents::sql::insert_consents(&mut *tx, user.id, consents, client).await?;
 
     let consumer: AttendeeData = sqlx::query_as::<_, AttendeeData>(
         r#"INSERT INTO consumer_data (user_id, phone, birth_date)
@@ -165,9 +209,104 @@ pub async fn create_attendee_with_data(
     Ok((user, consumer))
 }
 
+pub async fn get_user_by_id(db: &PgPool, id: Uuid) -> Result<Option<User>, sqlx::Error> {
+    let row: Option<UserRow> = sqlx::query_as(
+        r#"SELECT id, full_name, role, email, gov_identification, created_at, updated_at,
+                  disabled_at, email_verified_at, mfa_enabled_at
+           FROM users
+           WHERE id = $1"#,
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await?;
+    Ok(row.map(UserRow::into_user))
+}
+
+/// Look a user up by email, case-insensitively (same expression as `users_email_unique`).
+pub async fn find_user_by_email(db: &PgPool, email: &str) -> Result<Option<User>, sqlx::Error> {
+    let row: Option<UserRow> = sqlx::query_as(
+        r#"SELECT id, full_name, role, email, gov_identification, created_at, updated_at,
+                  disabled_at, email_verified_at, mfa_enabled_at
+           FROM users
+           WHERE lower(email) = lower($1)"#,
+    )
+    .bind(email.trim())
+    .fetch_optional(db)
+    .await?;
+    Ok(row.map(UserRow::into_user))
+}
+
+/// Apply a validated `PATCH /users/me` in one transaction: user fields, role data and
+/// the default address (`address` is the complete new address, created if the user had
+/// none). Bumps `users.updated_at` and returns the updated user.
+pub async fn update_profile(
+    db: &PgPool,
+    user_id: Uuid,
+    req: &UpdateMeRequest,
+    address: Option<&AddressFields>,
+) -> Result<User, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let row: UserRow = sqlx::query_as(
+        r#"UPDATE users
+           SET full_name = COALESCE($2, full_name),
+               updated_at = now()
+           WHERE id = $1
+           RETURNING id, full_name, role, email, gov_identification, created_at, updated_at,
+                  disabled_at, email_verified_at, mfa_enabled_at"#,
+    )
+    .bind(user_id)
+    .bind(&req.full_name)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if req.phone.is_some() || req.birth_date.is_some() {
+        sqlx::query(
+            r#"UPDATE consumer_data
+               SET phone = COALESCE($2, phone),
+                   birth_date = COALESCE($3, birth_date)
+               WHERE user_id = $1"#,
+        )
+        .bind(user_id)
+        .bind(&req.phone)
+        .bind(req.birth_date)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    if let Some(apelido) = &req.apelido {
+        sqlx::query(r#"UPDATE organizer_data SET apelido = $2 WHERE user_id = $1"#)
+            .bind(user_id)
+            .bind(apelido)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    if let Some(address) = address {
+        addresses::sql::save_default(&mut tx, user_id, address).await?;
+    }
+
+    tx.commit().await?;
+    Ok(row.into_user())
+}
+
+/// Replace a user's password hash (already hashed with `hash_password`).
+pub async fn update_password_hash(
+    db: impl PgExecutor<'_>,
+    user_id: Uuid,
+    password_hash: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"UPDATE users SET password_hash = $2 WHERE id = $1"#)
+        .bind(user_id)
+        .bind(password_hash)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
 impl AttendeeData {
     pub async fn get_data(pool: &PgPool, user_id: Uuid) -> Result<Self, sqlx::Error> {
-        sqlx::query_as::<_, AttendeeData>(r#"SELECT * FROM attendee_data WHERE user_id = $1"#)
+        sqlx::query_as::<_, AttendeeData>(r#"SELECT * FROM consumer_data WHERE user_id = $1"#)
             .bind(user_id)
             .fetch_one(pool)
             .await
@@ -1,6 +1,8 @@
-use axum::http::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+use axum::http::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
 use tower_http::cors::{Any, CorsLayer};
 
+use crate::middleware::rate_limit::{RATELIMIT_LIMIT, RATELIMIT_REMAINING, RATELIMIT_RESET};
+
 /// Parse CORS_ALLOWED_ORIGINS env var (comma-separated list) into allowed origins.
 /// Example: CORS_ALLOWED_ORIGINS=http://localhost:3000,https://app.example.com
 pub fn cors_layer_from_env() -> CorsLayer {
@@ -16,10 +18,15 @@ pub fn cors_layer_from_env() -> CorsLayer {
         })
         .unwrap_or_default();
 
-    let layer =
-        CorsLayer::new()
-            .allow_methods(Any)
-            .allow_headers([ACCEPT, AUTHORIZATION, CONTENT_TYPE]);
+    let layer = CorsLayer::new()
+        .allow_methods(Any)
+        .allow_headers([ACCEPT, AUTHORIZATION, CONTENT_TYPE])
+        .expose_headers([
+            RETRY_AFTER,
+            RATELIMIT_LIMIT,
+            RATELIMIT_REMAINING,
+            RATELIMIT_RESET,
+        ]);
 
     if origins.is_empty() {
         tracing::debug!("CORS: no CORS_ALLOWED_ORIGINS set, CORS layer allows no origins");
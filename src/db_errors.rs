@@ -0,0 +1,114 @@
+//! Translation of Postgres constraint violations into `ApiError`s.
+//!
+//! Every `sqlx::Error` converted with `?` goes through `translate`: known constraints (and
+//! the errors raised by `tickets_before_insert_guard`, which carry a constraint name since
+//! migration 0029) become `409`s with their own code, or `422 validation_failed` on the
+//! offending field. Anything else stays a `500 db_error`.
+
+use crate::{results::ApiError, validation::FieldError};
+
+/// SQLSTATE `unique_violation`.
+const UNIQUE_VIOLATION: &str = "23505";
+/// SQLSTATE `check_violation`.
+const CHECK_VIOLATION: &str = "23514";
+/// SQLSTATE `not_null_violation`.
+const NOT_NULL_VIOLATION: &str = "23502";
+/// SQLSTATE `foreign_key_violation`.
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+
+/// Constraints guarding a single request field: (constraint, field path, code, message).
+///
+/// Paths are the JSON names of the request fields the columns come from, without any parent
+/// object: `user_address` rows are written both from a nested `address` (signup, profile)
+/// and from the top-level fields of the address endpoints.
+const FIELD_CONSTRAINTS: &[(&str, &str, &str, &str)] = &[
+    (
+        "users_gov_identification_digits_chk",
+        "govIdentification",
+        "invalid_gov_identification",
+        "must be a CPF (11 digits) or a CNPJ (14 digits)",
+    ),
+    (
+        "user_address_cep_digits_chk",
+        "cep",
+        "invalid_cep",
+        "must be 8 digits",
+    ),
+    // Unnamed check of 0008_user_address.sql (Postgres' default name).
+    (
+        "user_address_estado_check",
+        "estado",
+        "invalid_uf",
+        "must be 2 characters (e.g. SP)",
+    ),
+    (
+        "events_cep_format_chk",
+        "cep",
+        "invalid_cep",
+        "must be 8 digits, optionally as 00000-000",
+    ),
+    (
+        "events_estado_len_chk",
+        "estado",
+        "invalid_uf",
+        "must be 2 characters (e.g. SP)",
+    ),
+    (
+        "events_time_range_chk",
+        "endsAt",
+        "invalid_time_range",
+        "must not be before startsAt",
+    ),
+    (
+        "tickets_guard_lot_required",
+        "lotId",
+        "required",
+        "must not be empty",
+    ),
+    (
+        "tickets_guard_lot_exists",
+        "lotId",
+        "invalid_lot",
+        "no such ticket lot",
+    ),
+    (
+        "tickets_guard_lot_event_match",
+        "lotId",
+        "lot_event_mismatch",
+        "the lot belongs to another event",
+    ),
+];
+
+/// Map a database error to the `ApiError` the client should see.
+pub fn translate(err: sqlx::Error) -> ApiError {
+    let Some(db) = err.as_database_error() else {
+        return ApiError::Db(err);
+    };
+    let (Some(code), Some(constraint)) = (db.code(), db.constraint()) else {
+        return ApiError::Db(err);
+    };
+
+    let mapped = match (code.as_ref(), constraint) {
+        (UNIQUE_VIOLATION, "users_email_unique") => ApiError::EmailTaken,
+        (UNIQUE_VIOLATION, "tickets_qr_code_unique") => ApiError::QrCodeTaken,
+        (UNIQUE_VIOLATION, "legal_documents_kind_version_unique") => ApiError::DocumentVersionTaken,
+        (CHECK_VIOLATION, "tickets_guard_lot_not_sold_out") => ApiError::LotSoldOut,
+        (CHECK_VIOLATION, "tickets_guard_lot_sellable") => ApiError::LotNotSellable,
+        (CHECK_VIOLATION | NOT_NULL_VIOLATION | FOREIGN_KEY_VIOLATION, _) => {
+            match FIELD_CONSTRAINTS
+                .iter()
+                .find(|(name, ..)| *name == constraint)
+            {
+                Some(&(_, path, code, message)) => ApiError::Validation(vec![FieldError {
+                    path: path.to_string(),
+                    code,
+                    message: message.to_string(),
+                }]),
+                None => return ApiError::Db(err),
+            }
+        }
+        _ => return ApiError::Db(err),
+    };
+    tracing::debug!(target: "api.db", %code, constraint, mapped = ?mapped.code(), "constraint violation");
+    mapped
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use axum::async_trait;
+
+/// An outgoing transactional email.
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Pluggable delivery of transactional emails (password reset, verification, ...).
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> anyhow::Result<()>;
+}
+
+/// Development mailer: writes emails to the log instead of sending them.
+///
+/// The body contains live tokens, so never use it in production.
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: Email) -> anyhow::Result<()> {
+        tracing::info!(
+            target: "mailer",
+            to = %email.to,
+            subject = %email.subject,
+            body = %email.body,
+            "email (log mailer, not delivered)"
+        );
+        Ok(())
+    }
+}
+
+/// Build the mailer selected by `MAILER` (currently only `log`, the default).
+pub fn mailer_from_env() -> anyhow::Result<Arc<dyn Mailer>> {
+    match std::env::var("MAILER").as_deref() {
+        Err(_) | Ok("") | Ok("log") => Ok(Arc::new(LogMailer)),
+        Ok(other) => anyhow::bail!("unknown MAILER `{other}`"),
+    }
+}
+
+/// Base URL of the web app, used to build links in emails (`APP_BASE_URL`).
+pub fn app_base_url() -> String {
+    std::env::var("APP_BASE_URL")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| s.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| "http://localhost:3000".to_string())
+}
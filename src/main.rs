@@ -1,20 +1,39 @@
 mod apps;
+mod cache;
 mod cors;
+mod db_errors;
+mod mailer;
 mod middleware;
 mod results;
 mod routes;
 mod state;
+mod validation;
 
 use anyhow::Result;
 
 use axum::{extract::Request, middleware::Next};
 use sqlx::{PgPool, Pool, Postgres};
 use std::net::SocketAddr;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::{cors::cors_layer_from_env, results::ApiError, state::AppState};
+use crate::{
+    apps::{
+        addresses, api_keys,
+        auth::{
+            password_policy::PasswordPolicy, revocation::RevocationStore, throttle::LoginThrottle,
+        },
+        deletion,
+        exports::{self, config::ExportConfig},
+        users::cache::UserCache,
+    },
+    cache::TtlCache,
+    cors::cors_layer_from_env,
+    middleware::{authz::MfaPolicy, jwt_keys::JwtKeys, rate_limit},
+    results::ApiError,
+    state::AppState,
+};
 
 async fn log_requests(request: Request, next: Next) -> axum::response::Response {
     let method = request.method().clone();
@@ -95,14 +114,93 @@ async fn main() -> Result<()> {
     let db: Pool<Postgres> = PgPool::connect(&database_url).await?;
     tracing::info!("Database connection established");
 
-    let state = AppState { db };
+    // `noxel-rust-backend process-deletions`: anonymize accounts whose deletion grace
+    // period has ended, then exit (e.g. from a daily cron job).
+    if std::env::args().nth(1).as_deref() == Some("process-deletions") {
+        let anonymized = deletion::jobs::process_due(&db, None).await?;
+        tracing::info!(
+            count = anonymized.len(),
+            "processed pending account deletions"
+        );
+        return Ok(());
+    }
+
+    // `noxel-rust-backend import-ceps <file.csv>`: load a CEP dataset into `cep_directory`,
+    // then exit (see `addresses::lookup` for the format).
+    if std::env::args().nth(1).as_deref() == Some("import-ceps") {
+        let path = std::env::args()
+            .nth(2)
+            .ok_or_else(|| anyhow::anyhow!("usage: import-ceps <file.csv>"))?;
+        let data = std::fs::read_to_string(&path)?;
+        let entries = addresses::lookup::parse_dataset(&data)?;
+        let written = addresses::sql::import_directory(&db, &entries).await?;
+        tracing::info!(%path, ceps = entries.len(), written, "imported CEP dataset");
+        return Ok(());
+    }
+
+    let jwt = JwtKeys::from_env()?;
+    let rate_limiter = rate_limit::backend_from_env(&db)?;
+    let cep_resolver = addresses::lookup::resolver_from_env(&db)?;
+
+    let state = AppState {
+        db,
+        jwt,
+        revocations: RevocationStore::from_env(),
+        users: UserCache::from_env(),
+        api_key_uses: TtlCache::new(api_keys::API_KEY_USE_INTERVAL),
+        login_throttle: LoginThrottle::from_env(),
+        password_policy: PasswordPolicy::from_env()?,
+        rate_limiter,
+        mailer: mailer::mailer_from_env()?,
+        mfa_policy: MfaPolicy::from_env()?,
+        exports: ExportConfig::from_env(),
+        cep_resolver,
+    };
+
+    // Background exports run in-process: those left pending by a previous run never finish.
+    match exports::sql::fail_interrupted(&state.db).await {
+        Ok(0) => {}
+        Ok(failed) => tracing::warn!(failed, "marked interrupted data exports as failed"),
+        Err(e) => tracing::warn!(cause = %e, "failed to mark interrupted data exports"),
+    }
+
+    // Periodic cleanup: revoked token ids are only needed until the tokens would have
+    // expired anyway, login throttle rows until their failures fall out of the window, and
+    // rate limit buckets idle for `rate_limit::MAX_PERIOD` are full again, and
+    // data exports are deleted once their download window has closed.
+    {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+            loop {
+                interval.tick().await;
+                match state.revocations.purge_expired(&state.db).await {
+                    Ok(purged) => tracing::debug!(purged, "purged expired revoked tokens"),
+                    Err(e) => tracing::warn!(cause = %e, "failed to purge expired revoked tokens"),
+                }
+                match state.login_throttle.purge_expired(&state.db).await {
+                    Ok(purged) => tracing::debug!(purged, "purged stale login throttle rows"),
+                    Err(e) => tracing::warn!(cause = %e, "failed to purge login throttle rows"),
+                }
+                match state.rate_limiter.purge_idle(rate_limit::MAX_PERIOD).await {
+                    Ok(purged) => tracing::debug!(purged, "purged idle rate limit buckets"),
+                    Err(e) => tracing::warn!(cause = %e, "failed to purge rate limit buckets"),
+                }
+                state.api_key_uses.purge_expired();
+                match exports::sql::purge_expired(&state.db).await {
+                    Ok(purged) => tracing::debug!(purged, "purged expired data exports"),
+                    Err(e) => tracing::warn!(cause = %e, "failed to purge data exports"),
+                }
+            }
+        });
+    }
 
     let port: u16 = std::env::var("PORT")
         .ok()
         .and_then(|s| s.parse().ok())
         .unwrap_or(8080);
 
-    let app = routes::router()
+    let app = routes::router(state.clone())
         .with_state(state)
         .layer(cors_layer_from_env())
         .layer(axum::middleware::from_fn(log_requests));
@@ -110,9 +208,13 @@ async fn main() -> Result<()> {
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     tracing::info!(%addr, "listening");
 
-    axum::serve(tokio::net::TcpListener::bind(addr).await.unwrap(), app)
-        .await
-        .unwrap();
+    // Peer addresses feed `ClientIp` (login throttling, rate limits).
+    axum::serve(
+        tokio::net::TcpListener::bind(addr).await.unwrap(),
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 
     Ok(())
 }
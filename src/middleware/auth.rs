@@ -1,46 +1,185 @@
 use axum::{
-    extract::Request,
-    http::{header, StatusCode},
+    extract::{Request, State},
+    http::{header, HeaderMap},
     middleware::Next,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
+use jsonwebtoken::errors::ErrorKind;
+use uuid::Uuid;
 
-use crate::{apps::users::models::User, middleware};
+use crate::{
+    apps::{
+        api_keys::{self, models::ApiKeyScope},
+        auth::tokens,
+        users::models::{User, UserRole},
+    },
+    middleware,
+    results::ApiError,
+    AppState,
+};
 
 #[derive(Debug, Clone)]
 pub struct AuthContext {
     pub user: User,
+    /// How the request authenticated
+    pub credential: Credential,
+}
+
+/// Credential presented with a request.
+#[derive(Debug, Clone)]
+pub enum Credential {
+    /// `Authorization: Bearer <jwt>`: a full user session
+    AccessToken {
+        /// Id (`jti`) of the access token
+        jti: Uuid,
+        /// Expiry (`exp`) of the access token
+        exp: u64,
+        /// Session the token belongs to
+        sid: Uuid,
+        /// Admin acting as the user, for impersonation tokens (`act` claim)
+        impersonator: Option<Uuid>,
+    },
+    /// `Authorization: ApiKey <key>`: limited to the key's scopes
+    ApiKey { id: Uuid, scopes: Vec<ApiKeyScope> },
 }
 
-/// Very small auth middleware placeholder.
-/// Current behavior (intentionally simple):
-/// - Requires `Authorization: Bearer <something>`
+/// Auth middleware:
+/// - Requires `Authorization: Bearer <jwt>` or `Authorization: ApiKey <key>`
+/// - Verifies the JWT signature and expiry (API keys: looks the key up by hash)
+/// - Rejects tokens that were revoked (logout), whose session was signed out, or issued
+///   before the user's `tokens_valid_after` cut-off (logout everywhere)
+/// - Loads the current user (cached briefly on `AppState`) into `AuthContext`
+/// - Enforces the `MfaPolicy`: users whose role requires a second factor must have enrolled
 ///
-/// Replace this with real JWT/session validation.
-pub async fn require_auth(mut req: Request, next: Next) -> impl IntoResponse {
-    let auth = req
-        .headers()
+/// Failures are regular `ApiError` responses (`token_missing`, `token_expired`,
+/// `token_invalid`, `token_revoked`, `invalid_api_key`, `account_disabled`,
+/// `mfa_required`) with a `WWW-Authenticate` challenge on the 401s.
+///
+/// API keys are accepted on every route behind this layer; routes that need a full
+/// session add `authz::require_session`, and scoped routes check `authz::ensure_scope`.
+pub async fn require_auth(State(state): State<AppState>, mut req: Request, next: Next) -> Response {
+    let auth_context = match authenticate(&state, req.headers()).await {
+        Ok(auth_context) => auth_context,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(e) = state.mfa_policy.ensure(&auth_context) {
+        return e.into_response();
+    }
+    req.extensions_mut().insert(auth_context);
+    next.run(req).await
+}
+
+/// `require_auth` without the `MfaPolicy` check, for the routes a user must still reach
+/// before enrolling a second factor (TOTP enrollment, logout).
+pub async fn require_auth_allow_mfa_enrollment(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    match authenticate(&state, req.headers()).await {
+        Ok(auth_context) => {
+            req.extensions_mut().insert(auth_context);
+            next.run(req).await
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn authenticate(state: &AppState, headers: &HeaderMap) -> Result<AuthContext, ApiError> {
+    let auth = headers
         .get(header::AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
         .unwrap_or("");
 
+    if let Some(key) = auth.strip_prefix("ApiKey ") {
+        return authenticate_api_key(state, key.trim()).await;
+    }
+
     let token = auth.strip_prefix("Bearer ").unwrap_or(auth);
     if token.trim().is_empty() {
-        return (StatusCode::UNAUTHORIZED, "missing token").into_response();
+        return Err(ApiError::TokenMissing);
     }
 
-    let secret = match std::env::var("JWT_SECRET") {
-        Ok(v) if !v.is_empty() => v,
-        _ => return (StatusCode::INTERNAL_SERVER_ERROR, "missing JWT_SECRET").into_response(),
-    };
+    let claims = middleware::jwt::verify_token(token, &state.jwt).map_err(|e| match e.kind() {
+        ErrorKind::ExpiredSignature => ApiError::TokenExpired,
+        _ => ApiError::TokenInvalid,
+    })?;
 
-    let claims = match middleware::jwt::verify_token(token, &secret) {
-        Ok(claims) => claims,
-        Err(_) => return (StatusCode::UNAUTHORIZED, "invalid token").into_response(),
-    };
+    if state
+        .revocations
+        .is_revoked(&state.db, claims.jti, claims.sub, claims.iat_ms)
+        .await?
+        || state
+            .revocations
+            .is_session_revoked(&state.db, claims.sid)
+            .await?
+    {
+        return Err(ApiError::TokenRevoked);
+    }
 
-    req.extensions_mut().insert(AuthContext {
-        user: claims.user.clone(),
-    });
-    next.run(req).await
+    // Always act on the current user row, not on what the token says: role changes and
+    // account disables apply to the next request on the instance that made them, and on
+    // the others once their cached copy expires (see `UserCache`).
+    let user = state
+        .users
+        .get(&state.db, claims.sub)
+        .await?
+        .ok_or(ApiError::TokenInvalid)?;
+    if user.is_disabled() {
+        return Err(ApiError::AccountDisabled);
+    }
+
+    let impersonator = claims.act.map(|act| act.sub);
+    if let Some(admin_id) = impersonator {
+        tracing::info!(target: "api.admin.impersonation", %admin_id, user_id = %user.id, jti = %claims.jti, "impersonated request");
+    }
+
+    Ok(AuthContext {
+        user,
+        credential: Credential::AccessToken {
+            jti: claims.jti,
+            exp: claims.exp,
+            sid: claims.sid,
+            impersonator,
+        },
+    })
+}
+
+async fn authenticate_api_key(state: &AppState, key: &str) -> Result<AuthContext, ApiError> {
+    let api_key = api_keys::sql::find_active_api_key(&state.db, &tokens::hash_token(key))
+        .await?
+        .ok_or(ApiError::InvalidApiKey)?;
+
+    let user = state
+        .users
+        .get(&state.db, api_key.user_id)
+        .await?
+        .ok_or(ApiError::InvalidApiKey)?;
+    if user.is_disabled() {
+        return Err(ApiError::AccountDisabled);
+    }
+    // Keys belong to organizers; they stop working if the account changes role.
+    if user.role != UserRole::Organizer {
+        return Err(ApiError::InvalidApiKey);
+    }
+
+    // Last-used tracking must not slow down or fail the request, nor write on every one.
+    if state.api_key_uses.get(&api_key.id).is_none() {
+        state.api_key_uses.insert(api_key.id, ());
+        let db = state.db.clone();
+        let id = api_key.id;
+        tokio::spawn(async move {
+            if let Err(e) = api_keys::sql::touch_api_key(&db, id).await {
+                tracing::warn!(target: "api.auth", api_key_id = %id, cause = %e, "failed to record api key use");
+            }
+        });
+    }
+
+    Ok(AuthContext {
+        user,
+        credential: Credential::ApiKey {
+            id: api_key.id,
+            scopes: api_key.scopes(),
+        },
+    })
 }
@@ -0,0 +1,220 @@
+//! Role-based authorization on top of `AuthContext`.
+
+use std::{marker::PhantomData, ops::Deref};
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::request::Parts,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    apps::{api_keys::models::ApiKeyScope, users::models::UserRole},
+    middleware::auth::{AuthContext, Credential},
+    results::ApiError,
+};
+
+/// A set of roles, checked at the type level by `RequireRole`.
+///
+/// Implemented by the role markers below (declared as handlers need them) and by tuples of
+/// them, so `RequireRole<(Organizer, Admin)>` accepts either role.
+pub trait RoleSet: Send + Sync + 'static {
+    fn allows(role: &UserRole) -> bool;
+}
+
+macro_rules! role_marker {
+    ($(#[$doc:meta] $name:ident),* $(,)?) => {
+        $(
+            #[$doc]
+            pub enum $name {}
+
+            impl RoleSet for $name {
+                fn allows(role: &UserRole) -> bool {
+                    *role == UserRole::$name
+                }
+            }
+        )*
+    };
+}
+
+role_marker!(
+    /// Marker for `UserRole::Organizer`.
+    Organizer,
+);
+
+impl<A: RoleSet, B: RoleSet> RoleSet for (A, B) {
+    fn allows(role: &UserRole) -> bool {
+        A::allows(role) || B::allows(role)
+    }
+}
+
+impl<A: RoleSet, B: RoleSet, C: RoleSet> RoleSet for (A, B, C) {
+    fn allows(role: &UserRole) -> bool {
+        A::allows(role) || B::allows(role) || C::allows(role)
+    }
+}
+
+/// Extractor that only lets through users whose role is in `R`.
+///
+/// Must run behind `require_auth` (it reads the `AuthContext` it inserts):
+/// - no `AuthContext` -> `ApiError::Unauthorized`
+/// - role not in `R` -> `ApiError::Forbidden`
+///
+/// ```ignore
+/// async fn create_event(RequireRole(auth, ..): RequireRole<Organizer>) { ... }
+/// ```
+pub struct RequireRole<R: RoleSet>(pub AuthContext, pub PhantomData<R>);
+
+impl<R: RoleSet> Deref for RequireRole<R> {
+    type Target = AuthContext;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[async_trait]
+impl<S, R> FromRequestParts<S> for RequireRole<R>
+where
+    S: Send + Sync,
+    R: RoleSet,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let auth_context = parts
+            .extensions
+            .get::<AuthContext>()
+            .cloned()
+            .ok_or(ApiError::Unauthorized)?;
+        if !R::allows(&auth_context.user.role) {
+            tracing::debug!(
+                target: "api.authz",
+                user_id = %auth_context.user.id,
+                role = %auth_context.user.role.as_str(),
+                "role not allowed"
+            );
+            return Err(ApiError::Forbidden);
+        }
+        Ok(RequireRole(auth_context, PhantomData))
+    }
+}
+
+/// Route layer restricting a whole router to a set of roles.
+///
+/// `route_layer`s run outermost-last, so add this *before* the `require_auth` layer:
+///
+/// ```ignore
+/// Router::new()
+///     .route("/users", get(list_users))
+///     .route_layer(from_fn_with_state(&[UserRole::Admin][..], require_roles))
+///     .route_layer(from_fn_with_state(state, require_auth))
+/// ```
+pub async fn require_roles(
+    State(allowed): State<&'static [UserRole]>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(auth_context) = req.extensions().get::<AuthContext>() else {
+        return ApiError::Unauthorized.into_response();
+    };
+    if !allowed.contains(&auth_context.user.role) {
+        tracing::debug!(
+            target: "api.authz",
+            user_id = %auth_context.user.id,
+            role = %auth_context.user.role.as_str(),
+            "role not allowed"
+        );
+        return ApiError::Forbidden.into_response();
+    }
+    next.run(req).await
+}
+
+/// Route layer keeping API keys and impersonation tokens out: account management
+/// (credentials, sessions, keys, MFA) needs the user's own login. Add it *before* the
+/// `require_auth` layer, like `require_roles`.
+pub async fn require_session(req: Request, next: Next) -> Response {
+    let Some(auth_context) = req.extensions().get::<AuthContext>() else {
+        return ApiError::Unauthorized.into_response();
+    };
+    match &auth_context.credential {
+        Credential::ApiKey { id, .. } => {
+            tracing::debug!(
+                target: "api.authz",
+                user_id = %auth_context.user.id,
+                api_key_id = %id,
+                "blocked: api key on a session-only route"
+            );
+            return ApiError::SessionRequired.into_response();
+        }
+        Credential::AccessToken {
+            impersonator: Some(admin_id),
+            ..
+        } => {
+            tracing::info!(
+                target: "api.authz",
+                user_id = %auth_context.user.id,
+                %admin_id,
+                "blocked: impersonation token on a session-only route"
+            );
+            return ApiError::ImpersonationNotAllowed.into_response();
+        }
+        Credential::AccessToken { .. } => {}
+    }
+    next.run(req).await
+}
+
+/// Scope check for routes open to API keys. Full sessions (JWT) pass; API keys need
+/// `scope` among their scopes (`ApiError::InsufficientScope` otherwise).
+pub fn ensure_scope(auth_context: &AuthContext, scope: ApiKeyScope) -> Result<(), ApiError> {
+    match &auth_context.credential {
+        Credential::AccessToken { .. } => Ok(()),
+        Credential::ApiKey { scopes, .. } if scopes.contains(&scope) => Ok(()),
+        Credential::ApiKey { .. } => Err(ApiError::InsufficientScope(scope.as_str())),
+    }
+}
+
+/// Roles that must use two-factor authentication. Configured with `MFA_REQUIRED_ROLES`,
+/// a comma-separated list of roles (default `admin`; empty disables the policy).
+///
+/// Users with such a role who have not enrolled yet can still log in, but `require_auth`
+/// only lets them reach the enrollment endpoints (`ApiError::MfaRequired` elsewhere).
+#[derive(Debug, Clone)]
+pub struct MfaPolicy {
+    required_roles: Vec<UserRole>,
+}
+
+impl MfaPolicy {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let spec = std::env::var("MFA_REQUIRED_ROLES").unwrap_or_else(|_| "admin".to_string());
+        let required_roles = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .map(UserRole::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { required_roles })
+    }
+
+    /// Whether users with `role` must have a second factor.
+    pub fn is_required_for(&self, role: &UserRole) -> bool {
+        self.required_roles.contains(role)
+    }
+
+    /// `ApiError::MfaRequired` if the policy requires a second factor the user lacks.
+    pub fn ensure(&self, auth_context: &AuthContext) -> Result<(), ApiError> {
+        let user = &auth_context.user;
+        if self.is_required_for(&user.role) && !user.is_mfa_enabled() {
+            tracing::debug!(
+                target: "api.authz",
+                user_id = %user.id,
+                role = %user.role.as_str(),
+                "blocked: mfa enrollment required"
+            );
+            return Err(ApiError::MfaRequired);
+        }
+        Ok(())
+    }
+}
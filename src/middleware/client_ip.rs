@@ -0,0 +1,88 @@
+use std::{
+    convert::Infallible,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::OnceLock,
+};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts, Extensions, HeaderMap},
+};
+
+/// Address of the client that sent the request.
+///
+/// Taken from the TCP peer address (the server must be started with
+/// `into_make_service_with_connect_info::<SocketAddr>()`), or from the first
+/// `X-Forwarded-For` entry when `TRUST_X_FORWARDED_FOR=true`. Only enable that behind a
+/// proxy that overwrites the header, otherwise clients can pick their own address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+fn trust_forwarded_for() -> bool {
+    static TRUST: OnceLock<bool> = OnceLock::new();
+    *TRUST.get_or_init(|| {
+        std::env::var("TRUST_X_FORWARDED_FOR")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(false)
+    })
+}
+
+impl ClientIp {
+    /// Resolve the client address from request headers and extensions (for middleware that
+    /// holds a whole `Request` rather than `Parts`).
+    pub fn resolve(headers: &HeaderMap, extensions: &Extensions) -> Self {
+        if trust_forwarded_for() {
+            let forwarded = headers
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.split(',').next())
+                .and_then(|ip| ip.trim().parse().ok());
+            if let Some(ip) = forwarded {
+                return ClientIp(ip);
+            }
+        }
+        let ip = extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        ClientIp(ip)
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(ClientIp::resolve(&parts.headers, &parts.extensions))
+    }
+}
+
+/// Client address and `User-Agent`, recorded with sessions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientInfo {
+    pub ip: IpAddr,
+    pub user_agent: Option<String>,
+}
+
+/// Longest `User-Agent` kept; anything beyond is cut off.
+const MAX_USER_AGENT_LEN: usize = 512;
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let ClientIp(ip) = ClientIp::resolve(&parts.headers, &parts.extensions);
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|ua| !ua.is_empty())
+            .map(|ua| ua.chars().take(MAX_USER_AGENT_LEN).collect());
+        Ok(ClientInfo { ip, user_agent })
+    }
+}
@@ -1,15 +1,124 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::apps::users::models::User;
+use crate::{
+    apps::users::models::{User, UserRole},
+    middleware::jwt_keys::JwtKeys,
+};
 use anyhow::Result;
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use serde::{Deserialize, Serialize};
+use jsonwebtoken::{decode, decode_header, encode, Header, Validation};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use uuid::Uuid;
 
+/// Default access token lifetime: 15 minutes.
+const DEFAULT_ACCESS_TOKEN_TTL_SECS: u64 = 60 * 15;
+
+/// Lifetime of an "MFA pending" token: the window to type the second factor.
+pub const MFA_PENDING_TOKEN_TTL_SECS: u64 = 60 * 5;
+
+/// Audience of "MFA pending" tokens. Access tokens carry no `aud`, and a token with an
+/// `aud` fails `verify_token` (no audience is expected there), so the two cannot be
+/// swapped for one another.
+const MFA_PENDING_AUDIENCE: &str = "noxel:mfa-pending";
+
+/// Version of the claims layout below. Tokens with any other `ver` are rejected.
+pub const CLAIMS_VERSION: u32 = 4;
+
+/// Access token claims.
+///
+/// Deliberately minimal: no PII. The user is re-loaded on every request by
+/// `require_auth`, so `role` is informational only (for clients) and never trusted
+/// for authorization.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
-    pub user: User,
+    /// Subject: the user id
+    pub sub: Uuid,
+    /// Role at issue time
+    pub role: UserRole,
+    /// Claims layout version (`CLAIMS_VERSION`)
+    pub ver: u32,
+    /// Unique token id, used for server-side revocation
+    pub jti: Uuid,
     pub exp: u64,
     pub iat: u64,
+    /// Issue time in milliseconds, compared with the `tokens_valid_after` cut-off (`iat`
+    /// alone cannot order a token and a cut-off within the same second)
+    pub iat_ms: u64,
+    /// Session (`sessions.id`) the token belongs to. For impersonation tokens, the
+    /// admin's session: signing it out ends the impersonation too.
+    pub sid: Uuid,
+    /// Actor (RFC 8693): set only on impersonation tokens, to the admin acting as `sub`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
+}
+
+/// The party actually holding an impersonation token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Actor {
+    /// The admin's user id
+    pub sub: Uuid,
+}
+
+/// Claims of the short-lived token returned by login when a second factor is still
+/// needed. It only lets the client call `POST /auth/mfa/verify`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaPendingClaims {
+    /// Subject: the user id
+    pub sub: Uuid,
+    /// Always `MFA_PENDING_AUDIENCE`
+    pub aud: String,
+    /// Unique token id, revoked once the second factor has been accepted
+    pub jti: Uuid,
+    pub exp: u64,
+    pub iat: u64,
+    /// Issue time in milliseconds (see `Claims::iat_ms`)
+    pub iat_ms: u64,
+    /// Device label given at login, carried over to the refresh token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_label: Option<String>,
+}
+
+/// Access token lifetime in seconds, from `JWT_ACCESS_TTL_SECS` (default 15 minutes).
+///
+/// Access tokens are meant to be short-lived; clients keep sessions alive through
+/// `POST /auth/refresh`.
+pub fn access_token_ttl_secs() -> u64 {
+    std::env::var("JWT_ACCESS_TTL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_ACCESS_TOKEN_TTL_SECS)
+}
+
+/// Milliseconds since the epoch.
+fn now_millis() -> Result<u64, jsonwebtoken::errors::Error> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .map_err(|_| jsonwebtoken::errors::ErrorKind::InvalidToken.into())
+}
+
+/// Sign `claims` with the active signing key, setting its `kid` in the header.
+fn sign<T: Serialize>(claims: &T, keys: &JwtKeys) -> Result<String, jsonwebtoken::errors::Error> {
+    let (key, alg, kid) = keys.signing_key();
+    let mut header = Header::new(alg);
+    header.kid = kid;
+    encode(&header, claims, key)
+}
+
+/// Verify the signature and expiry of `token`, selecting the key by its `kid` header.
+/// `configure` adjusts the validation (e.g. the expected audience).
+fn verify<T: DeserializeOwned>(
+    token: &str,
+    keys: &JwtKeys,
+    configure: impl FnOnce(&mut Validation),
+) -> Result<T, jsonwebtoken::errors::Error> {
+    let header = decode_header(token)?;
+    let (key, alg) = keys
+        .verification_key(header.kid.as_deref())
+        .ok_or(jsonwebtoken::errors::ErrorKind::InvalidKeyFormat)?;
+    // Only the algorithm bound to the key is accepted (no `alg` confusion).
+    let mut validation = Validation::new(alg);
+    configure(&mut validation);
+    Ok(decode::<T>(token, key, &validation)?.claims)
 }
 
 /// Generate a JWT token for a user
@@ -17,36 +126,53 @@ pub struct Claims {
 /// # Arguments
 ///
 /// * `user` - The user to generate a token for
-/// * `secret` - The secret to use for the token
+/// * `keys` - The key set; the active signing key is used and its `kid` set in the header
 /// * `expiry` - The expiry time for the token
+/// * `session_id` - The session the token belongs to, revoked with it
 ///
 /// # Returns
 /// Returns the token if it is generated successfully
 pub fn generate_token(
     user: &User,
-    secret: &str,
+    keys: &JwtKeys,
     expiry: u64,
+    session_id: Uuid,
 ) -> Result<String, jsonwebtoken::errors::Error> {
-    let header = Header::default();
-
-    // get the current time in seconds since the epoch
-    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|_| {
-        jsonwebtoken::errors::Error::from(jsonwebtoken::errors::ErrorKind::InvalidToken)
-    })?;
+    sign(&access_claims(user, expiry, session_id, None)?, keys)
+}
 
-    let current_timestamp = now.as_secs();
-    let expiration_time = current_timestamp + expiry;
+/// Generate an access token letting the admin `actor_id` act as `user` (no refresh
+/// token). The `act` claim marks it; `session_id` is the admin's own session.
+pub fn generate_impersonation_token(
+    user: &User,
+    keys: &JwtKeys,
+    expiry: u64,
+    session_id: Uuid,
+    actor_id: Uuid,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let actor = Actor { sub: actor_id };
+    sign(&access_claims(user, expiry, session_id, Some(actor))?, keys)
+}
 
-    let claims = Claims {
-        user: user.clone(),
-        exp: expiration_time,
+fn access_claims(
+    user: &User,
+    expiry: u64,
+    session_id: Uuid,
+    act: Option<Actor>,
+) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let now_ms = now_millis()?;
+    let current_timestamp = now_ms / 1000;
+    Ok(Claims {
+        sub: user.id,
+        role: user.role.clone(),
+        ver: CLAIMS_VERSION,
+        jti: Uuid::new_v4(),
+        exp: current_timestamp + expiry,
         iat: current_timestamp,
-    };
-    encode(
-        &header,
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )
+        iat_ms: now_ms,
+        sid: session_id,
+        act,
+    })
 }
 
 /// Verify a JWT token
@@ -54,15 +180,46 @@ pub fn generate_token(
 /// # Arguments
 ///
 /// * `token` - The token to verify
-/// * `secret` - The secret to use for the token
+/// * `keys` - The key set; the key is selected by the token's `kid` header
 ///
 /// # Returns
 /// Returns the claims if the token is valid
-pub fn verify_token(token: &str, secret: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
-    )?;
-    Ok(token_data.claims)
+pub fn verify_token(token: &str, keys: &JwtKeys) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let claims: Claims = verify(token, keys, |_| {})?;
+    if claims.ver != CLAIMS_VERSION {
+        return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+    }
+    Ok(claims)
+}
+
+/// Generate an "MFA pending" token for a user who passed the password check but still
+/// has to provide a second factor.
+pub fn generate_mfa_pending_token(
+    user: &User,
+    keys: &JwtKeys,
+    device_label: Option<&str>,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let now_ms = now_millis()?;
+    let current_timestamp = now_ms / 1000;
+    let claims = MfaPendingClaims {
+        sub: user.id,
+        aud: MFA_PENDING_AUDIENCE.to_string(),
+        jti: Uuid::new_v4(),
+        exp: current_timestamp + MFA_PENDING_TOKEN_TTL_SECS,
+        iat: current_timestamp,
+        iat_ms: now_ms,
+        device_label: device_label.map(str::to_string),
+    };
+    sign(&claims, keys)
+}
+
+/// Verify an "MFA pending" token. Access tokens are rejected (no audience).
+pub fn verify_mfa_pending_token(
+    token: &str,
+    keys: &JwtKeys,
+) -> Result<MfaPendingClaims, jsonwebtoken::errors::Error> {
+    verify(token, keys, |validation| {
+        validation.set_audience(&[MFA_PENDING_AUDIENCE]);
+        validation.set_required_spec_claims(&["exp", "aud"]);
+    })
 }
@@ -0,0 +1,231 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use jsonwebtoken::{
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey,
+};
+use rsa::{pkcs1::DecodeRsaPrivateKey, pkcs1::DecodeRsaPublicKey, traits::PublicKeyParts};
+
+use crate::results::ApiError;
+
+/// A single configured JWT key.
+struct JwtKey {
+    alg: Algorithm,
+    /// `None` for verify-only keys (public PEM)
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+    /// Public JWK published on `/.well-known/jwks.json` (never set for HS256)
+    jwk: Option<Jwk>,
+}
+
+struct Inner {
+    /// Asymmetric keys by `kid`
+    keys: HashMap<String, JwtKey>,
+    /// `kid` of the key new tokens are signed with; `None` means "sign with `legacy`"
+    signing_kid: Option<String>,
+    /// HS256 key from `JWT_SECRET`, for tokens without a `kid` header
+    legacy: Option<JwtKey>,
+}
+
+/// Signing/verification keys, loaded once at startup.
+///
+/// Configuration:
+/// - `JWT_KEYS`: comma-separated `kid=path` list of PEM files. A private key (RSA or
+///   Ed25519, PKCS#8 or PKCS#1) can sign and verify; a public key only verifies.
+///   Every key is published in the JWKS, so a retired key can stay listed (ideally as a
+///   public PEM) until the last token signed with it has expired.
+/// - `JWT_SIGNING_KEY_ID`: `kid` used for new tokens. Optional when exactly one private
+///   key is configured.
+/// - `JWT_SECRET`: legacy HS256 secret. Used for signing only when `JWT_KEYS` is unset;
+///   otherwise only accepted for verifying tokens without a `kid` while they drain.
+#[derive(Clone)]
+pub struct JwtKeys {
+    inner: Arc<Inner>,
+}
+
+impl JwtKeys {
+    pub fn from_env() -> Result<Self> {
+        let legacy = std::env::var("JWT_SECRET")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|secret| JwtKey {
+                alg: Algorithm::HS256,
+                encoding: Some(EncodingKey::from_secret(secret.as_bytes())),
+                decoding: DecodingKey::from_secret(secret.as_bytes()),
+                jwk: None,
+            });
+
+        let mut keys = HashMap::new();
+        if let Ok(spec) = std::env::var("JWT_KEYS") {
+            for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                let (kid, path) = entry
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("JWT_KEYS entry `{entry}` is not `kid=path`"))?;
+                let key = load_pem_key(kid.trim(), path.trim())?;
+                tracing::info!(kid, alg = ?key.alg, can_sign = key.encoding.is_some(), "loaded JWT key");
+                keys.insert(kid.trim().to_string(), key);
+            }
+        }
+
+        if keys.is_empty() {
+            if legacy.is_none() {
+                return Err(ApiError::MissingJwtSecret.into());
+            }
+            tracing::warn!("JWT_KEYS not set, signing tokens with HS256 JWT_SECRET");
+            return Ok(Self {
+                inner: Arc::new(Inner {
+                    keys,
+                    signing_kid: None,
+                    legacy,
+                }),
+            });
+        }
+
+        let signing_kid = match std::env::var("JWT_SIGNING_KEY_ID") {
+            Ok(kid) if !kid.is_empty() => kid,
+            _ => {
+                let mut private = keys.iter().filter(|(_, k)| k.encoding.is_some());
+                match (private.next(), private.next()) {
+                    (Some((kid, _)), None) => kid.clone(),
+                    _ => bail!("JWT_SIGNING_KEY_ID must be set when JWT_KEYS has zero or several private keys"),
+                }
+            }
+        };
+        match keys.get(&signing_kid) {
+            Some(key) if key.encoding.is_some() => {}
+            Some(_) => bail!("JWT signing key `{signing_kid}` is a public key"),
+            None => bail!("JWT signing key `{signing_kid}` is not listed in JWT_KEYS"),
+        }
+        tracing::info!(kid = %signing_kid, "JWT signing key");
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                keys,
+                signing_kid: Some(signing_kid),
+                legacy,
+            }),
+        })
+    }
+
+    /// Key, algorithm and `kid` header to sign new tokens with.
+    pub(crate) fn signing_key(&self) -> (&EncodingKey, Algorithm, Option<String>) {
+        let (key, kid) = match &self.inner.signing_kid {
+            Some(kid) => (&self.inner.keys[kid], Some(kid.clone())),
+            None => (
+                self.inner
+                    .legacy
+                    .as_ref()
+                    .expect("JwtKeys always has a signing key"),
+                None,
+            ),
+        };
+        let encoding = key
+            .encoding
+            .as_ref()
+            .expect("signing key is checked to be private at startup");
+        (encoding, key.alg, kid)
+    }
+
+    /// Key and algorithm to verify a token carrying the given `kid` header.
+    pub(crate) fn verification_key(&self, kid: Option<&str>) -> Option<(&DecodingKey, Algorithm)> {
+        let key = match kid {
+            Some(kid) => self.inner.keys.get(kid)?,
+            None => self.inner.legacy.as_ref()?,
+        };
+        Some((&key.decoding, key.alg))
+    }
+
+    /// Public keys for `/.well-known/jwks.json`.
+    pub fn jwks(&self) -> JwkSet {
+        let mut keys: Vec<Jwk> = self
+            .inner
+            .keys
+            .values()
+            .filter_map(|k| k.jwk.clone())
+            .collect();
+        keys.sort_by(|a, b| a.common.key_id.cmp(&b.common.key_id));
+        JwkSet { keys }
+    }
+}
+
+fn load_pem_key(kid: &str, path: &str) -> Result<JwtKey> {
+    let pem = std::fs::read_to_string(path)
+        .with_context(|| format!("reading JWT key `{kid}` from {path}"))?;
+
+    if let Ok(signing) = ed25519_dalek::SigningKey::from_pkcs8_pem(&pem) {
+        let encoding = EncodingKey::from_ed_pem(pem.as_bytes())
+            .with_context(|| format!("loading Ed25519 key `{kid}`"))?;
+        return ed25519_key(kid, Some(encoding), &signing.verifying_key());
+    }
+    if let Ok(public) = ed25519_dalek::VerifyingKey::from_public_key_pem(&pem) {
+        return ed25519_key(kid, None, &public);
+    }
+
+    let rsa_private = rsa::RsaPrivateKey::from_pkcs8_pem(&pem)
+        .or_else(|_| rsa::RsaPrivateKey::from_pkcs1_pem(&pem));
+    if let Ok(private) = rsa_private {
+        let encoding = EncodingKey::from_rsa_pem(pem.as_bytes())
+            .with_context(|| format!("loading RSA key `{kid}`"))?;
+        return rsa_key(kid, Some(encoding), &private.to_public_key());
+    }
+    let rsa_public = rsa::RsaPublicKey::from_public_key_pem(&pem)
+        .or_else(|_| rsa::RsaPublicKey::from_pkcs1_pem(&pem));
+    if let Ok(public) = rsa_public {
+        return rsa_key(kid, None, &public);
+    }
+
+    bail!("JWT key `{kid}` ({path}) is not an RSA or Ed25519 PEM key")
+}
+
+fn common_parameters(kid: &str, alg: KeyAlgorithm) -> CommonParameters {
+    CommonParameters {
+        public_key_use: Some(PublicKeyUse::Signature),
+        key_algorithm: Some(alg),
+        key_id: Some(kid.to_string()),
+        ..Default::default()
+    }
+}
+
+fn ed25519_key(
+    kid: &str,
+    encoding: Option<EncodingKey>,
+    public: &ed25519_dalek::VerifyingKey,
+) -> Result<JwtKey> {
+    let jwk = Jwk {
+        common: common_parameters(kid, KeyAlgorithm::EdDSA),
+        algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            key_type: OctetKeyPairType::OctetKeyPair,
+            curve: EllipticCurve::Ed25519,
+            x: URL_SAFE_NO_PAD.encode(public.as_bytes()),
+        }),
+    };
+    Ok(JwtKey {
+        alg: Algorithm::EdDSA,
+        encoding,
+        decoding: DecodingKey::from_jwk(&jwk)?,
+        jwk: Some(jwk),
+    })
+}
+
+fn rsa_key(kid: &str, encoding: Option<EncodingKey>, public: &rsa::RsaPublicKey) -> Result<JwtKey> {
+    let jwk = Jwk {
+        common: common_parameters(kid, KeyAlgorithm::RS256),
+        algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: URL_SAFE_NO_PAD.encode(public.n().to_bytes_be()),
+            e: URL_SAFE_NO_PAD.encode(public.e().to_bytes_be()),
+        }),
+    };
+    Ok(JwtKey {
+        alg: Algorithm::RS256,
+        encoding,
+        decoding: DecodingKey::from_jwk(&jwk)?,
+        jwk: Some(jwk),
+    })
+}
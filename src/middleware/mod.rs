@@ -1,2 +1,6 @@
 pub mod auth;
+pub mod authz;
+pub mod client_ip;
 pub mod jwt;
+pub mod jwt_keys;
+pub mod rate_limit;
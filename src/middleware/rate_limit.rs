@@ -0,0 +1,429 @@
+//! Token-bucket rate limiting, configured per route group.
+//!
+//! Each group (e.g. `users_public`) gets its own buckets, keyed by client IP or by the
+//! authenticated user. Responses carry `RateLimit-Limit`, `RateLimit-Remaining` and
+//! `RateLimit-Reset`; refused requests get `429 rate_limited` with `Retry-After`.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    async_trait,
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sqlx::PgPool;
+
+use crate::{
+    middleware::{auth::AuthContext, client_ip::ClientIp},
+    results::ApiError,
+    AppState,
+};
+
+pub const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+pub const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+pub const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+/// Longest quota period. Buckets idle this long are full again, so the periodic cleanup
+/// drops them (`RateLimitBackend::purge_idle`).
+pub const MAX_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// Bucket size and refill speed: up to `burst` requests at once, refilled at `burst`
+/// tokens per `period`.
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    pub burst: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    pub const fn per_minute(burst: u32) -> Self {
+        Self {
+            burst,
+            period: Duration::from_secs(60),
+        }
+    }
+
+    fn refill_per_sec(&self) -> f64 {
+        self.burst as f64 / self.period.as_secs_f64()
+    }
+
+    /// Parse `burst/period_secs` (e.g. `10/60`); the period is at most `MAX_PERIOD`.
+    fn parse(spec: &str) -> Option<Self> {
+        let (burst, period) = spec.split_once('/')?;
+        let burst: u32 = burst.trim().parse().ok()?;
+        let period = Duration::from_secs(period.trim().parse().ok()?);
+        (burst > 0 && !period.is_zero() && period <= MAX_PERIOD).then_some(Self { burst, period })
+    }
+
+    /// Build the decision for a bucket left with `tokens` after the request.
+    fn decision(&self, tokens: f64, allowed: bool) -> Decision {
+        let rate = self.refill_per_sec();
+        let tokens = tokens.clamp(0.0, self.burst as f64);
+        Decision {
+            allowed,
+            limit: self.burst,
+            remaining: tokens.floor() as u32,
+            reset_secs: ((self.burst as f64 - tokens) / rate).ceil() as u64,
+            retry_after_secs: if allowed {
+                0
+            } else {
+                ((1.0 - tokens) / rate).ceil().max(1.0) as u64
+            },
+        }
+    }
+}
+
+/// Outcome of taking a token from a bucket.
+#[derive(Debug, Clone, Copy)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the bucket is full again
+    pub reset_secs: u64,
+    /// Seconds until the next token (refused requests only)
+    pub retry_after_secs: u64,
+}
+
+/// Storage for token buckets.
+#[async_trait]
+pub trait RateLimitBackend: Send + Sync {
+    /// Take one token from the bucket `key`, if there is one.
+    async fn acquire(&self, key: &str, quota: Quota) -> anyhow::Result<Decision>;
+
+    /// Drop buckets untouched for `max_idle` (they are full again by then).
+    async fn purge_idle(&self, max_idle: Duration) -> anyhow::Result<u64>;
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// In-process buckets. Limits are per instance.
+#[derive(Default)]
+pub struct MemoryBackend {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+#[async_trait]
+impl RateLimitBackend for MemoryBackend {
+    async fn acquire(&self, key: &str, quota: Quota) -> anyhow::Result<Decision> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: quota.burst as f64,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * quota.refill_per_sec()).min(quota.burst as f64);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        Ok(quota.decision(bucket.tokens, allowed))
+    }
+
+    async fn purge_idle(&self, max_idle: Duration) -> anyhow::Result<u64> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let before = buckets.len();
+        buckets.retain(|_, b| b.updated.elapsed() < max_idle);
+        Ok((before - buckets.len()) as u64)
+    }
+}
+
+/// Buckets in the `rate_limit_buckets` table, shared by every instance.
+pub struct PostgresBackend {
+    db: PgPool,
+}
+
+#[async_trait]
+impl RateLimitBackend for PostgresBackend {
+    async fn acquire(&self, key: &str, quota: Quota) -> anyhow::Result<Decision> {
+        let burst = quota.burst as f64;
+        let rate = quota.refill_per_sec();
+        // Refill and take a token in one statement; the conditional update leaves an
+        // empty bucket untouched (no row returned).
+        let tokens: Option<f64> = sqlx::query_scalar(
+            r#"INSERT INTO rate_limit_buckets AS b (key, tokens, updated_at)
+               VALUES ($1, $2 - 1, now())
+               ON CONFLICT (key) DO UPDATE
+               SET tokens = LEAST($2, b.tokens + EXTRACT(EPOCH FROM now() - b.updated_at)::float8 * $3) - 1,
+                   updated_at = now()
+               WHERE LEAST($2, b.tokens + EXTRACT(EPOCH FROM now() - b.updated_at)::float8 * $3) >= 1
+               RETURNING tokens"#,
+        )
+        .bind(key)
+        .bind(burst)
+        .bind(rate)
+        .fetch_optional(&self.db)
+        .await?;
+        if let Some(tokens) = tokens {
+            return Ok(quota.decision(tokens, true));
+        }
+
+        let tokens: Option<f64> = sqlx::query_scalar(
+            r#"SELECT LEAST($2, tokens + EXTRACT(EPOCH FROM now() - updated_at)::float8 * $3)
+               FROM rate_limit_buckets
+               WHERE key = $1"#,
+        )
+        .bind(key)
+        .bind(burst)
+        .bind(rate)
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(quota.decision(tokens.unwrap_or(0.0), false))
+    }
+
+    async fn purge_idle(&self, max_idle: Duration) -> anyhow::Result<u64> {
+        let result = sqlx::query(r#"DELETE FROM rate_limit_buckets WHERE updated_at < now() - $1"#)
+            .bind(chrono::Duration::from_std(max_idle)?)
+            .execute(&self.db)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// Build the backend selected by `RATE_LIMIT_BACKEND`: `memory` (default) or `postgres`
+/// (needed to share limits between several instances).
+pub fn backend_from_env(db: &PgPool) -> anyhow::Result<Arc<dyn RateLimitBackend>> {
+    match std::env::var("RATE_LIMIT_BACKEND").as_deref() {
+        Err(_) | Ok("") | Ok("memory") => Ok(Arc::new(MemoryBackend::default())),
+        Ok("postgres") => Ok(Arc::new(PostgresBackend { db: db.clone() })),
+        Ok(other) => anyhow::bail!("unknown RATE_LIMIT_BACKEND `{other}`"),
+    }
+}
+
+/// What a route group's buckets are keyed by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyBy {
+    /// Client IP (`ClientIp`)
+    Ip,
+    /// Authenticated user, falling back to the client IP. Only sees the user when the
+    /// layer runs inside `require_auth` (add it *before* the `require_auth` route layer).
+    UserOrIp,
+}
+
+/// Rate limit of one route group; the state of the `rate_limit` middleware.
+#[derive(Clone)]
+pub struct RateLimit {
+    group: &'static str,
+    /// `None` when disabled
+    quota: Option<Quota>,
+    key_by: KeyBy,
+    backend: Arc<dyn RateLimitBackend>,
+}
+
+impl RateLimit {
+    /// Limit for `group`, overridable with `RATE_LIMIT_<GROUP>` set to `burst/period_secs`
+    /// (e.g. `RATE_LIMIT_USERS_PUBLIC=10/60`) or `off`.
+    pub fn for_group(state: &AppState, group: &'static str, default: Quota, key_by: KeyBy) -> Self {
+        let var = format!("RATE_LIMIT_{}", group.to_uppercase());
+        let quota = match std::env::var(&var) {
+            Ok(spec) if spec.trim() == "off" => None,
+            Ok(spec) => Some(Quota::parse(&spec).unwrap_or_else(|| {
+                tracing::warn!(%var, %spec, "invalid rate limit, using the default");
+                default
+            })),
+            Err(_) => Some(default),
+        };
+        Self {
+            group,
+            quota,
+            key_by,
+            backend: state.rate_limiter.clone(),
+        }
+    }
+}
+
+/// Rate limiting middleware; use with `from_fn_with_state(RateLimit::for_group(..), rate_limit)`.
+///
+/// Backend errors are logged and the request is let through (fail open).
+pub async fn rate_limit(State(limit): State<RateLimit>, req: Request, next: Next) -> Response {
+    let Some(quota) = limit.quota else {
+        return next.run(req).await;
+    };
+
+    let user = match limit.key_by {
+        KeyBy::UserOrIp => req.extensions().get::<AuthContext>().map(|c| c.user.id),
+        KeyBy::Ip => None,
+    };
+    let key = match user {
+        Some(user_id) => format!("{}:user:{}", limit.group, user_id),
+        None => {
+            let ClientIp(ip) = ClientIp::resolve(req.headers(), req.extensions());
+            format!("{}:ip:{}", limit.group, ip)
+        }
+    };
+
+    let decision = match limit.backend.acquire(&key, quota).await {
+        Ok(decision) => decision,
+        Err(e) => {
+            tracing::warn!(target: "api.rate_limit", group = limit.group, cause = %e, "rate limit backend failed, request let through");
+            return next.run(req).await;
+        }
+    };
+
+    let mut response = if decision.allowed {
+        next.run(req).await
+    } else {
+        tracing::info!(target: "api.rate_limit", group = limit.group, %key, "rate limited");
+        ApiError::RateLimited {
+            retry_after_secs: decision.retry_after_secs,
+        }
+        .into_response()
+    };
+    let headers = response.headers_mut();
+    headers.insert(RATELIMIT_LIMIT, HeaderValue::from(decision.limit));
+    headers.insert(RATELIMIT_REMAINING, HeaderValue::from(decision.remaining));
+    headers.insert(RATELIMIT_RESET, HeaderValue::from(decision.reset_secs));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2 requests per minute: one token every 30 seconds.
+    const QUOTA: Quota = Quota {
+        burst: 2,
+        period: Duration::from_secs(60),
+    };
+
+    /// Set bucket `key` to `tokens`, last updated `ago`.
+    fn set_bucket(backend: &MemoryBackend, key: &str, tokens: f64, ago: Duration) {
+        let updated = Instant::now().checked_sub(ago).unwrap();
+        backend
+            .buckets
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), Bucket { tokens, updated });
+    }
+
+    #[test]
+    fn parses_quotas() {
+        let quota = Quota::parse(" 10 / 60 ").unwrap();
+        assert_eq!(quota.burst, 10);
+        assert_eq!(quota.period, Duration::from_secs(60));
+        assert_eq!(Quota::parse("5/3600").unwrap().period, MAX_PERIOD);
+    }
+
+    #[test]
+    fn rejects_invalid_quotas() {
+        for spec in [
+            "",
+            "10",
+            "0/60",
+            "10/0",
+            "10/3601",
+            "100/86400",
+            "-1/60",
+            "a/b",
+        ] {
+            assert!(Quota::parse(spec).is_none(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn allowed_decision_headers() {
+        let quota = Quota::per_minute(10);
+        let decision = quota.decision(9.0, true);
+        assert!(decision.allowed);
+        assert_eq!(decision.limit, 10);
+        assert_eq!(decision.remaining, 9);
+        // One token refills every 6 seconds.
+        assert_eq!(decision.reset_secs, 6);
+        assert_eq!(decision.retry_after_secs, 0);
+
+        let decision = quota.decision(10.0, true);
+        assert_eq!((decision.remaining, decision.reset_secs), (10, 0));
+    }
+
+    #[test]
+    fn refused_decision_headers() {
+        let quota = Quota::per_minute(10);
+        let decision = quota.decision(0.0, false);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(decision.reset_secs, 60);
+        assert_eq!(decision.retry_after_secs, 6);
+
+        // Half a token left: the next one is 3 seconds away.
+        let decision = quota.decision(0.5, false);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(decision.reset_secs, 57);
+        assert_eq!(decision.retry_after_secs, 3);
+    }
+
+    #[test]
+    fn retry_after_is_at_least_one_second() {
+        let quota = Quota::per_minute(600);
+        assert_eq!(quota.decision(0.99, false).retry_after_secs, 1);
+    }
+
+    #[tokio::test]
+    async fn refuses_when_the_bucket_is_empty() {
+        let backend = MemoryBackend::default();
+        for remaining in [1, 0] {
+            let decision = backend.acquire("k", QUOTA).await.unwrap();
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, remaining);
+        }
+        let decision = backend.acquire("k", QUOTA).await.unwrap();
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(decision.reset_secs, 60);
+        assert_eq!(decision.retry_after_secs, 30);
+
+        // Buckets are independent.
+        assert!(backend.acquire("other", QUOTA).await.unwrap().allowed);
+    }
+
+    #[tokio::test]
+    async fn refills_with_elapsed_time() {
+        let backend = MemoryBackend::default();
+        set_bucket(&backend, "k", 0.0, Duration::from_secs(15));
+        // Half a token: still refused, the next one is about 15 seconds away.
+        let decision = backend.acquire("k", QUOTA).await.unwrap();
+        assert!(!decision.allowed);
+        assert!((14..=15).contains(&decision.retry_after_secs));
+
+        set_bucket(&backend, "k", 0.0, Duration::from_secs(45));
+        // 1.5 tokens: one is taken, half a token is left.
+        let decision = backend.acquire("k", QUOTA).await.unwrap();
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert!((44..=45).contains(&decision.reset_secs));
+    }
+
+    #[tokio::test]
+    async fn refill_is_capped_at_the_burst() {
+        let backend = MemoryBackend::default();
+        set_bucket(&backend, "k", 1.0, Duration::from_secs(90));
+        let decision = backend.acquire("k", QUOTA).await.unwrap();
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 1);
+        assert_eq!(decision.reset_secs, 30);
+    }
+
+    #[tokio::test]
+    async fn purges_idle_buckets() {
+        let backend = MemoryBackend::default();
+        set_bucket(&backend, "idle", 0.0, Duration::from_secs(90));
+        backend.acquire("active", QUOTA).await.unwrap();
+        assert_eq!(
+            backend.purge_idle(Duration::from_secs(60)).await.unwrap(),
+            1
+        );
+        let buckets = backend.buckets.lock().unwrap();
+        assert!(buckets.contains_key("active") && !buckets.contains_key("idle"));
+    }
+}
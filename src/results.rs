@@ -1,17 +1,22 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use thiserror::Error;
 use utoipa::ToSchema;
 
+use crate::validation::FieldError;
+
 /// Consistent error payload for the API.
 #[derive(Debug, Clone, serde::Serialize, ToSchema)]
 pub struct ApiErrorBody {
     pub error: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<String>,
+    /// Failed validation rules (`validation_failed` only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<FieldError>>,
 }
 
 #[derive(Debug, Error)]
@@ -22,35 +27,175 @@ pub enum ApiError {
     #[error("unauthorized")]
     Unauthorized,
 
+    #[error("invalid email or password")]
+    InvalidCredentials,
+
+    #[error("current password is incorrect")]
+    InvalidPassword,
+
+    #[error("email address already in use")]
+    EmailTaken,
+
+    #[error("qr code already in use")]
+    QrCodeTaken,
+
+    #[error("this version of the document already exists")]
+    DocumentVersionTaken,
+
+    #[error("ticket lot sold out")]
+    LotSoldOut,
+
+    #[error("ticket lot is not on sale")]
+    LotNotSellable,
+
+    #[error("missing bearer token")]
+    TokenMissing,
+
+    #[error("token expired")]
+    TokenExpired,
+
+    #[error("invalid token")]
+    TokenInvalid,
+
+    #[error("token revoked")]
+    TokenRevoked,
+
+    #[error("invalid, expired or revoked api key")]
+    InvalidApiKey,
+
+    #[error("api keys cannot be used for this endpoint; log in instead")]
+    SessionRequired,
+
+    #[error("not allowed while impersonating a user")]
+    ImpersonationNotAllowed,
+
+    #[error("api key lacks the required scope: {0}")]
+    InsufficientScope(&'static str),
+
+    #[error("invalid refresh token")]
+    InvalidRefreshToken,
+
+    #[error("refresh token reused; session revoked")]
+    RefreshTokenReused,
+
+    #[error("invalid or expired password reset token")]
+    InvalidResetToken,
+
+    #[error("invalid or expired email verification token")]
+    InvalidVerificationToken,
+
+    #[error("invalid or expired email change token")]
+    InvalidEmailChangeToken,
+
+    #[error("email already verified")]
+    EmailAlreadyVerified,
+
+    #[error("invalid or expired mfa token")]
+    InvalidMfaToken,
+
+    #[error("invalid two-factor code")]
+    InvalidMfaCode,
+
+    #[error("two-factor authentication is required for this role; enroll a second factor first")]
+    MfaRequired,
+
+    #[error("two-factor authentication already enabled")]
+    MfaAlreadyEnabled,
+
+    #[error("two-factor authentication not enabled")]
+    MfaNotEnabled,
+
+    #[error("too many failed attempts; retry in {retry_after_secs} seconds")]
+    TooManyAttempts { retry_after_secs: u64 },
+
+    #[error("rate limit exceeded; retry in {retry_after_secs} seconds")]
+    RateLimited { retry_after_secs: u64 },
+
+    #[error("invalid or expired account unlock token")]
+    InvalidUnlockToken,
+
+    #[error("invalid or expired download link")]
+    InvalidDownloadLink,
+
+    #[error("address limit reached; delete an address first")]
+    TooManyAddresses,
+
     #[error("forbidden")]
     Forbidden,
 
+    #[error("account disabled")]
+    AccountDisabled,
+
     #[error("not found")]
     NotFound,
 
     #[error("bad request: {0}")]
     BadRequest(String),
 
+    #[error("request validation failed")]
+    Validation(Vec<FieldError>),
+
     #[error("missing env var DATABASE_URL")]
     MissingDatabaseUrl,
 
     #[error("missing env var JWT_SECRET")]
     MissingJwtSecret,
 
+    /// Built by `From<sqlx::Error>`, for errors `db_errors::translate` does not map.
     #[error("database error")]
-    Db(#[from] sqlx::Error),
+    Db(sqlx::Error),
 
     #[error("internal server error")]
     Internal,
 }
 
+/// Constraint violations become client errors (see `db_errors`).
+impl From<sqlx::Error> for ApiError {
+    fn from(err: sqlx::Error) -> Self {
+        crate::db_errors::translate(err)
+    }
+}
+
 impl ApiError {
     pub fn status(&self) -> StatusCode {
         match self {
             ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::InvalidCredentials => StatusCode::UNAUTHORIZED,
+            ApiError::InvalidPassword => StatusCode::FORBIDDEN,
+            ApiError::EmailTaken => StatusCode::CONFLICT,
+            ApiError::QrCodeTaken => StatusCode::CONFLICT,
+            ApiError::DocumentVersionTaken => StatusCode::CONFLICT,
+            ApiError::LotSoldOut => StatusCode::CONFLICT,
+            ApiError::LotNotSellable => StatusCode::CONFLICT,
+            ApiError::TokenMissing => StatusCode::UNAUTHORIZED,
+            ApiError::TokenExpired => StatusCode::UNAUTHORIZED,
+            ApiError::TokenInvalid => StatusCode::UNAUTHORIZED,
+            ApiError::TokenRevoked => StatusCode::UNAUTHORIZED,
+            ApiError::InvalidApiKey => StatusCode::UNAUTHORIZED,
+            ApiError::SessionRequired => StatusCode::FORBIDDEN,
+            ApiError::ImpersonationNotAllowed => StatusCode::FORBIDDEN,
+            ApiError::InsufficientScope(_) => StatusCode::FORBIDDEN,
+            ApiError::InvalidRefreshToken => StatusCode::UNAUTHORIZED,
+            ApiError::RefreshTokenReused => StatusCode::UNAUTHORIZED,
+            ApiError::InvalidResetToken => StatusCode::BAD_REQUEST,
+            ApiError::InvalidVerificationToken => StatusCode::BAD_REQUEST,
+            ApiError::InvalidEmailChangeToken => StatusCode::BAD_REQUEST,
+            ApiError::EmailAlreadyVerified => StatusCode::CONFLICT,
+            ApiError::InvalidMfaToken => StatusCode::UNAUTHORIZED,
+            ApiError::InvalidMfaCode => StatusCode::UNAUTHORIZED,
+            ApiError::MfaRequired => StatusCode::FORBIDDEN,
+            ApiError::MfaAlreadyEnabled => StatusCode::CONFLICT,
+            ApiError::MfaNotEnabled => StatusCode::CONFLICT,
+            ApiError::TooManyAttempts { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::InvalidUnlockToken => StatusCode::BAD_REQUEST,
+            ApiError::InvalidDownloadLink => StatusCode::FORBIDDEN,
+            ApiError::TooManyAddresses => StatusCode::CONFLICT,
             ApiError::Forbidden => StatusCode::FORBIDDEN,
+            ApiError::AccountDisabled => StatusCode::FORBIDDEN,
             ApiError::NotFound => StatusCode::NOT_FOUND,
             ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
             ApiError::MissingDatabaseUrl => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::MissingJwtSecret => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
@@ -62,9 +207,42 @@ impl ApiError {
     pub fn code(&self) -> Option<&'static str> {
         match self {
             ApiError::Unauthorized => Some("unauthorized"),
+            ApiError::InvalidCredentials => Some("invalid_credentials"),
+            ApiError::InvalidPassword => Some("invalid_password"),
+            ApiError::EmailTaken => Some("email_taken"),
+            ApiError::QrCodeTaken => Some("qr_code_taken"),
+            ApiError::DocumentVersionTaken => Some("document_version_taken"),
+            ApiError::LotSoldOut => Some("lot_sold_out"),
+            ApiError::LotNotSellable => Some("lot_not_sellable"),
+            ApiError::TokenMissing => Some("token_missing"),
+            ApiError::TokenExpired => Some("token_expired"),
+            ApiError::TokenInvalid => Some("token_invalid"),
+            ApiError::TokenRevoked => Some("token_revoked"),
+            ApiError::InvalidApiKey => Some("invalid_api_key"),
+            ApiError::SessionRequired => Some("session_required"),
+            ApiError::ImpersonationNotAllowed => Some("impersonation_not_allowed"),
+            ApiError::InsufficientScope(_) => Some("insufficient_scope"),
+            ApiError::InvalidRefreshToken => Some("invalid_refresh_token"),
+            ApiError::RefreshTokenReused => Some("refresh_token_reused"),
+            ApiError::InvalidResetToken => Some("invalid_reset_token"),
+            ApiError::InvalidVerificationToken => Some("invalid_verification_token"),
+            ApiError::InvalidEmailChangeToken => Some("invalid_email_change_token"),
+            ApiError::EmailAlreadyVerified => Some("email_already_verified"),
+            ApiError::InvalidMfaToken => Some("invalid_mfa_token"),
+            ApiError::InvalidMfaCode => Some("invalid_mfa_code"),
+            ApiError::MfaRequired => Some("mfa_required"),
+            ApiError::MfaAlreadyEnabled => Some("mfa_already_enabled"),
+            ApiError::MfaNotEnabled => Some("mfa_not_enabled"),
+            ApiError::TooManyAttempts { .. } => Some("too_many_attempts"),
+            ApiError::RateLimited { .. } => Some("rate_limited"),
+            ApiError::InvalidUnlockToken => Some("invalid_unlock_token"),
+            ApiError::InvalidDownloadLink => Some("invalid_download_link"),
+            ApiError::TooManyAddresses => Some("too_many_addresses"),
             ApiError::Forbidden => Some("forbidden"),
+            ApiError::AccountDisabled => Some("account_disabled"),
             ApiError::NotFound => Some("not_found"),
             ApiError::BadRequest(_) => Some("bad_request"),
+            ApiError::Validation(_) => Some("validation_failed"),
             ApiError::MissingDatabaseUrl => Some("missing_database_url"),
             ApiError::MissingJwtSecret => Some("missing_jwt_secret"),
             ApiError::Db(_) => Some("db_error"),
@@ -72,6 +250,31 @@ impl ApiError {
             ApiError::InvalidRole(_) => Some("invalid_role"),
         }
     }
+
+    /// `WWW-Authenticate` challenge for bearer token failures (RFC 6750 §3).
+    fn www_authenticate(&self) -> Option<String> {
+        let description = match self {
+            // No credentials at all: a bare challenge, without an error code.
+            ApiError::TokenMissing => return Some(r#"Bearer realm="noxel""#.to_string()),
+            ApiError::InvalidApiKey => return Some(r#"ApiKey realm="noxel""#.to_string()),
+            ApiError::TokenExpired | ApiError::TokenInvalid | ApiError::TokenRevoked => {
+                self.to_string()
+            }
+            _ => return None,
+        };
+        Some(format!(
+            r#"Bearer realm="noxel", error="invalid_token", error_description="{description}""#
+        ))
+    }
+
+    /// `Retry-After` value (seconds) for throttled requests.
+    fn retry_after(&self) -> Option<u64> {
+        match self {
+            ApiError::TooManyAttempts { retry_after_secs }
+            | ApiError::RateLimited { retry_after_secs } => Some(*retry_after_secs),
+            _ => None,
+        }
+    }
 }
 
 /// Build full cause chain so logs show the actual root cause (e.g. Postgres message).
@@ -111,11 +314,31 @@ impl IntoResponse for ApiError {
         } else {
             tracing::debug!(api_error = %self, status = %status, "API error 4xx");
         }
+        let challenge = self.www_authenticate();
+        let retry_after = self.retry_after();
+        let error = self.to_string();
+        let code = self.code().map(|s| s.to_string());
+        let fields = match self {
+            ApiError::Validation(fields) => Some(fields),
+            _ => None,
+        };
         let body = ApiErrorBody {
-            error: self.to_string(),
-            code: self.code().map(|s| s.to_string()),
+            error,
+            code,
+            fields,
         };
-        (status, Json(body)).into_response()
+        let mut response = (status, Json(body)).into_response();
+        if let Some(value) = challenge.and_then(|c| HeaderValue::from_str(&c).ok()) {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, value);
+        }
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
@@ -1,7 +1,10 @@
 use crate::{results::ApiResult, state::AppState};
 use axum::{http::StatusCode, routing::get, Json, Router};
 use serde::Serialize;
-use utoipa::{OpenApi, ToSchema};
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi, ToSchema,
+};
 use utoipa_swagger_ui::SwaggerUi;
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -20,21 +23,176 @@ async fn health() -> ApiResult<StatusCode, HealthResponse> {
     Ok((StatusCode::OK, Json(HealthResponse { ok: true })))
 }
 
+/// Name of the bearer JWT security scheme referenced by protected paths.
+const BEARER_AUTH: &str = "bearer_auth";
+
+/// Name of the API key security scheme, for paths that also accept organizer API keys.
+const API_KEY_AUTH: &str = "api_key";
+
+/// Registers the bearer JWT and API key security schemes and documents the auth failure
+/// codes.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            BEARER_AUTH,
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some(
+                        "Access token from signup, login or `POST /auth/refresh`, sent as \
+                         `Authorization: Bearer <token>`. Failures return `401` with an \
+                         `ApiErrorBody` code of `token_missing`, `token_expired`, \
+                         `token_invalid` or `token_revoked` and a `WWW-Authenticate: Bearer` \
+                         challenge; a disabled account returns `403` `account_disabled`, and a \
+                         role that requires MFA without an enrolled second factor returns \
+                         `403` `mfa_required`.",
+                    ))
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            API_KEY_AUTH,
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "Authorization",
+                "Organizer API key from `POST /users/me/api-keys`, sent as \
+                 `Authorization: ApiKey <key>`. Only accepted where listed, and limited to \
+                 the key's scopes (`403` `insufficient_scope`); an unknown, expired or \
+                 revoked key returns `401` `invalid_api_key`.",
+            ))),
+        );
+    }
+}
+
 #[derive(OpenApi)]
 #[openapi(
+    modifiers(&SecurityAddon),
     paths(
         health,
         crate::apps::users::handlers::signup_organizer,
         crate::apps::users::handlers::signup_attendee,
+        crate::apps::users::handlers::login,
+        crate::apps::users::handlers::get_me,
+        crate::apps::users::handlers::update_me,
+        crate::apps::users::handlers::change_password,
+        crate::apps::users::handlers::change_email,
+        crate::apps::auth::handlers::refresh,
+        crate::apps::auth::handlers::logout,
+        crate::apps::auth::handlers::logout_all,
+        crate::apps::auth::handlers::jwks,
+        crate::apps::auth::handlers::forgot_password,
+        crate::apps::auth::handlers::reset_password,
+        crate::apps::auth::handlers::unlock_account,
+        crate::apps::auth::handlers::verify_email,
+        crate::apps::auth::handlers::confirm_email_change,
+        crate::apps::auth::handlers::resend_email_verification,
+        crate::apps::auth::handlers::setup_totp,
+        crate::apps::auth::handlers::confirm_totp,
+        crate::apps::auth::handlers::disable_totp,
+        crate::apps::auth::handlers::verify_mfa,
+        crate::apps::api_keys::handlers::create_api_key,
+        crate::apps::api_keys::handlers::list_api_keys,
+        crate::apps::api_keys::handlers::revoke_api_key,
+        crate::apps::sessions::handlers::list_sessions,
+        crate::apps::sessions::handlers::revoke_session,
+        crate::apps::admin::handlers::list_users,
+        crate::apps::admin::handlers::get_user,
+        crate::apps::admin::handlers::change_role,
+        crate::apps::admin::handlers::disable_user,
+        crate::apps::admin::handlers::enable_user,
+        crate::apps::admin::handlers::force_password_reset,
+        crate::apps::admin::handlers::impersonate_user,
+        crate::apps::admin::handlers::list_pending_deletions,
+        crate::apps::admin::handlers::process_deletions,
+        crate::apps::admin::handlers::list_legal_documents,
+        crate::apps::admin::handlers::publish_legal_document,
+        crate::apps::exports::handlers::export_me,
+        crate::apps::exports::handlers::get_export,
+        crate::apps::exports::handlers::download_export,
+        crate::apps::deletion::handlers::get_deletion,
+        crate::apps::deletion::handlers::request_deletion,
+        crate::apps::deletion::handlers::cancel_deletion,
+        crate::apps::consents::handlers::list_documents,
+        crate::apps::consents::handlers::get_consents,
+        crate::apps::consents::handlers::accept_document,
+        crate::apps::consents::handlers::marketing_opt_in,
+        crate::apps::consents::handlers::marketing_opt_out,
+        crate::apps::addresses::handlers::list_addresses,
+        crate::apps::addresses::handlers::create_address,
+        crate::apps::addresses::handlers::update_address,
+        crate::apps::addresses::handlers::set_default_address,
+        crate::apps::addresses::handlers::delete_address,
+        crate::apps::addresses::handlers::lookup_cep,
     ),
     components(schemas(
         HealthResponse,
+        crate::results::ApiErrorBody,
+        crate::validation::FieldError,
         crate::apps::users::dto::SignupResponse,
+        crate::apps::users::dto::LoginResponse,
+        crate::apps::auth::dto::TokenResponse,
+        crate::apps::auth::dto::TotpSetupResponse,
+        crate::apps::auth::dto::MfaEnabledResponse,
+        crate::apps::auth::dto::MfaChallengeResponse,
+        crate::apps::auth::requests::RefreshRequest,
+        crate::apps::auth::requests::LogoutRequest,
+        crate::apps::auth::requests::ForgotPasswordRequest,
+        crate::apps::auth::requests::ResetPasswordRequest,
+        crate::apps::auth::requests::UnlockAccountRequest,
+        crate::apps::auth::requests::VerifyEmailRequest,
+        crate::apps::auth::requests::ConfirmEmailChangeRequest,
+        crate::apps::auth::requests::ConfirmTotpRequest,
+        crate::apps::auth::requests::VerifyMfaRequest,
+        crate::apps::auth::requests::DisableMfaRequest,
+        crate::apps::api_keys::models::ApiKey,
+        crate::apps::api_keys::models::ApiKeyScope,
+        crate::apps::api_keys::requests::CreateApiKeyRequest,
+        crate::apps::api_keys::dto::CreateApiKeyResponse,
+        crate::apps::api_keys::dto::ListApiKeysResponse,
+        crate::apps::sessions::models::Session,
+        crate::apps::sessions::dto::ListSessionsResponse,
         crate::apps::users::models::User,
         crate::apps::users::models::UserRole,
         crate::apps::users::requests::SignupAttendeeRequest,
         crate::apps::users::requests::SignupOrganizerRequest,
-        crate::apps::users::models::UserAddress,
+        crate::apps::users::requests::LoginRequest,
+        crate::apps::addresses::models::UserAddress,
+        crate::apps::addresses::requests::CreateAddressRequest,
+        crate::apps::addresses::requests::UpdateAddressRequest,
+        crate::apps::addresses::dto::ListAddressesResponse,
+        crate::apps::addresses::lookup::CepInfo,
+        crate::apps::users::models::RelatedData,
+        crate::apps::users::models::OrganizerData,
+        crate::apps::users::models::AttendeeData,
+        crate::apps::users::dto::UserWithRelatedData,
+        crate::apps::users::requests::UpdateMeRequest,
+        crate::apps::users::requests::ChangePasswordRequest,
+        crate::apps::users::requests::ChangeEmailRequest,
+        crate::apps::users::dto::ListUsersResponse,
+        crate::apps::admin::requests::ChangeRoleRequest,
+        crate::apps::admin::dto::ImpersonationResponse,
+        crate::apps::exports::models::UserExport,
+        crate::apps::exports::models::ExportedTicket,
+        crate::apps::exports::models::ExportedSession,
+        crate::apps::exports::models::ExportFormat,
+        crate::apps::exports::models::ExportStatus,
+        crate::apps::exports::models::DataExport,
+        crate::apps::deletion::dto::DeletionStatus,
+        crate::apps::deletion::dto::PendingDeletion,
+        crate::apps::deletion::dto::ListPendingDeletionsResponse,
+        crate::apps::deletion::dto::ProcessDeletionsResponse,
+        crate::apps::deletion::requests::RequestDeletionRequest,
+        crate::apps::consents::models::DocumentKind,
+        crate::apps::consents::models::LegalDocument,
+        crate::apps::consents::models::ConsentKind,
+        crate::apps::consents::models::ConsentRecord,
+        crate::apps::consents::dto::ConsentsResponse,
+        crate::apps::consents::dto::ListDocumentsResponse,
+        crate::apps::consents::requests::AcceptDocumentRequest,
+        crate::apps::consents::requests::PublishDocumentRequest,
     )),
     tags(
         (name = "noxel", description = "Noxel Rust Backend")
@@ -42,9 +200,18 @@ async fn health() -> ApiResult<StatusCode, HealthResponse> {
 )]
 struct ApiDoc;
 
-pub fn router() -> Router<AppState> {
+pub fn router(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/health", get(health))
-        .nest("/users", crate::apps::users::routes::router())
+        .route(
+            "/.well-known/jwks.json",
+            get(crate::apps::auth::handlers::jwks),
+        )
+        .nest("/users", crate::apps::users::routes::router(state.clone()))
+        .nest("/auth", crate::apps::auth::router(state.clone()))
+        .merge(crate::apps::exports::download_router(&state))
+        .merge(crate::apps::consents::public_router())
+        .merge(crate::apps::addresses::public_router(&state))
+        .nest("/admin", crate::apps::admin::router(state))
         .merge(SwaggerUi::new("/swagger-ui").url("/api-doc/openapi.json", ApiDoc::openapi()))
 }
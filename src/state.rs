@@ -1,6 +1,41 @@
+use std::sync::Arc;
+
 use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    apps::{
+        addresses::lookup::CepResolver,
+        auth::{
+            password_policy::PasswordPolicy, revocation::RevocationStore, throttle::LoginThrottle,
+        },
+        exports::config::ExportConfig,
+        users::cache::UserCache,
+    },
+    cache::TtlCache,
+    mailer::Mailer,
+    middleware::{authz::MfaPolicy, jwt_keys::JwtKeys, rate_limit::RateLimitBackend},
+};
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: PgPool,
+    pub jwt: JwtKeys,
+    pub revocations: RevocationStore,
+    pub users: UserCache,
+    /// API keys whose use was recorded recently, to write `last_used_at` at most once per
+    /// `api_keys::API_KEY_USE_INTERVAL`
+    pub api_key_uses: TtlCache<Uuid, ()>,
+    pub login_throttle: LoginThrottle,
+    /// Checked on every new password (signup, reset, change)
+    pub password_policy: PasswordPolicy,
+    /// Token buckets behind the per-route-group `rate_limit` layers
+    pub rate_limiter: Arc<dyn RateLimitBackend>,
+    pub mailer: Arc<dyn Mailer>,
+    /// Roles that must enroll a second factor (enforced by `require_auth`)
+    pub mfa_policy: MfaPolicy,
+    /// Download links and thresholds of personal data exports
+    pub exports: ExportConfig,
+    /// CEP directory used to prefill and check addresses
+    pub cep_resolver: Arc<dyn CepResolver>,
 }
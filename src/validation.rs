@@ -0,0 +1,309 @@
+//! Request body validation.
+//!
+//! Request types implement `Validate` by listing their rules, field by field:
+//!
+//! ```ignore
+//! impl Validate for SignupAttendeeRequest {
+//!     fn validate(&self, v: &mut Validator) {
+//!         v.field("fullName", &self.full_name).required().max_chars(255);
+//!         v.field("phone", &self.phone).required().phone();
+//!         v.date("birthDate", self.birth_date).not_in_future();
+//!         v.nested("address", &self.address);
+//!     }
+//! }
+//! ```
+//!
+//! and handlers take `ValidJson<T>` instead of `Json<T>`. Every failed rule is reported at
+//! once in a `422 validation_failed` response, under `fields`. The same rules are mirrored
+//! in the OpenAPI schema with `#[schema(min_length, max_length, pattern, ...)]`.
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::header,
+};
+use serde::de::DeserializeOwned;
+use utoipa::ToSchema;
+
+use crate::results::ApiError;
+
+/// One failed rule.
+#[derive(Debug, Clone, serde::Serialize, ToSchema)]
+pub struct FieldError {
+    /// JSON path of the field (e.g. `address.estado`)
+    #[schema(example = "address.estado")]
+    pub path: String,
+    /// Stable, machine-readable rule name
+    #[schema(example = "invalid_uf")]
+    pub code: &'static str,
+    #[schema(example = "must be a two-letter uppercase state abbreviation (e.g. SP)")]
+    pub message: String,
+}
+
+/// A request body with validation rules.
+pub trait Validate {
+    fn validate(&self, v: &mut Validator);
+}
+
+/// Collects `FieldError`s while the rules of a request run.
+#[derive(Debug, Default)]
+pub struct Validator {
+    prefix: String,
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    /// Rules for a string field. Absent (`None`) optional fields pass every rule but
+    /// `required`.
+    pub fn field<'a, 'v>(
+        &'v mut self,
+        path: &str,
+        value: impl Into<Option<&'a String>>,
+    ) -> Field<'a, 'v> {
+        Field {
+            path: self.path(path),
+            value: value.into().map(String::as_str),
+            failed: false,
+            validator: self,
+        }
+    }
+
+    /// Rules for a date field.
+    pub fn date<'v>(
+        &'v mut self,
+        path: &str,
+        value: impl Into<Option<chrono::NaiveDate>>,
+    ) -> DateField<'v> {
+        DateField {
+            path: self.path(path),
+            value: value.into(),
+            validator: self,
+        }
+    }
+
+    /// Run the rules of a nested object, with paths under `path`.
+    pub fn nested<'a, T: Validate + 'a>(&mut self, path: &str, value: impl Into<Option<&'a T>>) {
+        let Some(value) = value.into() else {
+            return;
+        };
+        let inner = self.path(path);
+        let outer = std::mem::replace(&mut self.prefix, inner);
+        value.validate(self);
+        self.prefix = outer;
+    }
+
+    fn push(&mut self, path: String, code: &'static str, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            path,
+            code,
+            message: message.into(),
+        });
+    }
+
+    fn path(&self, field: &str) -> String {
+        if self.prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{}", self.prefix, field)
+        }
+    }
+
+    /// Run `value`'s rules; `ApiError::Validation` with every failure, if any.
+    pub fn check<T: Validate>(value: &T) -> Result<(), ApiError> {
+        let mut v = Validator::default();
+        value.validate(&mut v);
+        if v.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::Validation(v.errors))
+        }
+    }
+
+    /// `check`, with the paths of `value`'s fields under `path` (for objects built by the
+    /// handler, e.g. an address patch merged into the stored address).
+    pub fn check_at<T: Validate>(path: &str, value: &T) -> Result<(), ApiError> {
+        let mut v = Validator::default();
+        v.nested(path, value);
+        if v.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::Validation(v.errors))
+        }
+    }
+}
+
+/// Rules on a string field. Rules chain; after the first failure the remaining rules of
+/// the field are skipped, so each field reports at most one error.
+pub struct Field<'a, 'v> {
+    validator: &'v mut Validator,
+    path: String,
+    value: Option<&'a str>,
+    failed: bool,
+}
+
+impl Field<'_, '_> {
+    fn rule(
+        mut self,
+        passes: impl FnOnce(Option<&str>) -> bool,
+        code: &'static str,
+        message: impl Into<String>,
+    ) -> Self {
+        if !self.failed && !passes(self.value) {
+            self.validator.push(self.path.clone(), code, message);
+            self.failed = true;
+        }
+        self
+    }
+
+    /// Present and not blank.
+    pub fn required(self) -> Self {
+        self.rule(
+            |v| v.is_some_and(|v| !v.trim().is_empty()),
+            "required",
+            "must not be empty",
+        )
+    }
+
+    /// Not blank when present (for optional fields of partial updates).
+    pub fn not_blank(self) -> Self {
+        self.rule(
+            |v| v.is_none_or(|v| !v.trim().is_empty()),
+            "required",
+            "must not be empty",
+        )
+    }
+
+    /// At most `max` characters.
+    pub fn max_chars(self, max: usize) -> Self {
+        self.rule(
+            |v| v.is_none_or(|v| v.chars().count() <= max),
+            "too_long",
+            format!("must be at most {max} characters"),
+        )
+    }
+
+    /// Looks like `local@domain.tld`, without spaces.
+    pub fn email(self) -> Self {
+        self.rule(
+            |v| v.is_none_or(|v| is_email(v.trim())),
+            "invalid_email",
+            "must be an email address",
+        )
+    }
+
+    /// Digits with an optional leading `+`, at most 15 characters (`consumer_data.phone`).
+    pub fn phone(self) -> Self {
+        self.rule(
+            |v| v.is_none_or(|v| is_phone(v.trim())),
+            "invalid_phone",
+            "must be digits with an optional leading +, at most 15 characters",
+        )
+    }
+
+    /// Two uppercase letters (`estado`).
+    pub fn uf(self) -> Self {
+        self.rule(
+            |v| v.is_none_or(|v| v.len() == 2 && v.bytes().all(|b| b.is_ascii_uppercase())),
+            "invalid_uf",
+            "must be a two-letter uppercase state abbreviation (e.g. SP)",
+        )
+    }
+}
+
+/// Rules on a date field.
+pub struct DateField<'v> {
+    validator: &'v mut Validator,
+    path: String,
+    value: Option<chrono::NaiveDate>,
+}
+
+impl DateField<'_> {
+    /// Today or earlier (UTC).
+    pub fn not_in_future(self) -> Self {
+        if self
+            .value
+            .is_some_and(|d| d > chrono::Utc::now().date_naive())
+        {
+            self.validator
+                .push(self.path.clone(), "in_future", "must not be in the future");
+        }
+        self
+    }
+}
+
+fn is_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !email.contains(char::is_whitespace)
+}
+
+fn is_phone(phone: &str) -> bool {
+    let digits = phone.strip_prefix('+').unwrap_or(phone);
+    phone.len() <= 15 && !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// JSON body extractor that runs the body's `Validate` rules.
+///
+/// Malformed JSON is a `400 bad_request`; a body of the wrong shape (missing field, wrong
+/// type, invalid CPF/CNPJ, ...) and failed rules are a `422 validation_failed` with the
+/// offending fields.
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for ValidJson<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_json = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.starts_with("application/json") || ct.contains("+json"));
+        if !is_json {
+            return Err(ApiError::BadRequest(
+                "expected a JSON body (Content-Type: application/json)".to_string(),
+            ));
+        }
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|e| ApiError::BadRequest(e.body_text()))?;
+
+        let deserializer = &mut serde_json::Deserializer::from_slice(&bytes);
+        let value: T = serde_path_to_error::deserialize(&mut *deserializer).map_err(|e| {
+            let path = e.path().to_string();
+            let inner = e.into_inner();
+            if inner.is_syntax() || inner.is_eof() {
+                return ApiError::BadRequest(format!("malformed JSON: {inner}"));
+            }
+            // serde_json appends " at line X column Y"; the path says it better.
+            let message = inner.to_string();
+            let message = message
+                .rsplit_once(" at line ")
+                .map_or(message.as_str(), |(m, _)| m)
+                .to_string();
+            ApiError::Validation(vec![FieldError {
+                path: if path == "." { String::new() } else { path },
+                code: "invalid",
+                message,
+            }])
+        })?;
+        // Trailing characters after the value (`{...} garbage`).
+        deserializer
+            .end()
+            .map_err(|e| ApiError::BadRequest(format!("malformed JSON: {e}")))?;
+
+        Validator::check(&value)?;
+        Ok(ValidJson(value))
+    }
+}